@@ -245,6 +245,8 @@ pub fn simple_test_stark<const COL_NUM: usize, E, H>(
         prophets,
         pre_exe_flag: false,
         print_flag: false,
+        breakpoints: Default::default(),
+        prophet_mock: None,
     };
 
     for inst in instructions {
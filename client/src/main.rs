@@ -186,6 +186,8 @@ fn main() {
                 prophets: HashMap::new(),
                 pre_exe_flag: false,
                 print_flag: false,
+                breakpoints: Default::default(),
+                prophet_mock: None,
             };
 
             let inputs = GenerationInputs::default();
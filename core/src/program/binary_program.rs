@@ -522,6 +522,53 @@ pub struct OlaProphet {
     pub outputs: Vec<OlaProphetOutput>,
 }
 
+/// Values used in place of evaluating prophets, keyed by the names of the
+/// prophet inputs and outputs. Every prophet declaring an output gets the
+/// mocked value of that name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProphetMock {
+    #[serde(default)]
+    pub inputs: BTreeMap<String, Vec<u64>>,
+    #[serde(default)]
+    pub outputs: BTreeMap<String, Vec<u64>>,
+}
+
+impl ProphetMock {
+    /// Checks the mocked values against the inputs and outputs `prophet`
+    /// declares and returns its output values in declaration order.
+    pub fn outputs_for(&self, prophet: &OlaProphet) -> Result<Vec<u64>, String> {
+        for input in prophet.inputs.iter() {
+            if let Some(values) = self.inputs.get(&input.name) {
+                if values.len() != input.length {
+                    return Err(format!(
+                        "prophet input '{}' has length {}, got {} mocked values",
+                        input.name,
+                        input.length,
+                        values.len()
+                    ));
+                }
+            }
+        }
+        let mut outputs = Vec::new();
+        for output in prophet.outputs.iter() {
+            let values = self
+                .outputs
+                .get(&output.name)
+                .ok_or(format!("prophet output '{}' is not mocked", output.name))?;
+            if values.len() != output.length {
+                return Err(format!(
+                    "prophet output '{}' has length {}, got {} mocked values",
+                    output.name,
+                    output.length,
+                    values.len()
+                ));
+            }
+            outputs.extend(values);
+        }
+        Ok(outputs)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OlaProphetInput {
     pub name: String,
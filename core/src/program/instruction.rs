@@ -54,6 +54,7 @@ pub enum Opcode {
     TSTORE = 8,
     SCCALL = 7,
     SIGCHECK = 6,
+    EVENT = 5,
 }
 
 impl fmt::Display for Opcode {
@@ -85,6 +86,7 @@ impl fmt::Display for Opcode {
             Opcode::TSTORE => write!(f, "tstore"),
             Opcode::SCCALL => write!(f, "sccall"),
             Opcode::SIGCHECK => write!(f, "sigcheck"),
+            Opcode::EVENT => write!(f, "log"),
         }
     }
 }
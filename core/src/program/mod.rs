@@ -1,9 +1,9 @@
-use crate::program::binary_program::OlaProphet;
+use crate::program::binary_program::{OlaProphet, ProphetMock};
 use crate::trace::trace::Trace;
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field64;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 pub mod binary_program;
 pub mod decoder;
@@ -23,6 +23,13 @@ pub struct Program {
     pub print_flag: bool,
     pub prophets: HashMap<u64, OlaProphet>,
     pub pre_exe_flag: bool,
+    /// Pcs at which `Process::execute` pauses before running the instruction.
+    #[serde(default)]
+    pub breakpoints: BTreeSet<u64>,
+    /// Values the prophets of the program evaluate to, instead of running
+    /// their code.
+    #[serde(default)]
+    pub prophet_mock: Option<ProphetMock>,
 }
 
 impl Program {}
@@ -16,6 +16,9 @@ pub enum ProcessorError {
     #[error("Interpreter run error: {0}")]
     InterpreterRunError(String),
 
+    #[error("Prophet mock error: {0}")]
+    ProphetMockError(String),
+
     #[error("U32 range check fail, value out range")]
     U32RangeCheckFail,
 
@@ -22,14 +22,52 @@ pub struct MemoryCell {
     pub value: GoldilocksField,
 }
 
+/// Memory use of an execution: the highest stack address and heap offset
+/// written, and the number of distinct addresses accessed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryFootprint {
+    pub max_stack_addr: Option<u64>,
+    pub max_heap_offset: Option<u64>,
+    pub slots_touched: usize,
+}
+
+impl MemoryFootprint {
+    /// Combines the footprints of two executions, such as a caller and a
+    /// callee running in separate memories.
+    pub fn merge(&mut self, other: &MemoryFootprint) {
+        self.max_stack_addr = self.max_stack_addr.max(other.max_stack_addr);
+        self.max_heap_offset = self.max_heap_offset.max(other.max_heap_offset);
+        self.slots_touched += other.slots_touched;
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct MemoryTree {
     // visit by memory address, MemoryCell vector store memory trace value， the last one is the
     // current status
     pub trace: BTreeMap<u64, Vec<MemoryCell>>,
+    // high-water marks of the stack and heap writes
+    pub max_stack_addr: Option<u64>,
+    pub max_heap_offset: Option<u64>,
 }
 
 impl MemoryTree {
+    /// Current value at `addr` without recording a read in the trace.
+    pub fn peek(&self, addr: u64) -> Option<GoldilocksField> {
+        self.trace
+            .get(&addr)
+            .and_then(|cells| cells.last())
+            .map(|cell| cell.value)
+    }
+
+    pub fn footprint(&self) -> MemoryFootprint {
+        MemoryFootprint {
+            max_stack_addr: self.max_stack_addr,
+            max_heap_offset: self.max_heap_offset,
+            slots_touched: self.trace.len(),
+        }
+    }
+
     pub fn read(
         &mut self,
         addr: u64,
@@ -100,6 +138,11 @@ impl MemoryTree {
             .entry(addr)
             .and_modify(|addr_trace| addr_trace.push(new_cell))
             .or_insert_with(|| vec![new_cell]);
+        if addr < HP_START_ADDR {
+            self.max_stack_addr = self.max_stack_addr.max(Some(addr));
+        } else if addr < PSP_START_ADDR {
+            self.max_heap_offset = self.max_heap_offset.max(Some(addr - HP_START_ADDR));
+        }
     }
 }
 
@@ -115,3 +158,52 @@ macro_rules! memory_zone_process {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::{MemoryFootprint, MemoryTree, HP_START_ADDR};
+
+    #[test]
+    fn test_array_allocation_footprint() {
+        let mut memory = MemoryTree::default();
+        let mut write = |addr: u64, value: u64| {
+            memory.write(
+                addr,
+                0,
+                GoldilocksField::ZERO,
+                GoldilocksField::ONE,
+                GoldilocksField::ONE,
+                GoldilocksField::ZERO,
+                GoldilocksField::ZERO,
+                GoldilocksField::ZERO,
+                GoldilocksField::from_canonical_u64(value),
+                GoldilocksField::ZERO,
+            )
+        };
+        // A 5 element array on the heap, its pointer stored at stack slot 2.
+        for index in 0..5 {
+            write(HP_START_ADDR + index, index);
+        }
+        write(2, HP_START_ADDR);
+        write(2, HP_START_ADDR);
+
+        let footprint = memory.footprint();
+        assert_eq!(
+            footprint,
+            MemoryFootprint {
+                max_stack_addr: Some(2),
+                max_heap_offset: Some(4),
+                slots_touched: 6,
+            }
+        );
+
+        let mut total = MemoryFootprint::default();
+        total.merge(&footprint);
+        total.merge(&footprint);
+        assert_eq!(total.slots_touched, 12);
+        assert_eq!(total.max_heap_offset, Some(4));
+    }
+}
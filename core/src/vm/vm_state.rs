@@ -14,6 +14,8 @@ pub enum SCCallType {
 pub enum VMState {
     ExeEnd(Option<Step>),
     SCCall(SCCallType),
+    /// Execution paused before the instruction at this pc.
+    Breakpoint(u64),
 }
 
 #[derive(Debug, Clone)]
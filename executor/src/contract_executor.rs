@@ -1698,7 +1698,7 @@ impl OlaContractExecutor {
         cloned.ctx.push(("_heap_ptr".to_string(), self.memory.hp()));
         let out = interpreter
             .run(&cloned, flatten_inputs, &self.memory)
-            .map_err(|err| ProcessorError::InterpreterRunError(err))?;
+            .map_err(|err| ProcessorError::InterpreterRunError(err.to_string()))?;
         let mut exe_diffs: Vec<MemoryDiff> = vec![];
         let mut trace_diffs: Vec<MemExePiece> = vec![];
         match out {
@@ -75,7 +75,12 @@ pub fn decode_raw_instruction(
                     instruction += &reg2_name;
                 }
             }
-            Opcode::CJMP | Opcode::TSTORE | Opcode::SCCALL | Opcode::SLOAD | Opcode::SSTORE => {
+            Opcode::CJMP
+            | Opcode::TSTORE
+            | Opcode::SCCALL
+            | Opcode::SLOAD
+            | Opcode::SSTORE
+            | Opcode::EVENT => {
                 instruction += &op_code.to_string();
                 instruction += " ";
                 let reg1_name = format!("r{}", reg1);
@@ -225,3 +230,10 @@ fn decode_raw_instruction_test() {
     let inst_str = decode_raw_instruction(&inst_str, imm);
     println!("inst_str: {:?}", inst_str);
 }
+
+#[test]
+fn decode_log_instruction_test() {
+    // `log r6 r5` as the assembler encodes it in erc20_bin.json.
+    let inst_str = decode_raw_instruction("0x0400800000000020", "0x0").unwrap();
+    assert_eq!(inst_str, ("log r6 r5".to_string(), NO_IMM_INSTRUCTION_LEN));
+}
@@ -5,6 +5,7 @@ use crate::storage::StorageTree;
 use core::state::state_storage::StateStorage;
 use core::vm::error::ProcessorError;
 use core::vm::memory::{MemoryTree, HP_START_ADDR, PSP_START_ADDR};
+use core::vm::types::Event;
 
 use core::merkle_tree::log::{StorageLog, StorageQuery};
 use core::merkle_tree::log::{StorageLogKind, WitnessStorageLog};
@@ -252,7 +253,11 @@ pub struct Process {
     pub storage_access_idx: GoldilocksField,
     pub storage_queries: Vec<StorageQuery>,
     pub return_data: Vec<GoldilocksField>,
+    // events logged by the contract, not yet collected by the vm
+    pub events: Vec<Event>,
     pub is_call: bool,
+    // set while stopped at a breakpoint, so resuming runs the instruction at pc
+    pub paused: bool,
 }
 
 impl Process {
@@ -271,9 +276,7 @@ impl Process {
             immediate_data: Default::default(),
             opcode: Default::default(),
             op1_imm: Default::default(),
-            memory: MemoryTree {
-                trace: BTreeMap::new(),
-            },
+            memory: MemoryTree::default(),
             psp: GoldilocksField(PSP_START_ADDR),
             psp_start: GoldilocksField(PSP_START_ADDR),
             hp: GoldilocksField(HP_START_ADDR),
@@ -289,7 +292,9 @@ impl Process {
             storage_access_idx: GoldilocksField::ZERO,
             storage_queries: Vec::new(),
             return_data: Vec::new(),
+            events: Vec::new(),
             is_call: false,
+            paused: false,
         }
     }
 
@@ -463,6 +468,27 @@ impl Process {
         Ok(())
     }
 
+    /// Writes prophet output values to the prophet memory, where the
+    /// instructions following the prophet read them from.
+    pub fn write_prophet_outputs(&mut self, values: Vec<u64>) {
+        self.psp_start = self.psp;
+        for value in values {
+            self.memory.write(
+                self.psp.0,
+                0, //write， clk is 0
+                GoldilocksField::from_canonical_u64(0 as u64),
+                GoldilocksField::from_canonical_u64(MemoryType::WriteOnce as u64),
+                GoldilocksField::from_canonical_u64(MemoryOperation::Write as u64),
+                GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
+                GoldilocksField::from_canonical_u64(1_u64),
+                GoldilocksField::from_canonical_u64(0_u64),
+                GoldilocksField(value),
+                self.env_idx,
+            );
+            self.psp += GoldilocksField::ONE;
+        }
+    }
+
     fn print_vm_state(&mut self, instruction: &str) {
         println!(
             "↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓ tp:{}, clk: {}, pc: {}, instruction: {} ↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓",
@@ -1279,6 +1305,63 @@ impl Process {
         Ok(end_step)
     }
 
+    fn execute_inst_log(&mut self, ops: &[&str], step: u64) -> Result<(), ProcessorError> {
+        if self.is_call {
+            return Err(ProcessorError::EventOnCallError);
+        }
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::EVENT as u8);
+        let op0_index = self.get_reg_index(ops[1]);
+        let value = self.get_index_value(ops[2])?;
+
+        self.register_selector.op0 = self.registers[op0_index];
+        self.register_selector.op1 = value.0;
+        self.register_selector.op0_reg_sel[op0_index] = GoldilocksField::from_canonical_u64(1);
+        if let ImmediateOrRegName::RegName(op1_index) = value.1 {
+            self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        }
+
+        // op0 points at the topic count followed by the address of each
+        // topic, op1 at the data length followed by the data.
+        let topics_addr = self.registers[op0_index].to_canonical_u64();
+        let topic_len;
+        memory_op!(self, topics_addr, topic_len, Opcode::EVENT);
+        let mut topics = Vec::new();
+        for index in 1..=topic_len.to_canonical_u64() {
+            let mut mem_addr = topics_addr + index;
+            let topic_addr;
+            memory_op!(self, mem_addr, topic_addr, Opcode::EVENT);
+            let mut topic = [0; TREE_VALUE_LEN];
+            for (offset, word) in topic.iter_mut().enumerate() {
+                mem_addr = topic_addr.to_canonical_u64() + offset as u64;
+                let topic_word;
+                memory_op!(self, mem_addr, topic_word, Opcode::EVENT);
+                *word = topic_word.to_canonical_u64();
+            }
+            topics.push(topic);
+        }
+
+        let data_addr = value.0.to_canonical_u64();
+        let data_len;
+        memory_op!(self, data_addr, data_len, Opcode::EVENT);
+        let mut data = Vec::new();
+        for index in 1..=data_len.to_canonical_u64() {
+            let mem_addr = data_addr + index;
+            let data_word;
+            memory_op!(self, mem_addr, data_word, Opcode::EVENT);
+            data.push(data_word.to_canonical_u64());
+        }
+
+        self.events.push(Event {
+            batch_number: 0,
+            index_in_batch: 0,
+            address: self.addr_storage.map(|fe| fe.to_canonical_u64()),
+            topics,
+            data,
+        });
+        self.pc += step;
+        Ok(())
+    }
+
     fn execute_inst_sstore(
         &mut self,
         program: &mut Program,
@@ -2135,25 +2218,32 @@ impl Process {
         // todo : why need clear?
         //self.storage_log.clear();
         let mut end_step = None;
-        let mut prog_hash_rows = calculate_arbitrary_poseidon_and_generate_intermediate_trace(
-            program
-                .instructions
-                .iter()
-                .map(|insts_str| {
-                    let inst = u64::from_str_radix(insts_str.trim_start_matches("0x"), 16)
-                        .map_err(|_| ProcessorError::ParseIntError)?;
-                    Ok(GoldilocksField::from_canonical_u64(inst))
-                })
-                .collect::<Result<Vec<_>, ProcessorError>>()?
-                .as_slice(),
-        )
-        .1;
-        for row in &mut prog_hash_rows {
-            row.filter_looked_normal = true;
+        if !self.paused {
+            let mut prog_hash_rows = calculate_arbitrary_poseidon_and_generate_intermediate_trace(
+                program
+                    .instructions
+                    .iter()
+                    .map(|insts_str| {
+                        let inst = u64::from_str_radix(insts_str.trim_start_matches("0x"), 16)
+                            .map_err(|_| ProcessorError::ParseIntError)?;
+                        Ok(GoldilocksField::from_canonical_u64(inst))
+                    })
+                    .collect::<Result<Vec<_>, ProcessorError>>()?
+                    .as_slice(),
+            )
+            .1;
+            for row in &mut prog_hash_rows {
+                row.filter_looked_normal = true;
+            }
+            program.trace.builtin_poseidon.extend(prog_hash_rows);
         }
-        program.trace.builtin_poseidon.extend(prog_hash_rows);
 
         loop {
+            if !self.paused && program.breakpoints.contains(&self.pc) {
+                self.paused = true;
+                return Ok(VMState::Breakpoint(self.pc));
+            }
+            self.paused = false;
             self.register_selector = RegisterSelector::default();
             let registers_status = self.registers;
             let ctx_regs_status = self.addr_storage.clone();
@@ -2235,6 +2325,7 @@ impl Process {
                     &ctx_code_regs_status,
                 )?,
                 "poseidon" => self.execute_inst_poseidon(program, &ops, step)?,
+                "log" => self.execute_inst_log(&ops, step)?,
                 "tload" => self.execute_inst_tload(
                     program,
                     &mut aux_steps,
@@ -2275,8 +2366,16 @@ impl Process {
                 _ => return Err(ProcessorError::ParseOpcodeError),
             }
 
-            if program.prophets.get(&pc_status).is_some() {
-                self.prophet(&mut program.prophets[&pc_status].clone())?
+            if let Some(prophet) = program.prophets.get(&pc_status) {
+                match &program.prophet_mock {
+                    Some(mock) => {
+                        let values = mock
+                            .outputs_for(prophet)
+                            .map_err(ProcessorError::ProphetMockError)?;
+                        self.write_prophet_outputs(values);
+                    }
+                    None => self.prophet(&mut prophet.clone())?,
+                }
             }
 
             if program.print_flag {
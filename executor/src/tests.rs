@@ -7,7 +7,7 @@ use core::crypto::ZkHasher;
 use core::merkle_tree::log::StorageLog;
 use core::merkle_tree::log::WitnessStorageLog;
 use core::merkle_tree::tree::AccountTree;
-use core::program::binary_program::BinaryProgram;
+use core::program::binary_program::{BinaryProgram, OlaProphet, ProphetMock};
 use core::program::instruction::Opcode;
 use core::program::Program;
 use core::state::state_storage::StateStorage;
@@ -57,6 +57,8 @@ fn executor_run_test_program(
         prophets: prophets,
         pre_exe_flag: false,
         print_flag: false,
+        breakpoints: Default::default(),
+        prophet_mock: None,
     };
 
     for inst in instructions {
@@ -245,6 +247,38 @@ fn prophet_sqrt_test() {
     );
 }
 
+#[test]
+fn prophet_mock_test() {
+    let prophet: OlaProphet = serde_json::from_str(
+        r#"{
+            "host": 3,
+            "code": "%{ entry() { cid.y = sqrt(cid.x); } %}",
+            "inputs": [{ "name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false }],
+            "outputs": [{ "name": "cid.y", "length": 1, "is_ref": false, "is_input_output": false }]
+        }"#,
+    )
+    .unwrap();
+    let mock: ProphetMock =
+        serde_json::from_str(r#"{ "inputs": { "cid.x": [16] }, "outputs": { "cid.y": [4] } }"#)
+            .unwrap();
+    let values = mock.outputs_for(&prophet).unwrap();
+    assert_eq!(values, vec![4]);
+
+    let mut process = Process::new();
+    let psp = process.psp;
+    process.write_prophet_outputs(values);
+    assert_eq!(process.psp_start, psp);
+    assert_eq!(
+        process.memory.peek(psp.0),
+        Some(GoldilocksField::from_canonical_u64(4))
+    );
+
+    let wrong_length: ProphetMock =
+        serde_json::from_str(r#"{ "outputs": { "cid.y": [4, 5] } }"#).unwrap();
+    assert!(wrong_length.outputs_for(&prophet).is_err());
+    assert!(ProphetMock::default().outputs_for(&prophet).is_err());
+}
+
 #[test]
 fn storage_test() {
     executor_run_test_program(
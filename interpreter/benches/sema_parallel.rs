@@ -0,0 +1,53 @@
+use core::program::binary_program::OlaProphet;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use interpreter::parser::Parser;
+use interpreter::sema::{SemaOptions, SymTableGen};
+
+fn gen_program(functions: usize, statements: usize) -> String {
+    let mut code = String::new();
+    for index in 0..functions {
+        code += &format!("function f{}(felt a) -> felt {{ felt b; b = a;", index);
+        for _ in 0..statements {
+            code += " b = b * 3 + a;";
+        }
+        code += " return b; }\n";
+    }
+    code += "entry() { }";
+    code
+}
+
+fn sema_parallel_benchmark(c: &mut Criterion) {
+    let prophet = OlaProphet {
+        host: 0,
+        code: String::new(),
+        ctx: Vec::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+    };
+    let root = Parser::new(&gen_program(64, 2000)).parse();
+    let mut group = c.benchmark_group("sema_parallel");
+    for parallel in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(parallel),
+            &parallel,
+            |b, parallel| {
+                b.iter(|| {
+                    let options = SemaOptions {
+                        parallel_functions: *parallel,
+                        ..Default::default()
+                    };
+                    let mut gen = SymTableGen::new_with_options(&prophet, options);
+                    root.write().unwrap().traverse(&mut gen).unwrap();
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = sema_parallel_benchmark
+];
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+use core::program::binary_program::OlaProphet;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use interpreter::parser::Parser;
+use interpreter::sema::{SemaOptions, SymTableGen};
+
+// Functions whose bodies mostly read and write globals, each name being
+// looked up through the function and global scopes.
+fn gen_program(globals: usize, functions: usize, statements: usize) -> String {
+    let mut code = String::new();
+    for index in 0..globals {
+        code += &format!("felt g{};\n", index);
+    }
+    for index in 0..functions {
+        code += &format!("function f{}(felt a) -> felt {{ felt b; b = a;", index);
+        for statement in 0..statements {
+            let global = statement % globals;
+            code += &format!(" g{} = g{} * 3 + b;", global, global);
+        }
+        code += " return b; }\n";
+    }
+    code += "entry() { }";
+    code
+}
+
+fn sema_resolution_benchmark(c: &mut Criterion) {
+    let prophet = OlaProphet {
+        host: 0,
+        code: String::new(),
+        ctx: Vec::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+    };
+    let root = Parser::new(&gen_program(32, 8, 2000)).parse();
+    let mut group = c.benchmark_group("sema_resolution");
+    for cache in [false, true] {
+        group.bench_with_input(BenchmarkId::from_parameter(cache), &cache, |b, cache| {
+            b.iter(|| {
+                let options = SemaOptions {
+                    resolution_cache: *cache,
+                    ..Default::default()
+                };
+                let mut gen = SymTableGen::new_with_options(&prophet, options);
+                root.write().unwrap().traverse(&mut gen).unwrap();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = sema_resolution_benchmark
+];
+criterion_main!(benches);
@@ -7,16 +7,18 @@ use std::ops::Deref;
 use crate::lexer::token::Token;
 use crate::lexer::token::Token::{Array, ArrayId, Cid, Id, IndexId};
 use crate::parser::node::{
-    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode,
-    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode,
-    IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode,
-    MultiAssignNode, PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, BreakNode, CallNode,
+    CompoundNode, CondStatNode, ContextIdentNode, ContinueNode, EntryBlockNode, EntryNode,
+    FeltNumNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode,
+    InvariantNode, LoopStatNode, MallocNode, MultiAssignNode, PrintfNode, ReturnNode, SqrtNode,
+    TernaryNode, TypeNode, UnaryOpNode, WhileStatNode,
 };
 use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
+use crate::sema::error::SemaError;
 use crate::sema::symbol::Symbol::FuncSymbol;
 use crate::utils::number::Number::{Bool, Nil};
 use crate::utils::number::NumberRet::{Multiple, Single};
-use crate::utils::number::{Number, NumberResult, NumberRet};
+use crate::utils::number::{Number, NumberResult, NumberRet, OverflowMode};
 use log::debug;
 
 #[macro_export]
@@ -104,6 +106,9 @@ pub struct Executor<'a> {
     // pub vm_mem: &'a MemoryTree,
     mem: &'a OlaMemory,
     stack_depth: usize,
+    overflow_mode: OverflowMode,
+    // `break` or `continue` reached and not yet handled by its loop.
+    jump: Option<Token>,
 }
 
 impl<'a> Executor<'a> {
@@ -114,6 +119,8 @@ impl<'a> Executor<'a> {
             outputs: Vec::new(),
             mem,
             stack_depth: GLOBAL_LEVEL,
+            overflow_mode: OverflowMode::default(),
+            jump: None,
         };
         executor.call_stack.records.push(RuntimeRecord::new(
             "global".to_string(),
@@ -163,6 +170,12 @@ impl<'a> Executor<'a> {
         executor
     }
 
+    /// Sets how overflowing i32 arithmetic behaves, checked by default.
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
     pub fn assign_value(&mut self, id: &Token, value: NumberRet) -> NumberResult {
         match id {
             Id(name) | Cid(name) => {
@@ -337,7 +350,7 @@ impl<'a> Traversal for Executor<'a> {
         );
 
         let mut ret = Ok(Single(Nil));
-        if let FuncSymbol(_func_name, ref params, block) =
+        if let FuncSymbol(_func_name, ref params, _, _, block) =
             node.func_symbol.clone().unwrap().read().unwrap().deref()
         {
             for (param, input) in params.iter().zip(node.actual_params.iter()) {
@@ -383,34 +396,36 @@ impl<'a> Traversal for Executor<'a> {
 
     fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult {
         let IdentDeclarationNode {
-            ident_node: IdentNode { identifier },
+            ident_node: IdentNode { identifier, .. },
             type_node: TypeNode { token },
+            ..
         } = node;
 
-        if let Array(_element_type, len) = token {
+        if let Array(element_type, len) = token {
+            // Multi-dimensional arrays are stored row after row.
+            let mut size = *len;
+            let mut element = element_type.as_ref();
+            while let Array(inner, inner_len) = element {
+                size *= inner_len;
+                element = inner;
+            }
             if let Id(name) = identifier {
                 if self.call_stack.records[self.stack_depth]
                     .array_idents
                     .get(name)
                     != None
                 {
-                    return Err(format!(
-                        "Found duplicate variable declaration for '{}'!",
-                        name
-                    ));
+                    return Err(SemaError::DuplicateDeclaration { name: name.clone() });
                 }
                 self.call_stack.records[self.stack_depth]
                     .array_idents
-                    .insert(name.to_string(), Some(vec![Nil; *len]));
+                    .insert(name.to_string(), Some(vec![Nil; size]));
             } else {
                 panic!("cannot get id name");
             }
         } else if let Id(name) = identifier {
             if self.call_stack.records[self.stack_depth].idents.get(name) != None {
-                return Err(format!(
-                    "Found duplicate variable declaration for '{}'!",
-                    name
-                ));
+                return Err(SemaError::DuplicateDeclaration { name: name.clone() });
             }
             self.call_stack.records[self.stack_depth]
                 .idents
@@ -449,13 +464,14 @@ impl<'a> Traversal for Executor<'a> {
         if let IdentIndexNode {
             identifier: Id(name),
             index,
+            ..
         } = node
         {
             let value = self.travel(index)?;
             debug!("ident:{},{:?}", name, value);
             self.index_lookup(name, value.get_single().get_number())
         } else {
-            Err(format!("Invalid identifier found {}", node.identifier))
+            Err(format!("Invalid identifier found {}", node.identifier).into())
         }
     }
 
@@ -467,8 +483,26 @@ impl<'a> Traversal for Executor<'a> {
         } = node;
 
         let lhs = self.travel(left)?.get_single();
+        // `&&` and `||` short-circuit, the right operand only runs when the
+        // left one does not decide the result.
+        match (&*operator, &lhs) {
+            (Token::And, Bool(false)) | (Token::Or, Bool(true)) => return Ok(Single(lhs)),
+            _ => {}
+        }
         let rhs = self.travel(right)?.get_single();
 
+        if let (Number::I32(l), Number::I32(r)) = (&lhs, &rhs) {
+            if matches!(
+                operator,
+                Token::Plus | Token::Multiply | Token::Minus | Token::IntegerDivision | Token::Mod
+            ) {
+                return match self.overflow_mode.i32_binop(*l, *r, operator) {
+                    Some(value) => Ok(Single(Number::I32(value))),
+                    None => Err(format!("i32 overflow in {} {} {}", l, operator, r).into()),
+                };
+            }
+        }
+
         let ret = match operator {
             Token::Plus => lhs + rhs,
             Token::Multiply => lhs * rhs,
@@ -488,6 +522,14 @@ impl<'a> Traversal for Executor<'a> {
         return Ok(Single(ret));
     }
 
+    fn travel_ternary(&mut self, node: &mut TernaryNode) -> NumberResult {
+        match self.travel(&node.condition)?.get_single() {
+            Bool(true) => self.travel(&node.then_expr),
+            Bool(false) => self.travel(&node.else_expr),
+            condition => Err(format!("Invalid ternary condition found {:?}", condition).into()),
+        }
+    }
+
     fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
         let UnaryOpNode { operator, expr } = node;
         match operator {
@@ -496,14 +538,14 @@ impl<'a> Traversal for Executor<'a> {
                 let ret = self.travel(expr)?.get_single();
                 Ok(Single(-ret))
             }
-            _ => Err(format!("Unexpected Unary Operator found: {}", operator)),
+            _ => Err(format!("Unexpected Unary Operator found: {}", operator).into()),
         }
     }
 
     fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult {
         for child in node.children.iter() {
             let ret = self.travel(child)?;
-            if self.is_return(&ret) {
+            if self.is_return(&ret) || self.jump.is_some() {
                 return Ok(ret);
             }
         }
@@ -520,50 +562,49 @@ impl<'a> Traversal for Executor<'a> {
     fn travel_ident(&mut self, node: &mut IdentNode) -> NumberResult {
         if let IdentNode {
             identifier: Id(name),
+            ..
         } = node
         {
             self.lookup(name)
         } else if let IdentNode {
             identifier: ArrayId(name),
+            ..
         } = node
         {
             self.array_lookup(name)
         } else {
-            Err(format!("Invalid identifier found {}", node.identifier))
+            Err(format!("Invalid identifier found {}", node.identifier).into())
         }
     }
 
     fn travel_context_ident(&mut self, node: &mut ContextIdentNode) -> NumberResult {
         if let ContextIdentNode {
             identifier: Cid(name),
+            ..
         } = node
         {
             self.lookup(name)
         } else {
-            Err(format!(
-                "Invalid context identifier found {}",
-                node.identifier
-            ))
+            Err(format!("Invalid context identifier found {}", node.identifier).into())
         }
     }
 
     fn travel_cond(&mut self, node: &mut CondStatNode) -> NumberResult {
-        let res = self.travel(&node.condition)?;
-        if let Single(Bool(flag)) = res {
-            if flag == true {
-                for child in node.consequences.iter() {
-                    let ret = self.travel(child)?;
-                    if self.is_return(&ret) {
-                        return Ok(ret);
-                    }
-                }
-            } else {
-                for child in node.alternatives.iter() {
-                    let ret = self.travel(child)?;
-                    if self.is_return(&ret) {
-                        return Ok(ret);
-                    }
+        let mut taken = None;
+        for (condition, consequences) in node.branches() {
+            match self.travel(condition)? {
+                Single(Bool(true)) => {
+                    taken = Some(consequences);
+                    break;
                 }
+                Single(Bool(false)) => {}
+                _ => return Ok(Single(Nil)),
+            }
+        }
+        for child in taken.unwrap_or(&node.alternatives).iter() {
+            let ret = self.travel(child)?;
+            if self.is_return(&ret) || self.jump.is_some() {
+                return Ok(ret);
             }
         }
         Ok(Single(Nil))
@@ -572,6 +613,9 @@ impl<'a> Traversal for Executor<'a> {
     fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
         let mut res = self.travel(&node.condition);
         while let Ok(Single(cond)) = res {
+            if let Some(invariant) = &node.invariant {
+                self.travel(invariant)?;
+            }
             if let Bool(flag) = cond {
                 if flag == true {
                     for child in node.consequences.iter() {
@@ -579,6 +623,12 @@ impl<'a> Traversal for Executor<'a> {
                         if self.is_return(&ret) {
                             return Ok(ret);
                         }
+                        if self.jump.is_some() {
+                            break;
+                        }
+                    }
+                    if self.jump.take() == Some(Token::Break) {
+                        break;
                     }
                 } else {
                     break;
@@ -589,6 +639,45 @@ impl<'a> Traversal for Executor<'a> {
         Ok(Single(Nil))
     }
 
+    fn travel_while(&mut self, node: &mut WhileStatNode) -> NumberResult {
+        while let Single(Bool(true)) = self.travel(&node.condition)? {
+            for child in node.consequences.iter() {
+                let ret = self.travel(child)?;
+                if self.is_return(&ret) {
+                    return Ok(ret);
+                }
+                if self.jump.is_some() {
+                    break;
+                }
+            }
+            if self.jump.take() == Some(Token::Break) {
+                break;
+            }
+        }
+        Ok(Single(Nil))
+    }
+
+    fn travel_break(&mut self, _node: &mut BreakNode) -> NumberResult {
+        self.jump = Some(Token::Break);
+        Ok(Single(Nil))
+    }
+
+    fn travel_continue(&mut self, _node: &mut ContinueNode) -> NumberResult {
+        self.jump = Some(Token::Continue);
+        Ok(Single(Nil))
+    }
+
+    fn travel_invariant(&mut self, node: &mut InvariantNode) -> NumberResult {
+        match self.travel(&node.condition)? {
+            Single(Bool(false)) => Err(format!(
+                "loop invariant at {}:{} does not hold",
+                node.span.0, node.span.1
+            )
+            .into()),
+            _ => Ok(Single(Nil)),
+        }
+    }
+
     fn travel_function(&mut self, _node: &mut FunctionNode) -> NumberResult {
         Ok(Single(Nil))
     }
@@ -670,7 +759,14 @@ impl<'a> Traversal for Executor<'a> {
     }
 
     fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult {
-        let flag_ret = self.travel(&node.flag)?.get_single().get_number();
+        let flag = self.travel(&node.flag)?.get_single();
+        // A format string prints the value in place of every `{}`.
+        if let Number::Str(format) = flag {
+            let value = self.travel(&node.val_addr)?.get_single().get_number();
+            println!("{}", format.replace("{}", &value.to_string()));
+            return Ok(Single(Nil));
+        }
+        let flag_ret = flag.get_number();
         if flag_ret == 4 {
             let addr = self.travel(&node.val_addr)?.get_single().get_number() as u64;
             let limbs = self.mem.batch_read(addr, 8).unwrap();
@@ -0,0 +1,476 @@
+use crate::lexer::token::Token;
+use crate::lexer::token::Token::{ArrayId, Id, IndexId};
+use crate::parser::node::{
+    AssignNode, BinOpNode, BlockNode, BoolNumNode, BreakNode, CallNode, CompoundNode, CondStatNode,
+    ContinueNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode,
+    IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, Node, ReturnNode, TypeNode,
+    UnaryOpNode, WhileStatNode,
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrType {
+    Felt,
+    I32,
+    Array(Box<IrType>, usize),
+}
+
+impl IrType {
+    fn from_token(token: &Token) -> Result<Self, String> {
+        match token {
+            Token::Felt => Ok(IrType::Felt),
+            Token::I32 => Ok(IrType::I32),
+            Token::Array(element, len) => {
+                Ok(IrType::Array(Box::new(IrType::from_token(element)?), *len))
+            }
+            _ => Err(format!("type {} has no ir type", token)),
+        }
+    }
+
+    /// Number of frame slots a value of this type takes.
+    pub fn size(&self) -> usize {
+        match self {
+            IrType::Array(element, len) => element.size() * len,
+            _ => 1,
+        }
+    }
+
+    fn element(&self) -> IrType {
+        match self {
+            IrType::Array(element, _) => element.as_ref().clone(),
+            ty => ty.clone(),
+        }
+    }
+}
+
+/// One operation of the IR. Temporaries are numbered per function, locals
+/// and parameters are addressed by their frame offset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrOp {
+    Alloc {
+        ty: IrType,
+        offset: usize,
+    },
+    Const {
+        dst: usize,
+        ty: IrType,
+        value: i128,
+    },
+    Load {
+        dst: usize,
+        ty: IrType,
+        offset: usize,
+    },
+    LoadIndex {
+        dst: usize,
+        ty: IrType,
+        offset: usize,
+        index: usize,
+    },
+    Store {
+        ty: IrType,
+        offset: usize,
+        src: usize,
+    },
+    StoreIndex {
+        ty: IrType,
+        offset: usize,
+        index: usize,
+        src: usize,
+    },
+    Unary {
+        dst: usize,
+        ty: IrType,
+        op: String,
+        src: usize,
+    },
+    Binary {
+        dst: usize,
+        ty: IrType,
+        op: String,
+        lhs: usize,
+        rhs: usize,
+    },
+    Call {
+        dst: Option<usize>,
+        func: String,
+        args: Vec<usize>,
+    },
+    Label(usize),
+    Jump(usize),
+    JumpIfZero {
+        cond: usize,
+        target: usize,
+    },
+    Return(Vec<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrLocal {
+    pub name: String,
+    pub ty: IrType,
+    pub offset: usize,
+}
+
+/// A lowered function, the entry block being lowered as "entry".
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrFunction {
+    pub name: String,
+    pub params: Vec<IrLocal>,
+    /// Parameters and declared variables, in frame order.
+    pub locals: Vec<IrLocal>,
+    pub returns: Vec<IrType>,
+    pub frame_size: usize,
+    pub ops: Vec<IrOp>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrProgram {
+    pub functions: Vec<IrFunction>,
+}
+
+struct FunctionLowering<'a> {
+    return_types: &'a BTreeMap<String, Vec<IrType>>,
+    locals: BTreeMap<String, IrLocal>,
+    function: IrFunction,
+    temps: usize,
+    labels: usize,
+    // Start and end labels of the loops enclosing the statement being
+    // lowered, innermost last.
+    loops: Vec<(usize, usize)>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(name: String, return_types: &'a BTreeMap<String, Vec<IrType>>) -> Self {
+        let returns = return_types.get(&name).cloned().unwrap_or_default();
+        FunctionLowering {
+            return_types,
+            locals: BTreeMap::new(),
+            function: IrFunction {
+                name,
+                params: Vec::new(),
+                locals: Vec::new(),
+                returns,
+                frame_size: 0,
+                ops: Vec::new(),
+            },
+            temps: 0,
+            labels: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, name: &str, ty: IrType) -> IrLocal {
+        let local = IrLocal {
+            name: name.to_string(),
+            offset: self.function.frame_size,
+            ty,
+        };
+        self.function.frame_size += local.ty.size();
+        self.locals.insert(local.name.clone(), local.clone());
+        self.function.locals.push(local.clone());
+        local
+    }
+
+    fn local(&self, name: &str) -> Result<IrLocal, String> {
+        self.locals
+            .get(name)
+            .cloned()
+            .ok_or(format!("variable {} is not declared", name))
+    }
+
+    fn temp(&mut self) -> usize {
+        self.temps += 1;
+        self.temps - 1
+    }
+
+    fn label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn expr(&mut self, node: &Arc<RwLock<dyn Node>>) -> Result<(usize, IrType), String> {
+        let node = node.read().unwrap();
+        let node = node.as_any();
+        if let Some(num) = node.downcast_ref::<IntegerNumNode>() {
+            let dst = self.temp();
+            let value = num.value as i128;
+            self.function.ops.push(IrOp::Const {
+                dst,
+                ty: IrType::I32,
+                value,
+            });
+            Ok((dst, IrType::I32))
+        } else if let Some(num) = node.downcast_ref::<FeltNumNode>() {
+            let dst = self.temp();
+            let value = num.value as i128;
+            self.function.ops.push(IrOp::Const {
+                dst,
+                ty: IrType::Felt,
+                value,
+            });
+            Ok((dst, IrType::Felt))
+        } else if let Some(num) = node.downcast_ref::<BoolNumNode>() {
+            // Conditions are lowered to i32s, one when they hold.
+            let dst = self.temp();
+            self.function.ops.push(IrOp::Const {
+                dst,
+                ty: IrType::I32,
+                value: num.value as i128,
+            });
+            Ok((dst, IrType::I32))
+        } else if let Some(ident) = node.downcast_ref::<IdentNode>() {
+            let local = self.local(&ident.identifier.to_string())?;
+            let dst = self.temp();
+            self.function.ops.push(IrOp::Load {
+                dst,
+                ty: local.ty.clone(),
+                offset: local.offset,
+            });
+            Ok((dst, local.ty))
+        } else if let Some(access) = node.downcast_ref::<IdentIndexNode>() {
+            let local = self.local(&access.identifier.to_string())?;
+            let (index, _) = self.expr(&access.index)?;
+            let dst = self.temp();
+            let ty = local.ty.element();
+            self.function.ops.push(IrOp::LoadIndex {
+                dst,
+                ty: ty.clone(),
+                offset: local.offset,
+                index,
+            });
+            Ok((dst, ty))
+        } else if let Some(binop) = node.downcast_ref::<BinOpNode>() {
+            let (lhs, lhs_type) = self.expr(&binop.left)?;
+            let (rhs, rhs_type) = self.expr(&binop.right)?;
+            let ty = if lhs_type == IrType::I32 && rhs_type == IrType::I32 {
+                IrType::I32
+            } else {
+                IrType::Felt
+            };
+            let dst = self.temp();
+            self.function.ops.push(IrOp::Binary {
+                dst,
+                ty: ty.clone(),
+                op: binop.operator.to_string(),
+                lhs,
+                rhs,
+            });
+            Ok((dst, ty))
+        } else if let Some(unary) = node.downcast_ref::<UnaryOpNode>() {
+            let (src, ty) = self.expr(&unary.expr)?;
+            let dst = self.temp();
+            self.function.ops.push(IrOp::Unary {
+                dst,
+                ty: ty.clone(),
+                op: unary.operator.to_string(),
+                src,
+            });
+            Ok((dst, ty))
+        } else if let Some(call) = node.downcast_ref::<CallNode>() {
+            let func = call.func_name.to_string();
+            let ty = self
+                .return_types
+                .get(&func)
+                .and_then(|returns| returns.first())
+                .cloned()
+                .ok_or(format!("call to {} has no value", func))?;
+            let dst = self.call(call, true)?.unwrap();
+            Ok((dst, ty))
+        } else {
+            Err("expression cannot be lowered to ir".to_string())
+        }
+    }
+
+    fn call(&mut self, call: &CallNode, has_value: bool) -> Result<Option<usize>, String> {
+        let mut args = Vec::new();
+        for param in call.actual_params.iter() {
+            args.push(self.expr(param)?.0);
+        }
+        let dst = if has_value { Some(self.temp()) } else { None };
+        self.function.ops.push(IrOp::Call {
+            dst,
+            func: call.func_name.to_string(),
+            args,
+        });
+        Ok(dst)
+    }
+
+    fn while_loop(
+        &mut self,
+        condition: &Arc<RwLock<dyn Node>>,
+        consequences: &[Arc<RwLock<dyn Node>>],
+    ) -> Result<(), String> {
+        let start_label = self.label();
+        let end_label = self.label();
+        self.function.ops.push(IrOp::Label(start_label));
+        let (condition, _) = self.expr(condition)?;
+        self.function.ops.push(IrOp::JumpIfZero {
+            cond: condition,
+            target: end_label,
+        });
+        self.loops.push((start_label, end_label));
+        self.statements(consequences)?;
+        self.loops.pop();
+        self.function.ops.push(IrOp::Jump(start_label));
+        self.function.ops.push(IrOp::Label(end_label));
+        Ok(())
+    }
+
+    fn enclosing_loop(&self, span: (usize, usize)) -> Result<(usize, usize), String> {
+        self.loops
+            .last()
+            .copied()
+            .ok_or_else(|| format!("jump at {}:{} is outside of a loop", span.0, span.1))
+    }
+
+    fn statements(&mut self, nodes: &[Arc<RwLock<dyn Node>>]) -> Result<(), String> {
+        for node in nodes.iter() {
+            self.statement(node)?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, node: &Arc<RwLock<dyn Node>>) -> Result<(), String> {
+        let node = node.read().unwrap();
+        let node = node.as_any();
+        if let Some(declaration) = node.downcast_ref::<IdentDeclarationNode>() {
+            let ty = IrType::from_token(&declaration.type_node.token)?;
+            let local = self.declare(&declaration.ident_node.identifier.to_string(), ty);
+            self.function.ops.push(IrOp::Alloc {
+                ty: local.ty,
+                offset: local.offset,
+            });
+        } else if let Some(assign) = node.downcast_ref::<AssignNode>() {
+            match &assign.identifier {
+                Id(name) | ArrayId(name) => {
+                    let local = self.local(name)?;
+                    let (src, _) = self.expr(&assign.expr)?;
+                    self.function.ops.push(IrOp::Store {
+                        ty: local.ty,
+                        offset: local.offset,
+                        src,
+                    });
+                }
+                IndexId(name, index) => {
+                    let local = self.local(name)?;
+                    let (index, _) = self.expr(index)?;
+                    let (src, _) = self.expr(&assign.expr)?;
+                    self.function.ops.push(IrOp::StoreIndex {
+                        ty: local.ty.element(),
+                        offset: local.offset,
+                        index,
+                        src,
+                    });
+                }
+                identifier => {
+                    return Err(format!("assignment to {} cannot be lowered", identifier))
+                }
+            }
+        } else if let Some(call) = node.downcast_ref::<CallNode>() {
+            self.call(call, false)?;
+        } else if let Some(ret) = node.downcast_ref::<ReturnNode>() {
+            let mut values = Vec::new();
+            for value in ret.returns.iter() {
+                values.push(self.expr(value)?.0);
+            }
+            self.function.ops.push(IrOp::Return(values));
+        } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+            let end_label = self.label();
+            for (condition, consequences) in cond.branches() {
+                let (condition, _) = self.expr(condition)?;
+                let else_label = self.label();
+                self.function.ops.push(IrOp::JumpIfZero {
+                    cond: condition,
+                    target: else_label,
+                });
+                self.statements(consequences)?;
+                self.function.ops.push(IrOp::Jump(end_label));
+                self.function.ops.push(IrOp::Label(else_label));
+            }
+            self.statements(&cond.alternatives)?;
+            self.function.ops.push(IrOp::Label(end_label));
+        } else if let Some(lp) = node.downcast_ref::<LoopStatNode>() {
+            self.while_loop(&lp.condition, &lp.consequences)?;
+        } else if let Some(lp) = node.downcast_ref::<WhileStatNode>() {
+            self.while_loop(&lp.condition, &lp.consequences)?;
+        } else if let Some(jump) = node.downcast_ref::<BreakNode>() {
+            let (_, end_label) = self.enclosing_loop(jump.span)?;
+            self.function.ops.push(IrOp::Jump(end_label));
+        } else if let Some(jump) = node.downcast_ref::<ContinueNode>() {
+            let (start_label, _) = self.enclosing_loop(jump.span)?;
+            self.function.ops.push(IrOp::Jump(start_label));
+        } else if let Some(compound) = node.downcast_ref::<CompoundNode>() {
+            self.statements(&compound.children)?;
+        } else if let Some(block) = node.downcast_ref::<BlockNode>() {
+            self.statements(&block.declarations)?;
+            self.statement(&block.compound_statement)?;
+        } else if let Some(block) = node.downcast_ref::<EntryBlockNode>() {
+            self.statements(&block.declarations)?;
+            self.statement(&block.compound_statement)?;
+        } else {
+            return Err("statement cannot be lowered to ir".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn lower_function(
+    node: &FunctionNode,
+    return_types: &BTreeMap<String, Vec<IrType>>,
+) -> Result<IrFunction, String> {
+    let mut lowering = FunctionLowering::new(node.func_name.to_string(), return_types);
+    for param in node.params.iter() {
+        let param = param.read().unwrap();
+        let param = param
+            .as_any()
+            .downcast_ref::<IdentDeclarationNode>()
+            .ok_or("function param is not a declaration")?;
+        let ty = IrType::from_token(&param.type_node.token)?;
+        let local = lowering.declare(&param.ident_node.identifier.to_string(), ty);
+        lowering.function.params.push(local);
+    }
+    lowering.statement(&node.block)?;
+    Ok(lowering.function)
+}
+
+/// Lowers an analyzed program into a flat list of typed operations per
+/// function, locals resolved to frame offsets and control flow turned into
+/// labels and jumps.
+pub fn lower(root: &Arc<RwLock<dyn Node>>) -> Result<IrProgram, String> {
+    let root = root.read().unwrap();
+    let entry = root
+        .as_any()
+        .downcast_ref::<EntryNode>()
+        .ok_or("root node is not an entry")?;
+
+    let mut return_types = BTreeMap::new();
+    for declaration in entry.global_declarations.iter() {
+        let declaration = declaration.read().unwrap();
+        if let Some(function) = declaration.as_any().downcast_ref::<FunctionNode>() {
+            let mut returns = Vec::new();
+            for ret in function.returns.iter() {
+                let ret = ret.read().unwrap();
+                let ret = ret
+                    .as_any()
+                    .downcast_ref::<TypeNode>()
+                    .ok_or("function return is not a type")?;
+                returns.push(IrType::from_token(&ret.token)?);
+            }
+            return_types.insert(function.func_name.to_string(), returns);
+        }
+    }
+
+    let mut functions = Vec::new();
+    for declaration in entry.global_declarations.iter() {
+        let declaration = declaration.read().unwrap();
+        if let Some(function) = declaration.as_any().downcast_ref::<FunctionNode>() {
+            functions.push(lower_function(function, &return_types)?);
+        }
+    }
+    let mut lowering = FunctionLowering::new("entry".to_string(), &return_types);
+    lowering.statement(&entry.entry_block)?;
+    functions.push(lowering.function);
+    Ok(IrProgram { functions })
+}
@@ -2,16 +2,18 @@ pub mod token;
 use self::token::Token;
 
 use crate::lexer::token::Token::{
-    And, Assign, Begin, Colon, Comma, Dot, Else, End, Entry, Equal, Felt, FeltConst, Function,
-    GreaterEqual, GreaterThan, I32Const, Id, If, IntegerDivision, LBracket, LParen, LessEqual,
-    LessThan, Malloc, Minus, Mod, Multiply, NotEqual, Or, Plus, Printf, RBracket, RParen, Return,
-    ReturnDel, Semi, Sqrt, While, EOF, I32,
+    And, Assign, Begin, Bool, Break, Colon, Comma, Const, Continue, Dot, Else, End, Entry, Equal,
+    Felt, FeltConst, Function, GreaterEqual, GreaterThan, I32Const, Id, If, IntegerDivision,
+    Invariant, LBracket, LParen, LessEqual, LessThan, Malloc, Minus, MinusAssign, Mod, Multiply,
+    MultiplyAssign, NotEqual, Or, Plus, PlusAssign, Printf, Question, RBracket, RParen, Return,
+    ReturnDel, Semi, Sqrt, Str, While, EOF, I32,
 };
 
 #[derive(Clone)]
 pub struct Lexer {
     text: String,
     position: usize,
+    token_start: usize,
     current_char: Option<char>,
 }
 
@@ -21,6 +23,7 @@ impl Lexer {
         Lexer {
             text: text.to_string(),
             position: 0,
+            token_start: 0,
             current_char: Some(chars[0]),
         }
     }
@@ -38,6 +41,12 @@ impl Lexer {
             "SQRT" => (true, Sqrt),
             "MALLOC" => (true, Malloc),
             "PRINTF" => (true, Printf),
+            "INVARIANT" => (true, Invariant),
+            "TRUE" => (true, Bool(true)),
+            "FALSE" => (true, Bool(false)),
+            "BREAK" => (true, Break),
+            "CONTINUE" => (true, Continue),
+            "CONST" => (true, Const),
             _ => (false, EOF),
         }
     }
@@ -63,16 +72,60 @@ impl Lexer {
         }
     }
     fn skip_comment(&mut self) {
-        while self.current_char != Some('\n') {
+        while self.current_char != None && self.current_char != Some('\n') {
             self.advance()
         }
         self.advance()
     }
+    /// Skips a `/* ... */` comment, the opening `/*` included. Block
+    /// comments nest, each `/*` needs its own `*/`.
+    fn skip_block_comment(&mut self) {
+        let mut depth = 0;
+        loop {
+            match (self.current_char, self.peek()) {
+                (Some('/'), Some('*')) => {
+                    depth += 1;
+                    self.advance();
+                }
+                (Some('*'), Some('/')) => {
+                    depth -= 1;
+                    self.advance();
+                    if depth == 0 {
+                        self.advance();
+                        return;
+                    }
+                }
+                (None, _) => {
+                    let (line, column) = self.token_position();
+                    panic!("Unterminated block comment at {}:{}", line, column)
+                }
+                _ => {}
+            }
+            self.advance()
+        }
+    }
     fn skip_whitespace(&mut self) {
         while self.current_char != None && self.current_char.unwrap().is_whitespace() {
             self.advance()
         }
     }
+    /// Handles a string literal, the current char being its opening quote.
+    fn string(&mut self) -> Option<Token> {
+        self.advance();
+        let mut value = String::new();
+        while self.current_char != Some('"') {
+            match self.current_char {
+                Some(char) => value.push(char),
+                None => {
+                    let (line, column) = self.token_position();
+                    panic!("Unterminated string literal at {}:{}", line, column)
+                }
+            }
+            self.advance();
+        }
+        self.advance();
+        Some(Str(value))
+    }
     /// Handles identifiers and reserved keywords
     fn id(&mut self) -> Option<Token> {
         let mut result = String::new();
@@ -95,21 +148,49 @@ impl Lexer {
 
     fn number(&mut self) -> Option<Token> {
         let mut digits = String::new();
+        if self.current_char == Some('0') && self.peek() == Some('x') {
+            self.advance();
+            self.advance();
+            digits.push_str("0x");
+            while self.current_char != None && self.current_char.unwrap().is_ascii_hexdigit() {
+                digits.push(self.current_char.unwrap());
+                self.advance();
+            }
+            if digits.len() == 2 {
+                panic!("Missing digits in hex felt literal: {}", digits)
+            }
+            // Like decimal ones, hex literals that do not fit the field are
+            // reported by the semantic analysis.
+            return Some(FeltConst(digits));
+        }
         while self.current_char != None && self.current_char.unwrap().is_digit(10) {
             digits.push(self.current_char.unwrap());
             self.advance();
         }
+        // Felt literals that do not fit the field are reported by the
+        // semantic analysis.
         if digits.parse::<i32>().is_ok() {
             Some(I32Const(digits))
-        } else if digits.parse::<u64>().is_ok() {
-            Some(FeltConst(digits))
         } else {
-            panic!("invalid const number");
+            Some(FeltConst(digits))
         }
     }
 
+    /// Returns the 1-based line and column where the last token returned
+    /// by `get_next_token` starts.
+    pub fn token_position(&self) -> (usize, usize) {
+        let before = &self.text[..self.token_start];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(newline) => self.token_start - newline,
+            None => self.token_start + 1,
+        };
+        (line, column)
+    }
+
     pub fn get_next_token(&mut self) -> Option<Token> {
         while self.current_char != None {
+            self.token_start = self.position;
             let token = match self.current_char.unwrap() {
                 char if char.is_whitespace() => {
                     self.skip_whitespace();
@@ -153,7 +234,21 @@ impl Lexer {
                     self.skip_comment();
                     continue;
                 }
+                '/' if self.peek() == Some('/') => {
+                    self.skip_comment();
+                    continue;
+                }
+                '/' if self.peek() == Some('*') => {
+                    self.skip_block_comment();
+                    continue;
+                }
                 char if char.is_digit(10) => self.number(),
+                '"' => self.string(),
+                '+' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    Some(PlusAssign)
+                }
                 '+' => {
                     self.advance();
                     Some(Plus)
@@ -171,6 +266,10 @@ impl Lexer {
                     self.advance();
                     Some(Colon)
                 }
+                '?' => {
+                    self.advance();
+                    Some(Question)
+                }
                 ';' => {
                     self.advance();
                     Some(Semi)
@@ -183,10 +282,20 @@ impl Lexer {
                     self.advance();
                     Some(Dot)
                 }
+                '-' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    Some(MinusAssign)
+                }
                 '-' => {
                     self.advance();
                     Some(Minus)
                 }
+                '*' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    Some(MultiplyAssign)
+                }
                 '*' => {
                     self.advance();
                     Some(Multiply)
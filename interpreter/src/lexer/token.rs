@@ -9,10 +9,13 @@ pub enum Token {
     Array(Box<Token>, usize),
     FeltConst(String),
     I32Const(String),
+    Bool(bool),
+    Str(String),
     Id(String),
     ArrayId(String),
     IndexId(String, Arc<RwLock<dyn Node>>),
     Colon,
+    Question,
     Comma,
     Semi,
     Dot,
@@ -49,6 +52,13 @@ pub enum Token {
     EOF,
     Malloc,
     Printf,
+    Invariant,
+    Break,
+    Continue,
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    Const,
 }
 
 impl PartialEq for Token {
@@ -63,6 +73,12 @@ impl<'a> fmt::Display for Token {
         if let Token::Array(token, len) = self {
             pre_fmt = format!("Array({}[{}])", token, len);
         }
+        if let Token::Bool(value) = self {
+            pre_fmt = value.to_string();
+        }
+        if let Token::Str(value) = self {
+            pre_fmt = format!("\"{}\"", value);
+        }
 
         let output = match self {
             Token::Felt => "FELT",
@@ -70,10 +86,13 @@ impl<'a> fmt::Display for Token {
             Token::Array(_, _) => &pre_fmt,
             Token::FeltConst(value) => value,
             Token::I32Const(value) => value,
+            Token::Bool(_) => &pre_fmt,
+            Token::Str(_) => &pre_fmt,
             Token::Id(name) => name,
             Token::ArrayId(name) => name,
             Token::IndexId(name, _) => name,
             Token::Colon => ":",
+            Token::Question => "?",
             Token::Comma => ",",
             Token::Semi => ";",
             Token::Dot => ".",
@@ -110,6 +129,13 @@ impl<'a> fmt::Display for Token {
             Token::EOF => "EOF",
             Token::Malloc => "malloc",
             Token::Printf => "printf",
+            Token::Invariant => "invariant",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::MultiplyAssign => "*=",
+            Token::Const => "const",
         };
         write!(f, "{}", output)
     }
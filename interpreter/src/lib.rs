@@ -1,5 +1,8 @@
 pub mod interpreter;
+pub mod ir;
 pub mod lexer;
 pub mod parser;
 pub mod sema;
+#[cfg(test)]
+mod tests;
 pub mod utils;
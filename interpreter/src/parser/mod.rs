@@ -1,18 +1,20 @@
 use crate::lexer::token::Token;
 use crate::lexer::token::Token::{
-    And, Array, Assign, Begin, Cid, Comma, Else, End, Entry, Equal, Felt, FeltConst, Function,
-    GreaterEqual, GreaterThan, I32Const, Id, If, IndexId, IntegerDivision, LBracket, LParen,
-    LessEqual, LessThan, Malloc, Minus, Mod, Multiply, NotEqual, Or, Plus, Printf, RBracket,
-    RParen, Return, ReturnDel, Semi, Sqrt, While, EOF, I32,
+    And, Array, Assign, Begin, Break, Cid, Comma, Const, Continue, Else, End, Entry, Equal, Felt,
+    FeltConst, Function, GreaterEqual, GreaterThan, I32Const, Id, If, IndexId, IntegerDivision,
+    Invariant, LBracket, LParen, LessEqual, LessThan, Malloc, Minus, MinusAssign, Mod, Multiply,
+    MultiplyAssign, NotEqual, Or, Plus, PlusAssign, Printf, Question, RBracket, RParen, Return,
+    ReturnDel, Semi, Sqrt, While, EOF, I32,
 };
 use crate::lexer::Lexer;
 use crate::parser::node::{
-    ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode, CondStatNode,
-    ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode,
-    IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode, MultiAssignNode, Node,
-    PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayNumNode, AssignNode, BinOpNode, BlockNode, BoolNumNode, BreakNode, CallNode, CompoundNode,
+    CondStatNode, ContextIdentNode, ContinueNode, EntryBlockNode, EntryNode, FeltNumNode,
+    FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, InvariantNode,
+    LoopStatNode, MallocNode, MultiAssignNode, Node, PrintfNode, ReturnNode, SqrtNode, StringNode,
+    TernaryNode, TypeNode, UnaryOpNode, WhileStatNode,
 };
-use crate::utils::number::Number;
+use crate::utils::number::{parse_felt_literal, Number};
 use log::debug;
 use std::sync::{Arc, RwLock};
 
@@ -21,13 +23,15 @@ pub mod traversal;
 
 #[macro_export]
 macro_rules! array_type_node {
-    ($v: expr, $len: expr) => {
+    ($v: expr, $len: expr, $felt_len: expr) => {
         $v.consume(&LBracket);
-        $len = match $v.get_current_token() {
-            FeltConst(num) => num,
-            I32Const(num) => num,
+        let (len, felt_len) = match $v.get_current_token() {
+            FeltConst(num) => (num, true),
+            I32Const(num) => (num, false),
             _ => panic!("not support token type for declare"),
         };
+        $len = len;
+        $felt_len = felt_len;
         $v.consume(&$v.get_current_token());
         $v.consume(&RBracket);
     };
@@ -37,16 +41,19 @@ macro_rules! array_type_node {
 pub struct Parser {
     lexer: Lexer,
     current_token: Option<Token>,
+    current_span: (usize, usize),
 }
 
 impl Parser {
     pub fn new(text: &str) -> Self {
         let mut lexer = Lexer::new(&text);
         let current_token = lexer.get_next_token();
+        let current_span = lexer.token_position();
 
         Parser {
             lexer,
             current_token,
+            current_span,
         }
     }
     fn get_current_token(&self) -> Token {
@@ -58,6 +65,7 @@ impl Parser {
 
         if current_token == *token_type {
             self.current_token = self.lexer.get_next_token();
+            self.current_span = self.lexer.token_position();
         } else {
             panic!(
                 "Unexpected token error: expected {}, received {}",
@@ -66,6 +74,23 @@ impl Parser {
         }
     }
 
+    // Consumes the `[len]` suffixes of an array type, each one wrapping the
+    // type before it: `felt[3][2]` is two arrays of three felts. Also returns
+    // whether a length is written as a felt constant.
+    fn array_type(&mut self, element: Token) -> (Token, bool) {
+        let mut token = element;
+        let mut felt_len = false;
+        while self.get_current_token() == LBracket {
+            let len;
+            let felt;
+            array_type_node!(self, len, felt);
+            felt_len |= felt;
+            let len = parse_felt_literal(&len).expect("array length out of range") as usize;
+            token = Array(Box::new(token), len);
+        }
+        (token, felt_len)
+    }
+
     fn entry(&mut self) -> Arc<RwLock<dyn Node>> {
         let declarations = self.global_declarations();
         self.consume(&Entry);
@@ -83,26 +108,21 @@ impl Parser {
         function_param_flag: bool,
     ) -> Vec<Arc<RwLock<dyn Node>>> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
-        let mut len = Default::default();
-        let mut array_flag = false;
-        if self.get_current_token() == LBracket {
-            array_type_node!(self, len);
-            array_flag = true;
-        }
+        let array_flag = self.get_current_token() == LBracket;
+        let (array_token, felt_len) = self.array_type(type_node.token.clone());
 
         let current_token = self.get_current_token();
 
         // match current_token { }
         if let Id(id) = current_token {
             debug!("declarations id:{}", id);
+            let ident_span = self.current_span;
             if array_flag {
                 let node = IdentDeclarationNode::new(
-                    IdentNode::new(Id(id.clone())),
-                    TypeNode::new(Array(
-                        Box::new(type_node.token.clone()),
-                        len.parse().unwrap(),
-                    )),
-                );
+                    IdentNode::new(Id(id.clone())).with_span(ident_span),
+                    TypeNode::new(array_token),
+                )
+                .with_felt_length(felt_len);
                 declarations.push(Arc::new(RwLock::new(node)));
 
                 let ident = self.get_current_token();
@@ -113,7 +133,15 @@ impl Parser {
             if !function_param_flag {
                 if self.get_current_token() == Assign {
                     debug!("declarations id assign:{}", id);
-                    let expr = self.assignment_call_statement(Some(Id(id)));
+                    let expr = self.assignment_call_statement(Some(Id(id)), ident_span);
+                    if let Some(assign) = expr
+                        .write()
+                        .unwrap()
+                        .as_any_mut()
+                        .downcast_mut::<AssignNode>()
+                    {
+                        assign.initializer = true;
+                    }
                     declarations.push(expr);
                     self.consume(&Semi);
                 } else {
@@ -133,7 +161,9 @@ impl Parser {
     fn global_declarations(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
         loop {
-            if self.get_current_token() == I32 || self.get_current_token() == Felt {
+            if self.get_current_token() == Const {
+                declarations.extend(self.const_declaration());
+            } else if self.get_current_token() == I32 || self.get_current_token() == Felt {
                 let type_node = self.type_spec();
                 declarations.extend(self.ident_declaration_assignment(&type_node, false));
             } else if self.get_current_token() == Function {
@@ -159,16 +189,8 @@ impl Parser {
                                 || self.get_current_token() == Felt
                             {
                                 let type_node = self.type_spec();
-                                if self.get_current_token() == LBracket {
-                                    let len;
-                                    array_type_node!(self, len);
-                                    let token =
-                                        Array(Box::new(type_node.token), len.parse().unwrap());
-                                    let node = TypeNode::new(token);
-                                    returns.push(Arc::new(RwLock::new(node)));
-                                } else {
-                                    returns.push(Arc::new(RwLock::new(type_node)));
-                                }
+                                let (token, _) = self.array_type(type_node.token);
+                                returns.push(Arc::new(RwLock::new(TypeNode::new(token))));
                                 if Comma == self.get_current_token() {
                                     self.consume(&Comma);
                                 }
@@ -176,15 +198,8 @@ impl Parser {
                             self.consume(&RParen);
                         } else {
                             let type_node = self.type_spec();
-                            if self.get_current_token() == LBracket {
-                                let len;
-                                array_type_node!(self, len);
-                                let token = Array(Box::new(type_node.token), len.parse().unwrap());
-                                let node = TypeNode::new(token);
-                                returns.push(Arc::new(RwLock::new(node)));
-                            } else {
-                                returns.push(Arc::new(RwLock::new(type_node)));
-                            }
+                            let (token, _) = self.array_type(type_node.token);
+                            returns.push(Arc::new(RwLock::new(TypeNode::new(token))));
                             if Comma == self.get_current_token() {
                                 self.consume(&Comma);
                             }
@@ -237,6 +252,7 @@ impl Parser {
         loop {
             if let Id(id) = self.get_current_token() {
                 debug!("id:{}", id);
+                let span = self.current_span;
                 self.consume(&self.get_current_token());
                 let mut token = Id(id.clone());
                 if self.get_current_token() == LBracket {
@@ -246,7 +262,7 @@ impl Parser {
                     self.consume(&RBracket);
                 }
 
-                results.push(self.assignment_call_statement(Some(token)));
+                results.push(self.assignment_call_statement(Some(token), span));
 
                 if let Id(_) = self.get_current_token() {
                     panic!(
@@ -258,7 +274,7 @@ impl Parser {
                     self.consume(&Semi);
                 }
             } else if let Cid(_id) = self.get_current_token() {
-                results.push(self.assignment_call_statement(None));
+                results.push(self.assignment_call_statement(None, self.current_span));
                 if let Id(_) = self.get_current_token() {
                     panic!(
                         "Invalid token in statement list: {}",
@@ -272,18 +288,31 @@ impl Parser {
                 results.push(self.cond_statement());
             } else if While == self.get_current_token() {
                 results.push(self.loop_statement());
+            } else if Break == self.get_current_token() || Continue == self.get_current_token() {
+                let span = self.current_span;
+                let token = self.get_current_token();
+                self.consume(&token);
+                let node: Arc<RwLock<dyn Node>> = if token == Break {
+                    Arc::new(RwLock::new(BreakNode::new(span)))
+                } else {
+                    Arc::new(RwLock::new(ContinueNode::new(span)))
+                };
+                results.push(node);
+                if self.get_current_token() != End {
+                    self.consume(&Semi);
+                }
             } else if End == self.get_current_token() {
                 break;
             } else if Return == self.get_current_token() {
                 self.consume(&Return);
                 let mut returns = Vec::new();
                 if self.get_current_token() != LParen {
-                    let expr = self.or_expr();
+                    let expr = self.ternary_expr();
                     returns.push(expr);
                 } else {
                     self.consume(&self.get_current_token());
                     while self.get_current_token() != RParen {
-                        let expr = self.or_expr();
+                        let expr = self.ternary_expr();
                         returns.push(expr);
                         if self.get_current_token() == Comma {
                             self.consume(&self.get_current_token());
@@ -307,9 +336,9 @@ impl Parser {
                         let type_node = self.type_spec();
                         idents.extend(self.ident_declaration_assignment(&type_node, true));
                     } else if let Id(_) = self.get_current_token() {
-                        idents.push(Arc::new(RwLock::new(IdentNode::new(
-                            self.get_current_token(),
-                        ))));
+                        idents.push(Arc::new(RwLock::new(
+                            IdentNode::new(self.get_current_token()).with_span(self.current_span),
+                        )));
                         self.consume(&self.get_current_token());
                         if self.get_current_token() == Comma {
                             self.consume(&Comma);
@@ -357,9 +386,10 @@ impl Parser {
         // variable_declaration : Id (Comma Id)* Colon type_spec
         let mut ident_nodes: Vec<IdentNode> = Vec::new();
         let identifier = self.get_current_token();
+        let span = self.current_span;
         self.consume(&identifier);
 
-        ident_nodes.push(IdentNode::new(identifier));
+        ident_nodes.push(IdentNode::new(identifier).with_span(span));
 
         let mut declaration_nodes: Vec<Arc<RwLock<dyn Node>>> = vec![];
         for node in ident_nodes {
@@ -371,9 +401,37 @@ impl Parser {
 
     fn declarations(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
-        while self.get_current_token() == I32 || self.get_current_token() == Felt {
-            let type_node = self.type_spec();
-            declarations.extend(self.ident_declaration_assignment(&type_node, false));
+        loop {
+            if self.get_current_token() == Const {
+                declarations.extend(self.const_declaration());
+            } else if self.get_current_token() == I32 || self.get_current_token() == Felt {
+                let type_node = self.type_spec();
+                declarations.extend(self.ident_declaration_assignment(&type_node, false));
+            } else {
+                break;
+            }
+        }
+        declarations
+    }
+
+    fn const_declaration(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
+        // const_declaration : Const type_spec Id Assign expr Semi
+        self.consume(&Const);
+        let type_node = self.type_spec();
+        let declarations = self.ident_declaration_assignment(&type_node, false);
+        {
+            let mut declaration = declarations[0].write().unwrap();
+            let declaration = declaration
+                .as_any_mut()
+                .downcast_mut::<IdentDeclarationNode>()
+                .unwrap();
+            if declarations.len() == 1 {
+                panic!(
+                    "const {} is declared without an initializer",
+                    declaration.ident_node.identifier
+                );
+            }
+            declaration.constant = true;
         }
         declarations
     }
@@ -389,7 +447,7 @@ impl Parser {
         self.consume(&LParen);
         let mut params = Vec::new();
         while self.get_current_token() != RParen {
-            let param = self.or_expr();
+            let param = self.ternary_expr();
             params.push(param);
             if self.get_current_token() == Comma {
                 self.consume(&Comma);
@@ -400,7 +458,12 @@ impl Parser {
         Arc::new(RwLock::new(node))
     }
 
-    fn assignment_call_statement(&mut self, id: Option<Token>) -> Arc<RwLock<dyn Node>> {
+    // `span` is the position of the assigned identifier.
+    fn assignment_call_statement(
+        &mut self,
+        id: Option<Token>,
+        span: (usize, usize),
+    ) -> Arc<RwLock<dyn Node>> {
         let left;
         if id.is_none() {
             left = self.get_current_token();
@@ -411,8 +474,26 @@ impl Parser {
         let current_token = self.get_current_token();
         if current_token == Assign {
             self.consume(&Assign);
-            let right = self.or_expr();
-            let node = AssignNode::new(left, right, current_token);
+            let right = self.ternary_expr();
+            let node = AssignNode::new(left, right, current_token).with_span(span);
+            Arc::new(RwLock::new(node))
+        } else if let Some(operator) = compound_operator(&current_token) {
+            // `x op= y` is parsed as `x = x op y`. The index of an array
+            // element target is parsed once, the target and the read of the
+            // element share it.
+            self.consume(&current_token);
+            let current = match &left {
+                IndexId(name, index) => Arc::new(RwLock::new(
+                    IdentIndexNode::new(Id(name.clone()), index.clone()).with_span(span),
+                )) as Arc<RwLock<dyn Node>>,
+                Cid(_) => Arc::new(RwLock::new(
+                    ContextIdentNode::new(left.clone()).with_span(span),
+                )),
+                _ => Arc::new(RwLock::new(IdentNode::new(left.clone()).with_span(span))),
+            };
+            let operand = self.ternary_expr();
+            let right = Arc::new(RwLock::new(BinOpNode::new(current, operand, operator)));
+            let node = AssignNode::new(left, right, Assign).with_span(span);
             Arc::new(RwLock::new(node))
         } else if current_token == LParen {
             self.call_statement(Some(left))
@@ -429,46 +510,71 @@ impl Parser {
         let true_state = self.statement_list();
         self.consume(&End);
 
+        let mut else_ifs = Vec::new();
         let mut flase_state = Vec::new();
-        if Else == self.get_current_token() {
+        while Else == self.get_current_token() {
             self.consume(&Else);
             if If == self.get_current_token() {
-                flase_state.push(self.cond_statement());
+                self.consume(&If);
+                let condition = self.or_expr();
+                self.consume(&Begin);
+                let state = self.statement_list();
+                self.consume(&End);
+                else_ifs.push((condition, state));
             } else if Begin == self.get_current_token() {
                 self.consume(&Begin);
                 flase_state.extend(self.statement_list());
                 self.consume(&End);
+                break;
             } else {
                 panic!("not support condition branch");
             }
         }
-        let node = CondStatNode::new(condition, true_state, flase_state);
+        let node = CondStatNode::new(condition, true_state, flase_state).with_else_ifs(else_ifs);
 
         return Arc::new(RwLock::new(node));
     }
 
     fn loop_statement(&mut self) -> Arc<RwLock<dyn Node>> {
         self.consume(&While);
+        let parenthesized = self.get_current_token() == LParen;
         let condition = self.or_expr();
+        if parenthesized && self.get_current_token() == Begin {
+            self.consume(&Begin);
+            let consequences = self.statement_list();
+            self.consume(&End);
+            return Arc::new(RwLock::new(WhileStatNode::new(condition, consequences)));
+        }
+
+        let mut invariant = None;
+        if self.get_current_token() == Invariant {
+            let span = self.current_span;
+            self.consume(&Invariant);
+            self.consume(&LParen);
+            let node = InvariantNode::new(self.or_expr(), span);
+            self.consume(&RParen);
+            invariant = Some(Arc::new(RwLock::new(node)) as Arc<RwLock<dyn Node>>);
+        }
 
         self.consume(&Begin);
         let consequences = self.statement_list();
         self.consume(&End);
 
-        let node = LoopStatNode::new(condition, consequences);
+        let node = LoopStatNode::new(condition, consequences).with_invariant(invariant);
 
         Arc::new(RwLock::new(node))
     }
 
     fn identifier(&mut self) -> Arc<RwLock<dyn Node>> {
         let current_token = self.get_current_token();
+        let span = self.current_span;
         if let Id(_) = current_token {
             self.consume(&self.get_current_token());
             if self.get_current_token() == LParen {
                 self.consume(&self.get_current_token());
                 let mut params = Vec::new();
                 while self.get_current_token() != RParen {
-                    let param = self.or_expr();
+                    let param = self.ternary_expr();
                     params.push(param);
                     if self.get_current_token() == Comma {
                         self.consume(&Comma);
@@ -480,16 +586,16 @@ impl Parser {
             } else if LBracket == self.get_current_token() {
                 self.consume(&LBracket);
                 let index = self.add_expr();
-                let node = IdentIndexNode::new(current_token, index);
+                let node = IdentIndexNode::new(current_token, index).with_span(span);
                 self.consume(&RBracket);
                 Arc::new(RwLock::new(node))
             } else {
-                let node = IdentNode::new(current_token);
+                let node = IdentNode::new(current_token).with_span(span);
                 Arc::new(RwLock::new(node))
             }
         } else if let Cid(_) = self.get_current_token() {
             self.consume(&current_token);
-            let node = ContextIdentNode::new(current_token);
+            let node = ContextIdentNode::new(current_token).with_span(span);
             Arc::new(RwLock::new(node))
         } else {
             panic!("Invalid variable: {}", current_token);
@@ -508,7 +614,7 @@ impl Parser {
             FeltConst(value) => {
                 current_token = self.get_current_token();
                 self.consume(&current_token);
-                Arc::new(RwLock::new(FeltNumNode::new(value.parse::<u64>().unwrap())))
+                Arc::new(RwLock::new(FeltNumNode::from_literal(&value)))
             }
             I32Const(value) => {
                 current_token = self.get_current_token();
@@ -517,6 +623,15 @@ impl Parser {
                     value.parse::<i32>().unwrap(),
                 )))
             }
+            Token::Bool(value) => {
+                self.consume(&current_token);
+                Arc::new(RwLock::new(BoolNumNode::new(value)))
+            }
+            Token::Str(ref value) => {
+                let node = StringNode::new(value.clone());
+                self.consume(&current_token);
+                Arc::new(RwLock::new(node))
+            }
             Sqrt => {
                 self.consume(&current_token);
                 self.consume(&LParen);
@@ -533,7 +648,7 @@ impl Parser {
             }
             LParen => {
                 self.consume(&current_token);
-                let node = self.or_expr();
+                let node = self.ternary_expr();
                 self.consume(&RParen);
                 node
             }
@@ -636,12 +751,42 @@ impl Parser {
         node
     }
 
+    // `condition ? then : else`, binding looser than `||`. The branches nest
+    // to the right, `a ? b : c ? d : e` is `a ? b : (c ? d : e)`.
+    fn ternary_expr(&mut self) -> Arc<RwLock<dyn Node>> {
+        let condition = self.or_expr();
+        if self.get_current_token() != Question {
+            return condition;
+        }
+        self.consume(&Question);
+        let then_expr = self.ternary_expr();
+        self.consume(&Token::Colon);
+        let else_expr = self.ternary_expr();
+        Arc::new(RwLock::new(TernaryNode::new(
+            condition, then_expr, else_expr,
+        )))
+    }
+
     fn array_const(&mut self) -> Arc<RwLock<dyn Node>> {
+        Arc::new(RwLock::new(self.array_literal()))
+    }
+
+    // An array literal, nested literals being the rows of a multi-dimensional
+    // array.
+    fn array_literal(&mut self) -> ArrayNumNode {
         self.consume(&LBracket);
         let mut values = Vec::new();
+        let mut rows = Vec::new();
         loop {
             let current_token = self.get_current_token();
-            if let I32Const(value) = current_token {
+            if current_token == LBracket && values.is_empty() {
+                rows.push(self.array_literal());
+                if Comma == self.get_current_token() {
+                    self.consume(&self.get_current_token());
+                }
+            } else if !rows.is_empty() && current_token != RBracket {
+                panic!("invalid array const")
+            } else if let I32Const(value) = current_token {
                 values.push(Number::I32(value.parse().unwrap()));
                 self.consume(&self.get_current_token());
 
@@ -649,7 +794,8 @@ impl Parser {
                     self.consume(&self.get_current_token());
                 }
             } else if let FeltConst(value) = current_token {
-                values.push(Number::Felt(value.parse().unwrap()));
+                let felt = parse_felt_literal(&value).expect("felt constant out of range");
+                values.push(Number::Felt(felt as i128));
 
                 self.consume(&self.get_current_token());
                 if Comma == self.get_current_token() {
@@ -663,8 +809,11 @@ impl Parser {
             }
         }
 
-        let node = ArrayNumNode::new(values);
-        Arc::new(RwLock::new(node))
+        if rows.is_empty() {
+            ArrayNumNode::new(values)
+        } else {
+            ArrayNumNode::nested(rows)
+        }
     }
 
     pub fn parse(&mut self) -> Arc<RwLock<dyn Node>> {
@@ -676,3 +825,13 @@ impl Parser {
         node
     }
 }
+
+// Arithmetic operator a compound assignment operator applies.
+fn compound_operator(token: &Token) -> Option<Token> {
+    match token {
+        PlusAssign => Some(Plus),
+        MinusAssign => Some(Minus),
+        MultiplyAssign => Some(Multiply),
+        _ => None,
+    }
+}
@@ -6,10 +6,10 @@ use std::sync::{Arc, RwLock};
 use crate::lexer::token::Token;
 use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
 use crate::sema::symbol::Symbol;
-use crate::utils::number::{Number, NumberResult};
+use crate::utils::number::{parse_felt_literal, Number, NumberResult};
 use node_derive::Node;
 
-pub trait Node {
+pub trait Node: Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn traverse(&mut self, visitor: &mut dyn Traversal) -> NumberResult;
@@ -26,25 +26,74 @@ impl IntegerNumNode {
     }
 }
 
+#[derive(Debug, Node)]
+pub struct BoolNumNode {
+    pub value: bool,
+}
+
+impl BoolNumNode {
+    pub fn new(value: bool) -> Self {
+        BoolNumNode { value }
+    }
+}
+
+#[derive(Debug, Node)]
+pub struct StringNode {
+    pub value: String,
+}
+
+impl StringNode {
+    pub fn new(value: String) -> Self {
+        StringNode { value }
+    }
+}
+
 #[derive(Debug, Node)]
 pub struct FeltNumNode {
     pub value: u64,
+    pub literal: String,
 }
 
 impl FeltNumNode {
     pub fn new(value: u64) -> Self {
-        FeltNumNode { value }
+        FeltNumNode {
+            value,
+            literal: value.to_string(),
+        }
+    }
+
+    /// Keeps the literal as written, `value` is zero when it does not fit in
+    /// 64 bits and is rejected by the semantic analysis.
+    pub fn from_literal(literal: &str) -> Self {
+        FeltNumNode {
+            value: parse_felt_literal(literal).unwrap_or_default(),
+            literal: literal.to_string(),
+        }
     }
 }
 
 #[derive(Debug, Node)]
 pub struct ArrayNumNode {
+    /// Elements in row-major order, the ones of every row for a nested literal.
     pub values: Vec<Number>,
+    /// Rows of a multi-dimensional literal, empty for a one-dimensional one.
+    pub rows: Vec<ArrayNumNode>,
 }
 
 impl ArrayNumNode {
     pub fn new(values: Vec<Number>) -> Self {
-        ArrayNumNode { values }
+        ArrayNumNode {
+            values,
+            rows: Vec::new(),
+        }
+    }
+
+    /// A literal made of the literals `rows`, e.g. `[[1, 2], [3, 4]]`.
+    pub fn nested(rows: Vec<ArrayNumNode>) -> Self {
+        ArrayNumNode {
+            values: rows.iter().flat_map(|row| row.values.clone()).collect(),
+            rows,
+        }
     }
 }
 
@@ -70,6 +119,12 @@ pub fn to_string(node: &Arc<RwLock<dyn Node>>) -> String {
         safe_downcast_ref::<IntegerNumNode>(node).value.to_string()
     } else if is_node_type::<FeltNumNode>(node) {
         safe_downcast_ref::<FeltNumNode>(node).value.to_string()
+    } else if is_node_type::<BoolNumNode>(node) {
+        safe_downcast_ref::<BoolNumNode>(node).value.to_string()
+    } else if is_node_type::<StringNode>(node) {
+        format!("{:?}", safe_downcast_ref::<StringNode>(node).value)
+    } else if is_node_type::<TernaryNode>(node) {
+        format!("{:?}", safe_downcast_ref::<TernaryNode>(node))
     } else if is_node_type::<IdentNode>(node) {
         safe_downcast_ref::<IdentNode>(node).identifier.to_string()
     } else if is_node_type::<ContextIdentNode>(node) {
@@ -114,25 +169,79 @@ impl Debug for UnaryOpNode {
     }
 }
 
+/// `condition ? then_expr : else_expr`, the value of one of the branches.
+#[derive(Node)]
+pub struct TernaryNode {
+    pub condition: Arc<RwLock<dyn Node>>,
+    pub then_expr: Arc<RwLock<dyn Node>>,
+    pub else_expr: Arc<RwLock<dyn Node>>,
+}
+
+impl TernaryNode {
+    pub fn new(
+        condition: Arc<RwLock<dyn Node>>,
+        then_expr: Arc<RwLock<dyn Node>>,
+        else_expr: Arc<RwLock<dyn Node>>,
+    ) -> Self {
+        TernaryNode {
+            condition,
+            then_expr,
+            else_expr,
+        }
+    }
+}
+
+impl Debug for TernaryNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TernaryNode({} ? {} : {}) ",
+            to_string(&self.condition),
+            to_string(&self.then_expr),
+            to_string(&self.else_expr)
+        )
+    }
+}
+
 #[derive(Node)]
 pub struct IdentNode {
     pub identifier: Token,
+    /// Line and column in the source, (0, 0) for nodes not built from it.
+    pub span: (usize, usize),
 }
 
 impl IdentNode {
     pub fn new(identifier: Token) -> Self {
-        IdentNode { identifier }
+        IdentNode {
+            identifier,
+            span: (0, 0),
+        }
+    }
+
+    pub fn with_span(mut self, span: (usize, usize)) -> Self {
+        self.span = span;
+        self
     }
 }
 
 #[derive(Node)]
 pub struct ContextIdentNode {
     pub identifier: Token,
+    /// Line and column in the source, (0, 0) for nodes not built from it.
+    pub span: (usize, usize),
 }
 
 impl ContextIdentNode {
     pub fn new(identifier: Token) -> Self {
-        ContextIdentNode { identifier }
+        ContextIdentNode {
+            identifier,
+            span: (0, 0),
+        }
+    }
+
+    pub fn with_span(mut self, span: (usize, usize)) -> Self {
+        self.span = span;
+        self
     }
 }
 
@@ -141,6 +250,10 @@ pub struct AssignNode {
     pub identifier: Token,
     pub expr: Arc<RwLock<dyn Node>>,
     pub operator: Token,
+    /// Line and column in the source, (0, 0) for nodes not built from it.
+    pub span: (usize, usize),
+    /// Whether this is the initializer of a declaration, `felt x = 1;`.
+    pub initializer: bool,
 }
 
 impl AssignNode {
@@ -149,8 +262,15 @@ impl AssignNode {
             identifier,
             expr,
             operator,
+            span: (0, 0),
+            initializer: false,
         }
     }
+
+    pub fn with_span(mut self, span: (usize, usize)) -> Self {
+        self.span = span;
+        self
+    }
 }
 
 #[derive(Node)]
@@ -181,6 +301,11 @@ impl MultiAssignNode {
 pub struct IdentDeclarationNode {
     pub ident_node: IdentNode,
     pub type_node: TypeNode,
+    /// Whether the array length was written as a felt literal.
+    pub felt_length: bool,
+    /// Whether the variable is declared const, only its initializer assigns
+    /// it then.
+    pub constant: bool,
 }
 
 impl IdentDeclarationNode {
@@ -188,8 +313,15 @@ impl IdentDeclarationNode {
         IdentDeclarationNode {
             ident_node,
             type_node,
+            felt_length: false,
+            constant: false,
         }
     }
+
+    pub fn with_felt_length(mut self, felt_length: bool) -> Self {
+        self.felt_length = felt_length;
+        self
+    }
 }
 
 #[derive(Clone, Node)]
@@ -226,11 +358,22 @@ impl ArrayIdentNode {
 pub struct IdentIndexNode {
     pub identifier: Token,
     pub index: Arc<RwLock<dyn Node>>,
+    /// Line and column in the source, (0, 0) for nodes not built from it.
+    pub span: (usize, usize),
 }
 
 impl IdentIndexNode {
     pub fn new(identifier: Token, index: Arc<RwLock<dyn Node>>) -> Self {
-        IdentIndexNode { identifier, index }
+        IdentIndexNode {
+            identifier,
+            index,
+            span: (0, 0),
+        }
+    }
+
+    pub fn with_span(mut self, span: (usize, usize)) -> Self {
+        self.span = span;
+        self
     }
 }
 
@@ -285,6 +428,9 @@ impl CompoundNode {
 pub struct CondStatNode {
     pub condition: Arc<RwLock<dyn Node>>,
     pub consequences: Vec<Arc<RwLock<dyn Node>>>,
+    /// Condition and statements of each `else if`, in source order.
+    pub else_ifs: Vec<(Arc<RwLock<dyn Node>>, Vec<Arc<RwLock<dyn Node>>>)>,
+    /// Statements of the final `else`.
     pub alternatives: Vec<Arc<RwLock<dyn Node>>>,
 }
 
@@ -297,15 +443,41 @@ impl CondStatNode {
         CondStatNode {
             condition,
             consequences,
+            else_ifs: Vec::new(),
             alternatives,
         }
     }
+
+    pub fn with_else_ifs(
+        mut self,
+        else_ifs: Vec<(Arc<RwLock<dyn Node>>, Vec<Arc<RwLock<dyn Node>>>)>,
+    ) -> Self {
+        self.else_ifs = else_ifs;
+        self
+    }
+
+    /// Condition and statements of the `if` and of every `else if`, in order.
+    pub fn branches(&self) -> Vec<(&Arc<RwLock<dyn Node>>, &Vec<Arc<RwLock<dyn Node>>>)> {
+        let mut branches = vec![(&self.condition, &self.consequences)];
+        branches.extend(self.else_ifs.iter().map(|(cond, body)| (cond, body)));
+        branches
+    }
+
+    /// Statements of every branch, the final `else` included.
+    pub fn branch_statements(&self) -> impl Iterator<Item = &Arc<RwLock<dyn Node>>> {
+        self.consequences
+            .iter()
+            .chain(self.else_ifs.iter().flat_map(|(_, body)| body.iter()))
+            .chain(self.alternatives.iter())
+    }
 }
 
 #[derive(Node)]
 pub struct LoopStatNode {
     pub condition: Arc<RwLock<dyn Node>>,
     pub consequences: Vec<Arc<RwLock<dyn Node>>>,
+    /// `InvariantNode` annotating the loop, if any.
+    pub invariant: Option<Arc<RwLock<dyn Node>>>,
 }
 
 impl LoopStatNode {
@@ -313,8 +485,69 @@ impl LoopStatNode {
         LoopStatNode {
             condition,
             consequences,
+            invariant: None,
         }
     }
+
+    pub fn with_invariant(mut self, invariant: Option<Arc<RwLock<dyn Node>>>) -> Self {
+        self.invariant = invariant;
+        self
+    }
+}
+
+/// `while (condition) { ... }` loop, the condition being re-evaluated before
+/// every iteration. The condition must be a comparison.
+#[derive(Node)]
+pub struct WhileStatNode {
+    pub condition: Arc<RwLock<dyn Node>>,
+    pub consequences: Vec<Arc<RwLock<dyn Node>>>,
+}
+
+impl WhileStatNode {
+    pub fn new(condition: Arc<RwLock<dyn Node>>, consequences: Vec<Arc<RwLock<dyn Node>>>) -> Self {
+        WhileStatNode {
+            condition,
+            consequences,
+        }
+    }
+}
+
+/// `break` out of the innermost enclosing loop.
+#[derive(Node)]
+pub struct BreakNode {
+    pub span: (usize, usize),
+}
+
+impl BreakNode {
+    pub fn new(span: (usize, usize)) -> Self {
+        BreakNode { span }
+    }
+}
+
+/// `continue` with the next iteration of the innermost enclosing loop.
+#[derive(Node)]
+pub struct ContinueNode {
+    pub span: (usize, usize),
+}
+
+impl ContinueNode {
+    pub fn new(span: (usize, usize)) -> Self {
+        ContinueNode { span }
+    }
+}
+
+/// `invariant(condition)` annotation of a loop, a condition that holds each
+/// time the loop condition is evaluated.
+#[derive(Node)]
+pub struct InvariantNode {
+    pub condition: Arc<RwLock<dyn Node>>,
+    pub span: (usize, usize),
+}
+
+impl InvariantNode {
+    pub fn new(condition: Arc<RwLock<dyn Node>>, span: (usize, usize)) -> Self {
+        InvariantNode { condition, span }
+    }
 }
 
 #[derive(Node)]
@@ -364,6 +597,8 @@ pub struct CallNode {
     pub func_name: Token,
     pub actual_params: Vec<Arc<RwLock<dyn Node>>>,
     pub func_symbol: Option<Arc<RwLock<Symbol>>>,
+    /// Set by analysis when the call's result is returned right away.
+    pub tail_call: bool,
 }
 
 impl CallNode {
@@ -372,6 +607,7 @@ impl CallNode {
             func_name,
             actual_params,
             func_symbol: None,
+            tail_call: false,
         }
     }
 }
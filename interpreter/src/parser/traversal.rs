@@ -1,10 +1,15 @@
+use crate::lexer::token::Token;
 use crate::parser::node::{
-    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode,
-    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode,
-    IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode,
-    MultiAssignNode, Node, PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, BoolNumNode, BreakNode,
+    CallNode, CompoundNode, CondStatNode, ContextIdentNode, ContinueNode, EntryBlockNode,
+    EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode,
+    IntegerNumNode, InvariantNode, LoopStatNode, MallocNode, MultiAssignNode, Node, PrintfNode,
+    ReturnNode, SqrtNode, StringNode, TernaryNode, TypeNode, UnaryOpNode, WhileStatNode,
 };
+use crate::sema::error::SemaError;
+use crate::utils::number::Number::{Bool, Nil, Str};
 use crate::utils::number::NumberResult;
+use crate::utils::number::NumberRet::Single;
 use std::sync::{Arc, RwLock};
 
 pub fn is_node_type<T: Node + 'static>(node: &Arc<RwLock<dyn Node>>) -> bool {
@@ -89,6 +94,22 @@ pub trait Traversal {
                     .downcast_mut::<FeltNumNode>()
                     .expect("Failed to downcast to FeltNumNode type"),
             )
+        } else if is_node_type::<BoolNumNode>(node) {
+            self.travel_bool(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<BoolNumNode>()
+                    .expect("Failed to downcast to BoolNumNode type"),
+            )
+        } else if is_node_type::<StringNode>(node) {
+            self.travel_string(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<StringNode>()
+                    .expect("Failed to downcast to StringNode type"),
+            )
         } else if is_node_type::<BinOpNode>(node) {
             self.travel_binop(
                 node.write()
@@ -105,6 +126,14 @@ pub trait Traversal {
                     .downcast_mut::<UnaryOpNode>()
                     .expect("Failed to downcast to UnaryOpNode type"),
             )
+        } else if is_node_type::<TernaryNode>(node) {
+            self.travel_ternary(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<TernaryNode>()
+                    .expect("Failed to downcast to TernaryNode type"),
+            )
         } else if is_node_type::<AssignNode>(node) {
             self.travel_assign(
                 node.write()
@@ -153,6 +182,38 @@ pub trait Traversal {
                     .downcast_mut::<LoopStatNode>()
                     .expect("Failed to downcast to LoopStatNode type"),
             )
+        } else if is_node_type::<WhileStatNode>(node) {
+            self.travel_while(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<WhileStatNode>()
+                    .expect("Failed to downcast to WhileStatNode type"),
+            )
+        } else if is_node_type::<InvariantNode>(node) {
+            self.travel_invariant(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<InvariantNode>()
+                    .expect("Failed to downcast to InvariantNode type"),
+            )
+        } else if is_node_type::<BreakNode>(node) {
+            self.travel_break(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<BreakNode>()
+                    .expect("Failed to downcast to BreakNode type"),
+            )
+        } else if is_node_type::<ContinueNode>(node) {
+            self.travel_continue(
+                node.write()
+                    .unwrap()
+                    .as_any_mut()
+                    .downcast_mut::<ContinueNode>()
+                    .expect("Failed to downcast to ContinueNode type"),
+            )
         } else if is_node_type::<EntryNode>(node) {
             self.travel_entry(
                 node.write()
@@ -226,33 +287,166 @@ pub trait Traversal {
                     .expect("Failed to downcast to PrintfNode type"),
             )
         } else {
-            Err("Unknown node found".to_string())
+            Err(SemaError::Other("Unknown node found".to_string()))
         }
     }
-    fn travel_function(&mut self, node: &mut FunctionNode) -> NumberResult;
-    fn travel_block(&mut self, node: &mut BlockNode) -> NumberResult;
-    fn travel_entry_block(&mut self, node: &mut EntryBlockNode) -> NumberResult;
-    fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult;
-    fn travel_type(&mut self, node: &mut TypeNode) -> NumberResult;
-    fn travel_array_ident(&mut self, node: &mut ArrayIdentNode) -> NumberResult;
-    fn travel_integer(&mut self, node: &mut IntegerNumNode) -> NumberResult;
-    fn travel_felt(&mut self, node: &mut FeltNumNode) -> NumberResult;
-    fn travel_array(&mut self, node: &mut ArrayNumNode) -> NumberResult;
-    fn travel_binop(&mut self, node: &mut BinOpNode) -> NumberResult;
-    fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult;
-    fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult;
-    fn travel_cond(&mut self, node: &mut CondStatNode) -> NumberResult;
-    fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult;
-    fn travel_ident(&mut self, node: &mut IdentNode) -> NumberResult;
-    fn travel_ident_index(&mut self, node: &mut IdentIndexNode) -> NumberResult;
-    fn travel_context_ident(&mut self, node: &mut ContextIdentNode) -> NumberResult;
-    fn travel_assign(&mut self, node: &mut AssignNode) -> NumberResult;
-    fn travel_entry(&mut self, node: &mut EntryNode) -> NumberResult;
-    fn travel_call(&mut self, node: &mut CallNode) -> NumberResult;
-    fn travel_sqrt(&mut self, node: &mut SqrtNode) -> NumberResult;
-    fn travel_return(&mut self, node: &mut ReturnNode) -> NumberResult;
-    fn travel_multi_assign(&mut self, node: &mut MultiAssignNode) -> NumberResult;
-    fn travel_malloc(&mut self, node: &mut MallocNode) -> NumberResult;
-
-    fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult;
+    // The default of every node visits its children in source order and
+    // returns nil, so a pass only overrides the nodes it inspects.
+    fn travel_function(&mut self, node: &mut FunctionNode) -> NumberResult {
+        for param in node.params.iter() {
+            self.travel(param)?;
+        }
+        self.travel(&node.block)
+    }
+    fn travel_block(&mut self, node: &mut BlockNode) -> NumberResult {
+        for declaration in node.declarations.iter() {
+            self.travel(declaration)?;
+        }
+        self.travel(&node.compound_statement)
+    }
+    fn travel_entry_block(&mut self, node: &mut EntryBlockNode) -> NumberResult {
+        for declaration in node.declarations.iter() {
+            self.travel(declaration)?;
+        }
+        self.travel(&node.compound_statement)
+    }
+    fn travel_declaration(&mut self, _node: &mut IdentDeclarationNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_type(&mut self, _node: &mut TypeNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_array_ident(&mut self, _node: &mut ArrayIdentNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_integer(&mut self, _node: &mut IntegerNumNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_felt(&mut self, _node: &mut FeltNumNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_bool(&mut self, node: &mut BoolNumNode) -> NumberResult {
+        Ok(Single(Bool(node.value)))
+    }
+    fn travel_string(&mut self, node: &mut StringNode) -> NumberResult {
+        Ok(Single(Str(node.value.clone())))
+    }
+    fn travel_array(&mut self, _node: &mut ArrayNumNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_binop(&mut self, node: &mut BinOpNode) -> NumberResult {
+        self.travel(&node.left)?;
+        self.travel(&node.right)?;
+        Ok(Single(Nil))
+    }
+    fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
+        self.travel(&node.expr)?;
+        Ok(Single(Nil))
+    }
+    fn travel_ternary(&mut self, node: &mut TernaryNode) -> NumberResult {
+        self.travel(&node.condition)?;
+        self.travel(&node.then_expr)?;
+        self.travel(&node.else_expr)?;
+        Ok(Single(Nil))
+    }
+    fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult {
+        for child in node.children.iter() {
+            self.travel(child)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_cond(&mut self, node: &mut CondStatNode) -> NumberResult {
+        for (condition, consequences) in node.branches() {
+            self.travel(condition)?;
+            for expr in consequences.iter() {
+                self.travel(expr)?;
+            }
+        }
+        for expr in node.alternatives.iter() {
+            self.travel(expr)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
+        self.travel(&node.condition)?;
+        if let Some(invariant) = &node.invariant {
+            self.travel(invariant)?;
+        }
+        for expr in node.consequences.iter() {
+            self.travel(expr)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_while(&mut self, node: &mut WhileStatNode) -> NumberResult {
+        self.travel(&node.condition)?;
+        for expr in node.consequences.iter() {
+            self.travel(expr)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_invariant(&mut self, node: &mut InvariantNode) -> NumberResult {
+        self.travel(&node.condition)?;
+        Ok(Single(Nil))
+    }
+    fn travel_break(&mut self, _node: &mut BreakNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_continue(&mut self, _node: &mut ContinueNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_ident(&mut self, _node: &mut IdentNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_ident_index(&mut self, node: &mut IdentIndexNode) -> NumberResult {
+        self.travel(&node.index)?;
+        Ok(Single(Nil))
+    }
+    fn travel_context_ident(&mut self, _node: &mut ContextIdentNode) -> NumberResult {
+        Ok(Single(Nil))
+    }
+    fn travel_assign(&mut self, node: &mut AssignNode) -> NumberResult {
+        if let Token::IndexId(_, index) = &node.identifier {
+            self.travel(index)?;
+        }
+        self.travel(&node.expr)?;
+        Ok(Single(Nil))
+    }
+    fn travel_entry(&mut self, node: &mut EntryNode) -> NumberResult {
+        for declaration in node.global_declarations.iter() {
+            self.travel(declaration)?;
+        }
+        self.travel(&node.entry_block)
+    }
+    fn travel_call(&mut self, node: &mut CallNode) -> NumberResult {
+        for param in node.actual_params.iter() {
+            self.travel(param)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_sqrt(&mut self, node: &mut SqrtNode) -> NumberResult {
+        self.travel(&node.sqrt_value)?;
+        Ok(Single(Nil))
+    }
+    fn travel_return(&mut self, node: &mut ReturnNode) -> NumberResult {
+        for ret in node.returns.iter() {
+            self.travel(ret)?;
+        }
+        Ok(Single(Nil))
+    }
+    fn travel_multi_assign(&mut self, node: &mut MultiAssignNode) -> NumberResult {
+        for ident in node.identifier.iter() {
+            self.travel(ident)?;
+        }
+        self.travel(&node.call)?;
+        Ok(Single(Nil))
+    }
+    fn travel_malloc(&mut self, node: &mut MallocNode) -> NumberResult {
+        self.travel(&node.num_bytes)?;
+        Ok(Single(Nil))
+    }
+    fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult {
+        self.travel(&node.val_addr)?;
+        self.travel(&node.flag)?;
+        Ok(Single(Nil))
+    }
 }
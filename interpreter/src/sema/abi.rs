@@ -0,0 +1,114 @@
+use crate::lexer::token::Token;
+use crate::parser::node::{EntryNode, FunctionNode, IdentDeclarationNode, TypeNode};
+use core::program::binary_program::OlaProphet;
+use ola_lang_abi::Abi;
+use serde_json::{json, Value};
+
+fn abi_type(token: &Token) -> Result<String, String> {
+    match token {
+        Token::Felt => Ok("field".to_string()),
+        Token::I32 => Ok("u32".to_string()),
+        Token::Array(_, 0) => Err(format!("type {} is an empty array", token)),
+        Token::Array(token, len) => Ok(format!("{}[{}]", abi_type(token)?, len)),
+        _ => Err(format!("type {} has no abi mapping", token)),
+    }
+}
+
+fn abi_function(node: &FunctionNode) -> Result<Value, String> {
+    function_signature(node).map_err(|err| {
+        format!(
+            "function '{}' cannot be called through the abi: {}",
+            node.func_name, err
+        )
+    })
+}
+
+fn function_signature(node: &FunctionNode) -> Result<Value, String> {
+    let mut inputs = Vec::new();
+    for param in node.params.iter() {
+        let param = param.read().unwrap();
+        let param = param
+            .as_any()
+            .downcast_ref::<IdentDeclarationNode>()
+            .ok_or("function param is not a declaration")?;
+        inputs.push(json!({
+            "name": param.ident_node.identifier.to_string(),
+            "type": abi_type(&param.type_node.token)?,
+        }));
+    }
+
+    let mut outputs = Vec::new();
+    for ret in node.returns.iter() {
+        let ret = ret.read().unwrap();
+        let ret = ret
+            .as_any()
+            .downcast_ref::<TypeNode>()
+            .ok_or("function return is not a type")?;
+        outputs.push(json!({
+            "name": "",
+            "type": abi_type(&ret.token)?,
+        }));
+    }
+
+    Ok(json!({
+        "name": node.func_name.to_string(),
+        "type": "function",
+        "inputs": inputs,
+        "outputs": outputs,
+    }))
+}
+
+// Abi type of a prophet value of `length` felts.
+fn prophet_type(length: usize) -> Result<String, String> {
+    match length {
+        0 => Err("empty values have no abi mapping".to_string()),
+        1 => abi_type(&Token::Felt),
+        len => abi_type(&Token::Array(Box::new(Token::Felt), len)),
+    }
+}
+
+/// Builds the abi of the entry point, whose inputs and outputs are those of
+/// the prophet.
+pub fn entry_abi(prophet: &OlaProphet) -> Result<Abi, String> {
+    let mut inputs = Vec::new();
+    for input in prophet.inputs.iter() {
+        let input_type = prophet_type(input.length)
+            .map_err(|err| format!("entry input '{}': {}", input.name, err))?;
+        inputs.push(json!({ "name": input.name, "type": input_type }));
+    }
+    let mut outputs = Vec::new();
+    for output in prophet.outputs.iter() {
+        let output_type = prophet_type(output.length)
+            .map_err(|err| format!("entry output '{}': {}", output.name, err))?;
+        outputs.push(json!({ "name": output.name, "type": output_type }));
+    }
+    let entry = json!({
+        "name": "entry",
+        "type": "function",
+        "inputs": inputs,
+        "outputs": outputs,
+    });
+    serde_json::from_value(Value::Array(vec![entry]))
+        .map_err(|err| format!("failed to build entry abi: {}", err))
+}
+
+/// Checks that the entry point and every declared function have signatures
+/// the abi can encode, so the program can actually be called once deployed.
+pub fn validate_abi_signatures(node: &EntryNode, prophet: &OlaProphet) -> Result<(), String> {
+    abi_from_entry(node)?;
+    entry_abi(prophet)?;
+    Ok(())
+}
+
+/// Builds an `Abi` from the functions declared by an analyzed program.
+pub fn abi_from_entry(node: &EntryNode) -> Result<Abi, String> {
+    let mut functions = Vec::new();
+    for declaration in node.global_declarations.iter() {
+        let declaration = declaration.read().unwrap();
+        if let Some(function) = declaration.as_any().downcast_ref::<FunctionNode>() {
+            functions.push(abi_function(function)?);
+        }
+    }
+    serde_json::from_value(Value::Array(functions))
+        .map_err(|err| format!("failed to build abi: {}", err))
+}
@@ -0,0 +1,99 @@
+use crate::lexer::token::Token;
+use crate::sema::symbol::BuiltIn;
+use crate::sema::symbol::Symbol::{FuncSymbol, IdentSymbol};
+use crate::sema::{ExpressionType, SymTableGen, TypeAnnotation};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+/// Everything the analyzer reports about a source file, in a form that can be
+/// cached between builds. The AST itself is not stored; it is cheap to parse
+/// again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisArtifact {
+    pub source_hash: u64,
+    /// Global symbols by name, e.g. `felt[4]` or `function(felt,i32)`.
+    pub symbols: BTreeMap<String, String>,
+    pub warnings: Vec<String>,
+    pub type_annotations: Vec<TypeAnnotation>,
+    pub expression_types: Vec<ExpressionType>,
+    pub call_graph: BTreeMap<String, BTreeSet<String>>,
+}
+
+// Only used to detect changed sources, so the hash does not need to be
+// stable across toolchains; a mismatch just means analyzing again.
+pub fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(crate) fn type_name(BuiltIn(token): &BuiltIn) -> String {
+    match token {
+        Token::Array(element, len) => format!("{}[{}]", type_name(&BuiltIn(*element.clone())), len),
+        _ => token.to_string().to_lowercase(),
+    }
+}
+
+impl AnalysisArtifact {
+    /// Collects the results of `gen` after it has analyzed `source`.
+    pub fn from_analysis(source: &str, gen: &SymTableGen) -> Self {
+        // Analysis ends inside the entry block scope; collect it and every
+        // enclosing scope, inner declarations shadowing outer ones.
+        let mut symbols = BTreeMap::new();
+        let mut scope = Some(gen.current_scope.clone());
+        while let Some(current) = scope {
+            let current = current.read().unwrap();
+            for symbol in current.symbols.values() {
+                let (name, description) = match symbol {
+                    IdentSymbol(name, builtin, Some(len), _) => {
+                        (name, format!("{}[{}]", type_name(builtin), len))
+                    }
+                    IdentSymbol(name, builtin, None, _) => (name, type_name(builtin)),
+                    FuncSymbol(name, params, _, _, _) => {
+                        let params: Vec<String> =
+                            params.iter().map(|(_, t)| type_name(t)).collect();
+                        (name, format!("function({})", params.join(",")))
+                    }
+                    _ => continue,
+                };
+                symbols.entry(name.clone()).or_insert(description);
+            }
+            scope = current.enclosing_scope.clone();
+        }
+        AnalysisArtifact {
+            source_hash: source_hash(source),
+            symbols,
+            warnings: gen.warnings.clone(),
+            type_annotations: gen.type_annotations.clone(),
+            expression_types: gen.expression_types.clone(),
+            call_graph: gen.call_graph.clone(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(self).map_err(|e| format!("failed to serialize artifact: {}", e))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        bincode::deserialize(bytes).map_err(|e| format!("failed to deserialize artifact: {}", e))
+    }
+
+    /// Loads a cached artifact, or `None` if it is unreadable or was built
+    /// from a different source.
+    pub fn load_for(bytes: &[u8], source: &str) -> Option<Self> {
+        Self::from_bytes(bytes)
+            .ok()
+            .filter(|artifact| artifact.source_hash == source_hash(source))
+    }
+
+    /// Puts the cached diagnostics and type information back into `gen`, as
+    /// if it had analyzed the source itself.
+    pub fn restore(self, gen: &mut SymTableGen) {
+        gen.warnings = self.warnings;
+        gen.type_annotations = self.type_annotations;
+        gen.expression_types = self.expression_types;
+        gen.call_graph = self.call_graph;
+    }
+}
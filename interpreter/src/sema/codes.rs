@@ -0,0 +1,143 @@
+/// A stable code for a kind of error reported by semantic analysis. Codes are
+/// never reused or renumbered, new kinds are appended to `ERROR_CODES`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub name: &'static str,
+    // Fragments identifying the messages of this kind.
+    patterns: &'static [&'static str],
+}
+
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: "E0001",
+        name: "undeclared-variable",
+        patterns: &["Undeclared variable"],
+    },
+    ErrorCode {
+        code: "E0002",
+        name: "type-mismatch",
+        patterns: &[
+            "has type",
+            "cannot receive the",
+            "to variable '",
+            "expects felt or i32 operands",
+            "cannot compare",
+            "expects bool operands",
+            "have different types",
+        ],
+    },
+    ErrorCode {
+        code: "E0003",
+        name: "duplicate-declaration",
+        patterns: &["duplicate variable declaration", "shadows the one in scope"],
+    },
+    ErrorCode {
+        code: "E0004",
+        name: "arity-mismatch",
+        patterns: &["targets are assigned", "args, got"],
+    },
+    ErrorCode {
+        code: "E0005",
+        name: "missing-return",
+        patterns: &["does not return a value on all paths"],
+    },
+    ErrorCode {
+        code: "E0006",
+        name: "array-too-long",
+        patterns: &["above the maximum of"],
+    },
+    ErrorCode {
+        code: "E0007",
+        name: "statement-budget",
+        patterns: &["above the budget of"],
+    },
+    ErrorCode {
+        code: "E0008",
+        name: "constant-overflow",
+        patterns: &["constant overflow", "out of range for field modulus"],
+    },
+    ErrorCode {
+        code: "E0009",
+        name: "strict-arithmetic",
+        patterns: &["strict mode requires"],
+    },
+    ErrorCode {
+        code: "E0010",
+        name: "loop-invariant",
+        patterns: &["loop invariant", "while condition"],
+    },
+    ErrorCode {
+        code: "E0011",
+        name: "abi-incompatible",
+        patterns: &[
+            "cannot be called through the abi",
+            "no abi mapping",
+            "is an empty array",
+        ],
+    },
+    ErrorCode {
+        code: "E0012",
+        name: "invalid-scope",
+        patterns: &["must be analyzed from the global scope"],
+    },
+    ErrorCode {
+        code: "E0013",
+        name: "invalid-identifier",
+        patterns: &["Invalid identifier found"],
+    },
+    ErrorCode {
+        code: "E0014",
+        name: "builtin-shadowing",
+        patterns: &["shadows the builtin type"],
+    },
+    ErrorCode {
+        code: "E0015",
+        name: "non-constant-format",
+        patterns: &["format flag"],
+    },
+    ErrorCode {
+        code: "E0016",
+        name: "malloc-region",
+        patterns: &["outside its malloc region"],
+    },
+    ErrorCode {
+        code: "E0017",
+        name: "jump-outside-loop",
+        patterns: &["is outside of a loop"],
+    },
+    ErrorCode {
+        code: "E0018",
+        name: "const-assignment",
+        patterns: &["cannot assign to const"],
+    },
+    ErrorCode {
+        code: "E0019",
+        name: "use-before-declaration",
+        patterns: &["before its declaration"],
+    },
+];
+
+/// Looks up a registered code, e.g. `E0001`.
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES.iter().find(|entry| entry.code == code)
+}
+
+/// Code of the error kind `message` was reported for, if it is registered.
+pub fn error_code(message: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES.iter().find(|entry| {
+        entry
+            .patterns
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    })
+}
+
+/// Formats an analysis error as a diagnostic, `error[E0001]: ...`, or as a
+/// plain `error: ...` for errors without a registered code.
+pub fn diagnostic(message: &str) -> String {
+    match error_code(message) {
+        Some(entry) => format!("error[{}]: {}", entry.code, message),
+        None => format!("error: {}", message),
+    }
+}
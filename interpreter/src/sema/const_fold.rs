@@ -0,0 +1,171 @@
+use crate::lexer::token::Token;
+use crate::parser::node::{
+    AssignNode, BinOpNode, CallNode, CondStatNode, FeltNumNode, IdentIndexNode, IntegerNumNode,
+    InvariantNode, LoopStatNode, MallocNode, Node, PrintfNode, ReturnNode, SqrtNode, UnaryOpNode,
+    WhileStatNode,
+};
+use crate::parser::traversal::{is_node_type, Traversal};
+use crate::utils::number::Number::{Felt, Nil, I32};
+use crate::utils::number::NumberRet::Single;
+use crate::utils::number::{parse_felt_literal, FieldParams, Number, NumberResult, OverflowMode};
+use std::sync::{Arc, RwLock};
+
+/// Replaces arithmetic over literals with the literal it evaluates to, e.g.
+/// `(2 + 3) * x` becomes `5 * x`. Expressions reading an identifier are left
+/// as they are, as are divisions by zero and i32 results that overflow, so
+/// they still fail where they did. The folded literal has the type
+/// `binop_number_type` gives the operation: i32 for two i32 operands, felt
+/// otherwise.
+///
+/// Each traversal method returns the constant value of the node, `Nil` when
+/// it is not constant, and the nodes holding expressions replace their
+/// constant children.
+#[derive(Debug, Default)]
+pub struct ConstFolder {
+    field_params: FieldParams,
+    /// Number of expressions replaced by a literal.
+    pub folded: usize,
+}
+
+impl ConstFolder {
+    pub fn new(field_params: FieldParams) -> Self {
+        ConstFolder {
+            field_params,
+            folded: 0,
+        }
+    }
+
+    fn literal(value: &Number) -> Option<Arc<RwLock<dyn Node>>> {
+        match value {
+            I32(value) => Some(Arc::new(RwLock::new(IntegerNumNode::new(*value)))),
+            Felt(value) => Some(Arc::new(RwLock::new(FeltNumNode::new(*value as u64)))),
+            _ => None,
+        }
+    }
+
+    // Travels the expression in `slot` and replaces it by its value if it is
+    // a constant other than a literal.
+    fn fold(&mut self, slot: &mut Arc<RwLock<dyn Node>>) -> NumberResult {
+        let ret = self.travel(slot)?;
+        if let Single(value) = &ret {
+            if is_node_type::<BinOpNode>(slot) || is_node_type::<UnaryOpNode>(slot) {
+                if let Some(literal) = Self::literal(value) {
+                    *slot = literal;
+                    self.folded += 1;
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    fn fold_all(&mut self, slots: &mut [Arc<RwLock<dyn Node>>]) -> NumberResult {
+        for slot in slots.iter_mut() {
+            self.fold(slot)?;
+        }
+        Ok(Single(Nil))
+    }
+}
+
+impl Traversal for ConstFolder {
+    fn travel_integer(&mut self, node: &mut IntegerNumNode) -> NumberResult {
+        Ok(Single(I32(node.value)))
+    }
+
+    fn travel_felt(&mut self, node: &mut FeltNumNode) -> NumberResult {
+        // Out of range literals are reported by the semantic analysis.
+        if parse_felt_literal(&node.literal) != Some(node.value)
+            || !self.field_params.in_range(node.value as i128)
+        {
+            return Ok(Single(Nil));
+        }
+        Ok(Single(Felt(node.value as i128)))
+    }
+
+    fn travel_binop(&mut self, node: &mut BinOpNode) -> NumberResult {
+        let left = self.fold(&mut node.left)?;
+        let right = self.fold(&mut node.right)?;
+        let value = match (left, right) {
+            (Single(left @ (I32(_) | Felt(_))), Single(right @ (I32(_) | Felt(_)))) => self
+                .field_params
+                .fold_binop(&left, &right, &node.operator, OverflowMode::Checked)
+                .filter(|value| matches!(value, I32(_) | Felt(_))),
+            _ => None,
+        };
+        Ok(Single(value.unwrap_or(Nil)))
+    }
+
+    fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
+        let value = match (&node.operator, self.fold(&mut node.expr)?) {
+            (Token::Plus, Single(value @ (I32(_) | Felt(_)))) => value,
+            (Token::Minus, Single(I32(value))) => value.checked_neg().map_or(Nil, I32),
+            (Token::Minus, Single(Felt(value))) => Felt(self.field_params.reduce(-value)),
+            _ => Nil,
+        };
+        Ok(Single(value))
+    }
+
+    fn travel_cond(&mut self, node: &mut CondStatNode) -> NumberResult {
+        self.fold(&mut node.condition)?;
+        self.fold_all(&mut node.consequences)?;
+        for (condition, consequences) in node.else_ifs.iter_mut() {
+            self.fold(condition)?;
+            self.fold_all(consequences)?;
+        }
+        self.fold_all(&mut node.alternatives)
+    }
+
+    fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
+        self.fold(&mut node.condition)?;
+        if let Some(invariant) = &node.invariant {
+            self.travel(invariant)?;
+        }
+        self.fold_all(&mut node.consequences)
+    }
+
+    fn travel_while(&mut self, node: &mut WhileStatNode) -> NumberResult {
+        self.fold(&mut node.condition)?;
+        self.fold_all(&mut node.consequences)
+    }
+
+    fn travel_invariant(&mut self, node: &mut InvariantNode) -> NumberResult {
+        self.fold(&mut node.condition)?;
+        Ok(Single(Nil))
+    }
+
+    fn travel_ident_index(&mut self, node: &mut IdentIndexNode) -> NumberResult {
+        self.fold(&mut node.index)?;
+        Ok(Single(Nil))
+    }
+
+    fn travel_assign(&mut self, node: &mut AssignNode) -> NumberResult {
+        if let Token::IndexId(_, index) = &mut node.identifier {
+            self.fold(index)?;
+        }
+        self.fold(&mut node.expr)?;
+        Ok(Single(Nil))
+    }
+
+    fn travel_call(&mut self, node: &mut CallNode) -> NumberResult {
+        self.fold_all(&mut node.actual_params)
+    }
+
+    fn travel_sqrt(&mut self, node: &mut SqrtNode) -> NumberResult {
+        self.fold(&mut node.sqrt_value)?;
+        Ok(Single(Nil))
+    }
+
+    fn travel_return(&mut self, node: &mut ReturnNode) -> NumberResult {
+        self.fold_all(&mut node.returns)
+    }
+
+    fn travel_malloc(&mut self, node: &mut MallocNode) -> NumberResult {
+        self.fold(&mut node.num_bytes)?;
+        Ok(Single(Nil))
+    }
+
+    fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult {
+        self.fold(&mut node.val_addr)?;
+        self.fold(&mut node.flag)?;
+        Ok(Single(Nil))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::lexer::token::Token;
+use crate::parser::node::{EntryNode, FunctionNode, IdentDeclarationNode, TypeNode};
+use crate::sema::artifact::type_name;
+use crate::sema::symbol::BuiltIn;
+use core::program::binary_program::OlaProphet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Old and new signature of something present in both programs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Semantic differences between two analyzed programs. Functions and globals
+/// are listed by signature, sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProgramDiff {
+    pub added_functions: Vec<String>,
+    pub removed_functions: Vec<String>,
+    pub changed_functions: Vec<SignatureChange>,
+    pub added_globals: Vec<String>,
+    pub removed_globals: Vec<String>,
+    pub changed_globals: Vec<SignatureChange>,
+    pub entry: Option<SignatureChange>,
+}
+
+impl ProgramDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == ProgramDiff::default()
+    }
+}
+
+impl fmt::Display for ProgramDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for signature in self.added_functions.iter() {
+            writeln!(f, "+ function {}", signature)?;
+        }
+        for signature in self.removed_functions.iter() {
+            writeln!(f, "- function {}", signature)?;
+        }
+        for change in self.changed_functions.iter() {
+            writeln!(f, "~ function {} => {}", change.old, change.new)?;
+        }
+        for signature in self.added_globals.iter() {
+            writeln!(f, "+ global {}", signature)?;
+        }
+        for signature in self.removed_globals.iter() {
+            writeln!(f, "- global {}", signature)?;
+        }
+        for change in self.changed_globals.iter() {
+            writeln!(f, "~ global {} => {}", change.old, change.new)?;
+        }
+        if let Some(change) = &self.entry {
+            writeln!(f, "~ {} => {}", change.old, change.new)?;
+        }
+        Ok(())
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    type_name(&BuiltIn(token.clone()))
+}
+
+// Source-like signature, e.g. `add(felt a, i32[2] b) -> felt`.
+fn function_signature(node: &FunctionNode) -> String {
+    let params: Vec<String> = node
+        .params
+        .iter()
+        .filter_map(|param| {
+            let param = param.read().unwrap();
+            let param = param.as_any().downcast_ref::<IdentDeclarationNode>()?;
+            Some(format!(
+                "{} {}",
+                token_text(&param.type_node.token),
+                param.ident_node.identifier
+            ))
+        })
+        .collect();
+    let returns: Vec<String> = node
+        .returns
+        .iter()
+        .filter_map(|ret| {
+            let ret = ret.read().unwrap();
+            let ret = ret.as_any().downcast_ref::<TypeNode>()?;
+            Some(token_text(&ret.token))
+        })
+        .collect();
+    let signature = format!("{}({})", node.func_name, params.join(", "));
+    match returns.len() {
+        0 => signature,
+        1 => format!("{} -> {}", signature, returns[0]),
+        _ => format!("{} -> ({})", signature, returns.join(", ")),
+    }
+}
+
+// Signatures of the functions and of the other global declarations, by name.
+fn global_signatures(node: &EntryNode) -> (BTreeMap<String, String>, BTreeMap<String, String>) {
+    let mut functions = BTreeMap::new();
+    let mut globals = BTreeMap::new();
+    for declaration in node.global_declarations.iter() {
+        let declaration = declaration.read().unwrap();
+        let declaration = declaration.as_any();
+        if let Some(function) = declaration.downcast_ref::<FunctionNode>() {
+            functions.insert(function.func_name.to_string(), function_signature(function));
+        } else if let Some(global) = declaration.downcast_ref::<IdentDeclarationNode>() {
+            let name = global.ident_node.identifier.to_string();
+            let signature = format!("{} {}", token_text(&global.type_node.token), name);
+            globals.insert(name, signature);
+        }
+    }
+    (functions, globals)
+}
+
+fn prophet_type(length: usize) -> String {
+    match length {
+        1 => token_text(&Token::Felt),
+        len => token_text(&Token::Array(Box::new(Token::Felt), len)),
+    }
+}
+
+// The entry point takes the prophet inputs and produces its outputs.
+fn entry_signature(prophet: &OlaProphet) -> String {
+    let inputs: Vec<String> = prophet
+        .inputs
+        .iter()
+        .map(|input| format!("{} {}", prophet_type(input.length), input.name))
+        .collect();
+    let outputs: Vec<String> = prophet
+        .outputs
+        .iter()
+        .map(|output| format!("{} {}", prophet_type(output.length), output.name))
+        .collect();
+    format!("entry({}) -> ({})", inputs.join(", "), outputs.join(", "))
+}
+
+// Returns the added and removed signatures and the changed ones.
+fn diff_maps(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> (Vec<String>, Vec<String>, Vec<SignatureChange>) {
+    let added = new
+        .iter()
+        .filter(|(name, _)| !old.contains_key(*name))
+        .map(|(_, signature)| signature.clone())
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|(name, _)| !new.contains_key(*name))
+        .map(|(_, signature)| signature.clone())
+        .collect();
+    let changed = old
+        .iter()
+        .filter_map(|(name, old)| {
+            let new = new.get(name)?;
+            (old != new).then(|| SignatureChange {
+                name: name.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            })
+        })
+        .collect();
+    (added, removed, changed)
+}
+
+/// Compares two programs, each with the prophet its entry point is called
+/// with.
+pub fn diff_programs(
+    old: &EntryNode,
+    old_prophet: &OlaProphet,
+    new: &EntryNode,
+    new_prophet: &OlaProphet,
+) -> ProgramDiff {
+    let (old_functions, old_globals) = global_signatures(old);
+    let (new_functions, new_globals) = global_signatures(new);
+    let (added_functions, removed_functions, changed_functions) =
+        diff_maps(&old_functions, &new_functions);
+    let (added_globals, removed_globals, changed_globals) = diff_maps(&old_globals, &new_globals);
+    let (old_entry, new_entry) = (entry_signature(old_prophet), entry_signature(new_prophet));
+    let entry = (old_entry != new_entry).then(|| SignatureChange {
+        name: "entry".to_string(),
+        old: old_entry,
+        new: new_entry,
+    });
+    ProgramDiff {
+        added_functions,
+        removed_functions,
+        changed_functions,
+        added_globals,
+        removed_globals,
+        changed_globals,
+        entry,
+    }
+}
@@ -0,0 +1,128 @@
+use crate::sema::codes::{error_code, lookup, ErrorCode};
+use std::fmt;
+
+/// Error of a traversal. The kinds callers most often handle are structured,
+/// every other error is kept as its message. `Display` gives the message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemaError {
+    /// `name` is used without being declared. `context` is the kind of use
+    /// and `suggestion` a declared name it is likely a typo of.
+    UndeclaredVariable {
+        name: String,
+        context: String,
+        suggestion: Option<String>,
+    },
+    /// `name` is declared twice in the same scope.
+    DuplicateDeclaration {
+        name: String,
+    },
+    /// A value of type `got` where `expected` is required, `message` saying
+    /// where.
+    TypeMismatch {
+        expected: String,
+        got: String,
+        message: String,
+    },
+    /// `name` is declared const and assigned after its initializer.
+    ConstAssignment {
+        name: String,
+    },
+    /// `func` takes `expected` args and is called with `got`, or returns
+    /// `expected` values to `got` targets.
+    ArityMismatch {
+        func: String,
+        expected: usize,
+        got: usize,
+        arity: Arity,
+    },
+    Other(String),
+}
+
+/// What an arity mismatch counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Args,
+    Returns,
+}
+
+impl SemaError {
+    pub fn type_mismatch(expected: &str, got: &str, message: String) -> Self {
+        SemaError::TypeMismatch {
+            expected: expected.to_string(),
+            got: got.to_string(),
+            message,
+        }
+    }
+
+    /// Registered code of the error, the structured kinds having fixed ones.
+    pub fn code(&self) -> Option<&'static ErrorCode> {
+        match self {
+            SemaError::UndeclaredVariable { .. } => lookup("E0001"),
+            SemaError::TypeMismatch { .. } => lookup("E0002"),
+            SemaError::DuplicateDeclaration { .. } => lookup("E0003"),
+            SemaError::ConstAssignment { .. } => lookup("E0018"),
+            SemaError::ArityMismatch { .. } => lookup("E0004"),
+            SemaError::Other(message) => error_code(message),
+        }
+    }
+
+    /// Formats the error like [`diagnostic`], with the code of the kind.
+    ///
+    /// [`diagnostic`]: crate::sema::codes::diagnostic
+    pub fn diagnostic(&self) -> String {
+        match self.code() {
+            Some(entry) => format!("error[{}]: {}", entry.code, self),
+            None => format!("error: {}", self),
+        }
+    }
+}
+
+impl fmt::Display for SemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemaError::UndeclaredVariable {
+                name,
+                context,
+                suggestion,
+            } => {
+                write!(f, "{} Undeclared variable {} found.", context, name)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, " Did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            SemaError::DuplicateDeclaration { name } => {
+                write!(f, "Found duplicate variable declaration for '{}'!", name)
+            }
+            SemaError::ConstAssignment { name } => write!(f, "cannot assign to const '{}'", name),
+            SemaError::TypeMismatch { message, .. } => write!(f, "{}", message),
+            SemaError::ArityMismatch {
+                func,
+                expected,
+                got,
+                arity: Arity::Args,
+            } => write!(
+                f,
+                "function '{}' expects {} args, got {}",
+                func, expected, got
+            ),
+            SemaError::ArityMismatch {
+                func,
+                expected,
+                got,
+                arity: Arity::Returns,
+            } => write!(
+                f,
+                "function '{}' returns {} values, {} targets are assigned",
+                func, expected, got
+            ),
+            SemaError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for SemaError {
+    fn from(message: String) -> Self {
+        SemaError::Other(message)
+    }
+}
@@ -0,0 +1,206 @@
+use crate::lexer::token::Token::{ArrayId, Id, IndexId};
+use crate::parser::node::{
+    ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode, CondStatNode,
+    EntryBlockNode, EntryNode, FeltNumNode, FunctionNode, IdentIndexNode, IdentNode,
+    IntegerNumNode, LoopStatNode, MallocNode, MultiAssignNode, Node, PrintfNode, ReturnNode,
+    SqrtNode, UnaryOpNode, WhileStatNode,
+};
+use crate::sema::SymTableGen;
+use crate::utils::number::Number;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+// Statements of a function body or of the entry block, in execution order.
+pub(crate) fn body_statements(node: &Arc<RwLock<dyn Node>>) -> Vec<Arc<RwLock<dyn Node>>> {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    let (declarations, compound) = if let Some(block) = node.downcast_ref::<BlockNode>() {
+        (&block.declarations, &block.compound_statement)
+    } else if let Some(block) = node.downcast_ref::<EntryBlockNode>() {
+        (&block.declarations, &block.compound_statement)
+    } else {
+        return Vec::new();
+    };
+    let mut statements = declarations.clone();
+    let compound = compound.read().unwrap();
+    if let Some(compound) = compound.as_any().downcast_ref::<CompoundNode>() {
+        statements.extend(compound.children.iter().cloned());
+    }
+    statements
+}
+
+// Name and elements of an assignment of an array literal to a whole array.
+// Indexing a multi-dimensional array selects a row, which is not folded.
+fn literal_assignment(node: &Arc<RwLock<dyn Node>>) -> Option<(String, Vec<Number>)> {
+    let node = node.read().unwrap();
+    let assign = node.as_any().downcast_ref::<AssignNode>()?;
+    let name = match &assign.identifier {
+        Id(name) | ArrayId(name) => name.clone(),
+        _ => return None,
+    };
+    let expr = assign.expr.read().unwrap();
+    let array = expr.as_any().downcast_ref::<ArrayNumNode>()?;
+    if !array.rows.is_empty() {
+        return None;
+    }
+    Some((name, array.values.clone()))
+}
+
+// Counts the writes to every variable in `node`, element writes included.
+fn count_writes(node: &Arc<RwLock<dyn Node>>, writes: &mut BTreeMap<String, usize>) {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if let Some(assign) = node.downcast_ref::<AssignNode>() {
+        if let Id(name) | ArrayId(name) | IndexId(name, _) = &assign.identifier {
+            *writes.entry(name.clone()).or_default() += 1;
+        }
+    } else if let Some(assign) = node.downcast_ref::<MultiAssignNode>() {
+        for ident in assign.identifier.iter() {
+            let ident = ident.read().unwrap();
+            if let Some(ident) = ident.as_any().downcast_ref::<IdentNode>() {
+                *writes.entry(ident.identifier.to_string()).or_default() += 1;
+            }
+        }
+    } else if let Some(compound) = node.downcast_ref::<CompoundNode>() {
+        for child in compound.children.iter() {
+            count_writes(child, writes);
+        }
+    } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+        for child in cond.branch_statements() {
+            count_writes(child, writes);
+        }
+    } else if let Some(lp) = node.downcast_ref::<LoopStatNode>() {
+        for child in lp.consequences.iter() {
+            count_writes(child, writes);
+        }
+    } else if let Some(lp) = node.downcast_ref::<WhileStatNode>() {
+        for child in lp.consequences.iter() {
+            count_writes(child, writes);
+        }
+    }
+}
+
+struct ArrayFolder<'a> {
+    gen: &'a SymTableGen,
+    constants: BTreeMap<String, Vec<Number>>,
+    folded: usize,
+}
+
+impl ArrayFolder<'_> {
+    // Literal the access in `slot` folds to, if any.
+    fn folded_value(&self, slot: &Arc<RwLock<dyn Node>>) -> Option<Arc<RwLock<dyn Node>>> {
+        let node = slot.read().unwrap();
+        let access = node.as_any().downcast_ref::<IdentIndexNode>()?;
+        let values = self.constants.get(&access.identifier.to_string())?;
+        let index = match self.gen.const_eval(&access.index)? {
+            Number::I32(index) => usize::try_from(index).ok()?,
+            Number::Felt(index) => usize::try_from(index).ok()?,
+            _ => return None,
+        };
+        match values.get(index)? {
+            Number::I32(value) => Some(Arc::new(RwLock::new(IntegerNumNode::new(*value)))),
+            Number::Felt(value) => Some(Arc::new(RwLock::new(FeltNumNode::new(*value as u64)))),
+            _ => None,
+        }
+    }
+
+    fn fold_all(&mut self, slots: &mut [Arc<RwLock<dyn Node>>]) {
+        for slot in slots.iter_mut() {
+            self.fold(slot);
+        }
+    }
+
+    fn fold(&mut self, slot: &mut Arc<RwLock<dyn Node>>) {
+        if let Some(value) = self.folded_value(slot) {
+            *slot = value;
+            self.folded += 1;
+            return;
+        }
+        let mut node = slot.write().unwrap();
+        let node = node.as_any_mut();
+        if let Some(binop) = node.downcast_mut::<BinOpNode>() {
+            self.fold(&mut binop.left);
+            self.fold(&mut binop.right);
+        } else if let Some(unary) = node.downcast_mut::<UnaryOpNode>() {
+            self.fold(&mut unary.expr);
+        } else if let Some(access) = node.downcast_mut::<IdentIndexNode>() {
+            self.fold(&mut access.index);
+        } else if let Some(assign) = node.downcast_mut::<AssignNode>() {
+            self.fold(&mut assign.expr);
+        } else if let Some(assign) = node.downcast_mut::<MultiAssignNode>() {
+            self.fold_all(&mut assign.expr);
+            self.fold(&mut assign.call);
+        } else if let Some(call) = node.downcast_mut::<CallNode>() {
+            self.fold_all(&mut call.actual_params);
+        } else if let Some(compound) = node.downcast_mut::<CompoundNode>() {
+            self.fold_all(&mut compound.children);
+        } else if let Some(cond) = node.downcast_mut::<CondStatNode>() {
+            self.fold(&mut cond.condition);
+            self.fold_all(&mut cond.consequences);
+            for (condition, consequences) in cond.else_ifs.iter_mut() {
+                self.fold(condition);
+                self.fold_all(consequences);
+            }
+            self.fold_all(&mut cond.alternatives);
+        } else if let Some(lp) = node.downcast_mut::<LoopStatNode>() {
+            self.fold(&mut lp.condition);
+            self.fold_all(&mut lp.consequences);
+        } else if let Some(lp) = node.downcast_mut::<WhileStatNode>() {
+            self.fold(&mut lp.condition);
+            self.fold_all(&mut lp.consequences);
+        } else if let Some(sqrt) = node.downcast_mut::<SqrtNode>() {
+            self.fold(&mut sqrt.sqrt_value);
+        } else if let Some(ret) = node.downcast_mut::<ReturnNode>() {
+            self.fold_all(&mut ret.returns);
+        } else if let Some(malloc) = node.downcast_mut::<MallocNode>() {
+            self.fold(&mut malloc.num_bytes);
+        } else if let Some(printf) = node.downcast_mut::<PrintfNode>() {
+            self.fold(&mut printf.val_addr);
+            self.fold(&mut printf.flag);
+        }
+    }
+
+    // An array is constant from its literal assignment on, as long as that
+    // assignment is a top-level statement of the body and the only write to
+    // the array.
+    fn fold_body(&mut self, body: &Arc<RwLock<dyn Node>>) {
+        let mut statements = body_statements(body);
+        let mut writes = BTreeMap::new();
+        for statement in statements.iter() {
+            count_writes(statement, &mut writes);
+        }
+        self.constants.clear();
+        for statement in statements.iter_mut() {
+            self.fold(statement);
+            if let Some((name, values)) = literal_assignment(statement) {
+                if writes.get(&name) == Some(&1) {
+                    self.constants.insert(name, values);
+                }
+            }
+        }
+    }
+}
+
+/// Replaces index accesses of constant arrays with the accessed element.
+/// An array is constant once it has been assigned an array literal it is
+/// never reassigned after, element writes included, and the index must be a
+/// constant expression in bounds. Runs on the analyzed entry node and returns
+/// the number of folded accesses.
+pub fn fold_constant_arrays(gen: &SymTableGen, root: &Arc<RwLock<dyn Node>>) -> usize {
+    let mut folder = ArrayFolder {
+        gen,
+        constants: BTreeMap::new(),
+        folded: 0,
+    };
+    let root = root.read().unwrap();
+    if let Some(entry) = root.as_any().downcast_ref::<EntryNode>() {
+        for declaration in entry.global_declarations.iter() {
+            let declaration = declaration.read().unwrap();
+            if let Some(function) = declaration.as_any().downcast_ref::<FunctionNode>() {
+                folder.fold_body(&function.block);
+            }
+        }
+        folder.fold_body(&entry.entry_block);
+    }
+    folder.folded
+}
@@ -1,80 +1,1990 @@
 use crate::lexer::token::Token;
-use crate::lexer::token::Token::{Array, ArrayId, Cid, Felt, Id};
+use crate::lexer::token::Token::{Array, ArrayId, Cid, Felt, Id, IndexId};
 use crate::parser::node::{
-    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode,
-    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode,
-    IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode,
-    MultiAssignNode, Node, PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, BoolNumNode, BreakNode,
+    CallNode, CompoundNode, CondStatNode, ContextIdentNode, ContinueNode, EntryBlockNode,
+    EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode,
+    IntegerNumNode, InvariantNode, LoopStatNode, MallocNode, MultiAssignNode, Node, PrintfNode,
+    ReturnNode, SqrtNode, StringNode, TernaryNode, TypeNode, UnaryOpNode, WhileStatNode,
 };
-use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
+use crate::parser::traversal::{is_node_type, Traversal};
+use crate::sema::artifact::type_name;
+use crate::sema::error::{Arity, SemaError};
+use crate::sema::fold::body_statements;
+use crate::sema::naming::{NamingConvention, NamingStyle};
 use crate::sema::symbol::Symbol::{BuiltInSymbol, FuncSymbol, IdentSymbol};
-use crate::sema::symbol::{BuiltIn, SymbolTable};
+use crate::sema::symbol::{BuiltIn, Symbol, SymbolTable, BUILTIN_FUNCTIONS};
+use crate::sema::tail::mark_tail_calls;
 use crate::utils::number::Number::Nil;
 use crate::utils::number::NumberRet::{Multiple, Single};
-use crate::utils::number::{number_from_token, Number, NumberResult};
+use crate::utils::number::{
+    literal_bits, number_from_token, FieldParams, Number, NumberResult, NumberRet, OverflowMode,
+    GOLDILOCKS_PRIME,
+};
 use core::program::binary_program::OlaProphet;
-use log::debug;
-use std::collections::HashMap;
+use core::types::{Field, GoldilocksField};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use std::panic;
+use std::str::FromStr;
 use std::sync::{Arc, RwLock};
+use std::thread;
+
+pub mod abi;
+pub mod artifact;
+pub mod codes;
+pub mod const_fold;
+pub mod diff;
+pub mod error;
+pub mod fold;
+pub mod naming;
+pub mod pass;
+pub mod symbol;
+pub mod tail;
+
+#[macro_export]
+macro_rules! inf_var_insert {
+    ($input: tt, $current_scope: tt) => {
+        if $input.dims == [1] {
+            let variable = IdentSymbol($input.name.to_string(), BuiltIn(Felt), None, false);
+            $current_scope.insert(variable);
+        } else {
+            // The innermost dimension is the last one.
+            let token = $input
+                .dims
+                .iter()
+                .rev()
+                .fold(Felt, |token, len| Array(Box::new(token), *len));
+            let variable = IdentSymbol($input.name.to_string(), BuiltIn(token), None, false);
+            $current_scope.insert(variable);
+        }
+    };
+}
+
+/// A prophet input or output seeded into the global scope: a felt, or a felt
+/// array when `length` is above 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalVariable {
+    pub name: String,
+    /// Number of felts, across every dimension.
+    pub length: usize,
+    /// Dimensions, outermost first, `[length]` for a felt or a flat array.
+    pub dims: Vec<usize>,
+}
+
+impl GlobalVariable {
+    pub fn new(name: &str, length: usize) -> Self {
+        GlobalVariable {
+            name: name.to_string(),
+            length,
+            dims: vec![length],
+        }
+    }
+
+    /// A multi-dimensional felt array, e.g. `&[2, 3]` for two rows of three.
+    pub fn array(name: &str, dims: &[usize]) -> Self {
+        GlobalVariable {
+            name: name.to_string(),
+            length: dims.iter().product(),
+            dims: dims.to_vec(),
+        }
+    }
+}
+
+/// Default upper bound for declared array lengths.
+pub const DEFAULT_MAX_ARRAY_LENGTH: usize = 1 << 20;
+
+/// Default number of statements a single function body may contain.
+pub const DEFAULT_FUNCTION_STATEMENT_BUDGET: usize = 2048;
+
+/// Default number of statements a whole program may contain.
+pub const DEFAULT_TOTAL_STATEMENT_BUDGET: usize = 16384;
+
+/// Scope holding the prophet inputs, outputs and ctx values.
+pub const PROPHET_SCOPE_LEVEL: u32 = 1;
+/// Scope holding the global declarations, nested in the prophet scope.
+pub const GLOBAL_SCOPE_LEVEL: u32 = 2;
+/// Scope of the entry block and of every function body, nested in the global
+/// scope.
+pub const LOCAL_SCOPE_LEVEL: u32 = 3;
+
+#[derive(Clone)]
+pub struct SemaOptions {
+    /// Warn when identifiers in the same scope differ only by letter case.
+    pub case_collision_lint: bool,
+    /// Field used to validate felt constants and fold felt arithmetic.
+    pub field_params: FieldParams,
+    /// Analyze function bodies in parallel once all global symbols are
+    /// registered. Functions may then call functions declared after them.
+    pub parallel_functions: bool,
+    /// How overflowing i32 constant arithmetic is folded. In checked mode it
+    /// is an error.
+    pub overflow_mode: OverflowMode,
+    /// Record the inferred type of every expression, for `dump_types`.
+    pub dump_types: TypeDump,
+    /// Warn about array lengths and literal array indices above this value,
+    /// which are better written as named consts. `None` disables the lint.
+    pub magic_number_threshold: Option<i128>,
+    /// Reject felt expressions as array indices and felt literals as array
+    /// lengths instead of coercing them.
+    pub strict_arithmetic: bool,
+    /// Largest array length a declaration may use.
+    pub max_array_length: usize,
+    /// Most statements a function body, or the entry block, may contain.
+    /// Nested statements count too.
+    pub function_statement_budget: usize,
+    /// Most statements the whole program may contain.
+    pub total_statement_budget: usize,
+    /// What to do when a declaration reuses a name declared in an enclosing
+    /// scope.
+    pub shadowing: ShadowPolicy,
+    /// Naming style lint for declared names, off for every kind by default.
+    pub naming_convention: NamingConvention,
+    /// Note felt literals equal to special field elements, such as p-1 or
+    /// the multiplicative group generator. Small decimal literals are i32
+    /// constants, so 0, 1 and 7 are only noted when written in hex.
+    pub special_constant_notes: bool,
+    /// Declarations left out of `SymTableGen::unused_symbols`.
+    pub unused_exclusions: UnusedExclusions,
+    /// Reject felt constant arithmetic whose exact result falls outside the
+    /// Goldilocks field and silently wraps around.
+    pub felt_overflow_check: bool,
+    /// Reject references to a variable that come before its declaration in
+    /// the source, such as a function body reading a global declared after
+    /// the function.
+    pub declaration_order: bool,
+    /// Memoize in each scope the variables of enclosing scopes its
+    /// identifiers resolve to. Only turned off to measure the cache.
+    pub resolution_cache: bool,
+}
+
+impl Default for SemaOptions {
+    fn default() -> Self {
+        SemaOptions {
+            case_collision_lint: false,
+            field_params: FieldParams::default(),
+            parallel_functions: false,
+            overflow_mode: OverflowMode::default(),
+            dump_types: TypeDump::Off,
+            magic_number_threshold: None,
+            strict_arithmetic: false,
+            max_array_length: DEFAULT_MAX_ARRAY_LENGTH,
+            function_statement_budget: DEFAULT_FUNCTION_STATEMENT_BUDGET,
+            total_statement_budget: DEFAULT_TOTAL_STATEMENT_BUDGET,
+            shadowing: ShadowPolicy::default(),
+            naming_convention: NamingConvention::default(),
+            special_constant_notes: false,
+            unused_exclusions: UnusedExclusions::default(),
+            felt_overflow_check: false,
+            declaration_order: false,
+            resolution_cache: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ShadowPolicy {
+    /// Reject the declaration.
+    #[default]
+    Deny,
+    /// Warn and declare the new variable, hiding the outer one.
+    WarnLog,
+    /// Declare the new variable, hiding the outer one.
+    Allow,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TypeDump {
+    #[default]
+    Off,
+    Text,
+    Json,
+}
+
+impl FromStr for TypeDump {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(TypeDump::Off),
+            "text" => Ok(TypeDump::Text),
+            "json" => Ok(TypeDump::Json),
+            _ => Err(format!(
+                "unknown type dump format '{}', expected off, text or json",
+                s
+            )),
+        }
+    }
+}
+
+struct FunctionScope {
+    name: String,
+    params: Vec<Symbol>,
+    array_params: Vec<String>,
+    returns_value: bool,
+    block: Arc<RwLock<dyn Node>>,
+}
+
+// Declared type of a variable symbol, arrays as their full array type.
+fn symbol_type(symbol: Symbol) -> Option<Token> {
+    match symbol {
+        IdentSymbol(_, BuiltIn(token), Some(len), _) => Some(Array(Box::new(token), len)),
+        IdentSymbol(_, BuiltIn(token), None, _) => Some(token),
+        _ => None,
+    }
+}
+
+// Names the notable elements of the field with the given modulus. The
+// generator and root of unity are only known for the Goldilocks field.
+fn special_field_value(value: u64, modulus: u64) -> Option<&'static str> {
+    match value {
+        0 => Some("zero"),
+        1 => Some("one"),
+        value if value == modulus - 1 => Some("p-1, the field element -1"),
+        _ if modulus != GOLDILOCKS_PRIME => None,
+        value if value == GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR.0 => {
+            Some("the multiplicative group generator")
+        }
+        value if value == GoldilocksField::POWER_OF_TWO_GENERATOR.0 => {
+            Some("the primitive 2^32-th root of unity")
+        }
+        _ => None,
+    }
+}
+
+fn number_ret(mut values: Vec<Number>) -> NumberRet {
+    match values.len() {
+        0 => Single(Nil),
+        1 => Single(values.remove(0)),
+        _ => Multiple(values),
+    }
+}
+
+// Value of a call, typed after the declared return types of the function.
+fn declared_return(returns: &[BuiltIn]) -> NumberRet {
+    number_ret(returns.iter().map(|ret| Number::from(&ret.0)).collect())
+}
+
+// Merges the types returned by two return statements of `function`. A felt
+// absorbs an i32, as in arithmetic.
+fn merge_return_types(
+    function: &str,
+    previous: Vec<Number>,
+    current: Vec<Number>,
+) -> Result<Vec<Number>, String> {
+    if previous.len() != current.len() {
+        return Err(format!(
+            "function '{}' returns {} values, {} at an earlier return",
+            function,
+            current.len(),
+            previous.len()
+        ));
+    }
+    previous
+        .into_iter()
+        .zip(current)
+        .enumerate()
+        .map(|(index, types)| match types {
+            (Number::I32(_), Number::Felt(_)) | (Number::Felt(_), Number::I32(_)) => {
+                Ok(Number::Felt(0))
+            }
+            (previous, current) if mem::discriminant(&previous) == mem::discriminant(&current) => {
+                Ok(previous)
+            }
+            (previous, current) => Err(format!(
+                "function '{}' returns {} at position {}, {} at an earlier return",
+                function,
+                number_type_name(&current),
+                index,
+                number_type_name(&previous)
+            )),
+        })
+        .collect()
+}
+
+// Whether every control-flow path through `node` ends in a return. A loop
+// body may not run at all, so loops never count as terminating.
+fn always_returns(node: &Arc<RwLock<dyn Node>>) -> bool {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if node.is::<ReturnNode>() {
+        true
+    } else if let Some(block) = node.downcast_ref::<BlockNode>() {
+        always_returns(&block.compound_statement)
+    } else if let Some(compound) = node.downcast_ref::<CompoundNode>() {
+        compound.children.iter().any(always_returns)
+    } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+        cond.branches()
+            .iter()
+            .all(|(_, body)| body.iter().any(always_returns))
+            && cond.alternatives.iter().any(always_returns)
+    } else {
+        false
+    }
+}
+
+// Number of statements in `node`, declarations and the statements nested in
+// conditions and loops included.
+fn count_statements(node: &Arc<RwLock<dyn Node>>) -> usize {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if let Some(block) = node.downcast_ref::<BlockNode>() {
+        block.declarations.len() + count_statements(&block.compound_statement)
+    } else if let Some(block) = node.downcast_ref::<EntryBlockNode>() {
+        block.declarations.len() + count_statements(&block.compound_statement)
+    } else if let Some(compound) = node.downcast_ref::<CompoundNode>() {
+        compound.children.iter().map(count_statements).sum()
+    } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+        1 + cond
+            .branch_statements()
+            .map(count_statements)
+            .sum::<usize>()
+    } else if let Some(lp) = node.downcast_ref::<LoopStatNode>() {
+        1 + lp.consequences.iter().map(count_statements).sum::<usize>()
+    } else if let Some(lp) = node.downcast_ref::<WhileStatNode>() {
+        1 + lp.consequences.iter().map(count_statements).sum::<usize>()
+    } else {
+        1
+    }
+}
+
+/// Resolved type of one identifier occurrence in the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeAnnotation {
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+    pub type_name: String,
+}
+
+/// Inferred type of one expression. The position is that of the leftmost
+/// identifier in the expression, (0, 0) if it has none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpressionType {
+    pub line: usize,
+    pub column: usize,
+    pub expression: String,
+    pub type_name: String,
+}
+
+fn is_comparison(operator: &Token) -> bool {
+    matches!(
+        operator,
+        Token::Equal
+            | Token::NotEqual
+            | Token::LessThan
+            | Token::LessEqual
+            | Token::GreaterThan
+            | Token::GreaterEqual
+    )
+}
+
+fn is_arithmetic(operator: &Token) -> bool {
+    matches!(
+        operator,
+        Token::Plus | Token::Minus | Token::Multiply | Token::IntegerDivision | Token::Mod
+    )
+}
+
+fn number_type_name(number: &Number) -> &'static str {
+    match number {
+        Number::Nil => "nil",
+        Number::I32(_) => "i32",
+        Number::Felt(_) => "felt",
+        Number::Bool(_) => "bool",
+        Number::Str(_) => "string",
+    }
+}
+
+// Short source-like rendering of an expression, for type dumps.
+fn expression_text(node: &Arc<RwLock<dyn Node>>) -> String {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if let Some(ident) = node.downcast_ref::<IdentNode>() {
+        ident.identifier.to_string()
+    } else if let Some(ident) = node.downcast_ref::<IdentIndexNode>() {
+        format!("{}[{}]", ident.identifier, expression_text(&ident.index))
+    } else if let Some(num) = node.downcast_ref::<IntegerNumNode>() {
+        num.value.to_string()
+    } else if let Some(num) = node.downcast_ref::<FeltNumNode>() {
+        num.literal.clone()
+    } else if let Some(num) = node.downcast_ref::<BoolNumNode>() {
+        num.value.to_string()
+    } else if let Some(string) = node.downcast_ref::<StringNode>() {
+        format!("{:?}", string.value)
+    } else if let Some(binop) = node.downcast_ref::<BinOpNode>() {
+        format!(
+            "({} {} {})",
+            expression_text(&binop.left),
+            binop.operator,
+            expression_text(&binop.right)
+        )
+    } else if let Some(unary) = node.downcast_ref::<UnaryOpNode>() {
+        format!("{}{}", unary.operator, expression_text(&unary.expr))
+    } else if let Some(ternary) = node.downcast_ref::<TernaryNode>() {
+        format!(
+            "({} ? {} : {})",
+            expression_text(&ternary.condition),
+            expression_text(&ternary.then_expr),
+            expression_text(&ternary.else_expr)
+        )
+    } else {
+        "..".to_string()
+    }
+}
+
+// Whether two side-effect free expressions are structurally identical.
+// Calls are never pure, so expressions containing one never match.
+fn same_expression(left: &Arc<RwLock<dyn Node>>, right: &Arc<RwLock<dyn Node>>) -> bool {
+    let left = left.read().unwrap();
+    let right = right.read().unwrap();
+    let (left, right) = (left.as_any(), right.as_any());
+    if let (Some(l), Some(r)) = (
+        left.downcast_ref::<IdentNode>(),
+        right.downcast_ref::<IdentNode>(),
+    ) {
+        l.identifier == r.identifier
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<IdentIndexNode>(),
+        right.downcast_ref::<IdentIndexNode>(),
+    ) {
+        l.identifier == r.identifier && same_expression(&l.index, &r.index)
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<ContextIdentNode>(),
+        right.downcast_ref::<ContextIdentNode>(),
+    ) {
+        l.identifier == r.identifier
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<IntegerNumNode>(),
+        right.downcast_ref::<IntegerNumNode>(),
+    ) {
+        l.value == r.value
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<FeltNumNode>(),
+        right.downcast_ref::<FeltNumNode>(),
+    ) {
+        l.value == r.value
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<BinOpNode>(),
+        right.downcast_ref::<BinOpNode>(),
+    ) {
+        l.operator == r.operator
+            && same_expression(&l.left, &r.left)
+            && same_expression(&l.right, &r.right)
+    } else if let (Some(l), Some(r)) = (
+        left.downcast_ref::<UnaryOpNode>(),
+        right.downcast_ref::<UnaryOpNode>(),
+    ) {
+        l.operator == r.operator && same_expression(&l.expr, &r.expr)
+    } else {
+        false
+    }
+}
+
+// Collects the names of the variables `node` reads or writes, nested
+// statements included, and whether it calls a function.
+fn mentioned_names(node: &Arc<RwLock<dyn Node>>, names: &mut BTreeSet<String>) -> bool {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    let mut children: Vec<&Arc<RwLock<dyn Node>>> = Vec::new();
+    let mut has_call = false;
+    if let Some(ident) = node.downcast_ref::<IdentNode>() {
+        names.insert(ident.identifier.to_string());
+    } else if let Some(ident) = node.downcast_ref::<ContextIdentNode>() {
+        names.insert(ident.identifier.to_string());
+    } else if let Some(access) = node.downcast_ref::<IdentIndexNode>() {
+        names.insert(access.identifier.to_string());
+        children.push(&access.index);
+    } else if let Some(binop) = node.downcast_ref::<BinOpNode>() {
+        children.extend([&binop.left, &binop.right]);
+    } else if let Some(unary) = node.downcast_ref::<UnaryOpNode>() {
+        children.push(&unary.expr);
+    } else if let Some(assign) = node.downcast_ref::<AssignNode>() {
+        names.insert(assign.identifier.to_string());
+        if let IndexId(_, index) = &assign.identifier {
+            children.push(index);
+        }
+        children.push(&assign.expr);
+    } else if let Some(assign) = node.downcast_ref::<MultiAssignNode>() {
+        children.extend(assign.identifier.iter().chain(assign.expr.iter()));
+        children.push(&assign.call);
+    } else if let Some(call) = node.downcast_ref::<CallNode>() {
+        has_call = true;
+        children.extend(call.actual_params.iter());
+    } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+        children.extend(cond.branches().into_iter().map(|(condition, _)| condition));
+        children.extend(cond.branch_statements());
+    } else if let Some(lp) = node.downcast_ref::<LoopStatNode>() {
+        children.push(&lp.condition);
+        children.extend(lp.consequences.iter());
+    } else if let Some(lp) = node.downcast_ref::<WhileStatNode>() {
+        children.push(&lp.condition);
+        children.extend(lp.consequences.iter());
+    } else if let Some(compound) = node.downcast_ref::<CompoundNode>() {
+        children.extend(compound.children.iter());
+    } else if let Some(sqrt) = node.downcast_ref::<SqrtNode>() {
+        children.push(&sqrt.sqrt_value);
+    } else if let Some(ret) = node.downcast_ref::<ReturnNode>() {
+        children.extend(ret.returns.iter());
+    } else if let Some(malloc) = node.downcast_ref::<MallocNode>() {
+        children.push(&malloc.num_bytes);
+    } else if let Some(printf) = node.downcast_ref::<PrintfNode>() {
+        children.extend([&printf.val_addr, &printf.flag]);
+    }
+    for child in children {
+        has_call |= mentioned_names(child, names);
+    }
+    has_call
+}
+
+// Value of an integer or felt literal.
+fn literal_value(node: &Arc<RwLock<dyn Node>>) -> Option<i128> {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if let Some(num) = node.downcast_ref::<IntegerNumNode>() {
+        Some(num.value as i128)
+    } else {
+        node.downcast_ref::<FeltNumNode>()
+            .map(|num| num.value as i128)
+    }
+}
+
+fn leftmost_span(node: &Arc<RwLock<dyn Node>>) -> (usize, usize) {
+    let node = node.read().unwrap();
+    let node = node.as_any();
+    if let Some(ident) = node.downcast_ref::<IdentNode>() {
+        ident.span
+    } else if let Some(ident) = node.downcast_ref::<IdentIndexNode>() {
+        ident.span
+    } else if let Some(binop) = node.downcast_ref::<BinOpNode>() {
+        match leftmost_span(&binop.left) {
+            (0, 0) => leftmost_span(&binop.right),
+            span => span,
+        }
+    } else if let Some(unary) = node.downcast_ref::<UnaryOpNode>() {
+        leftmost_span(&unary.expr)
+    } else {
+        (0, 0)
+    }
+}
+
+// Scalar type the elements of a possibly multi-dimensional array have.
+fn array_element(token: &Token) -> &Token {
+    match token {
+        Array(element, _) => array_element(element),
+        _ => token,
+    }
+}
+
+// Array parameters are stored with their array type, array variables with
+// the type of their rows.
+fn element_type_name(token: &Token) -> String {
+    array_element(token).to_string().to_lowercase()
+}
+
+// Type of `name[index]` for the symbol of `name`: the row type of an array
+// variable, which is itself an array for a multi-dimensional one, or the
+// element type of an array parameter.
+fn indexed_type(symbol: &Symbol) -> Option<Token> {
+    match symbol {
+        IdentSymbol(_, BuiltIn(token), Some(_), _) => Some(token.clone()),
+        IdentSymbol(_, BuiltIn(Array(element, _)), None, _) => Some(*element.clone()),
+        _ => None,
+    }
+}
+
+// Declared type of a variable, e.g. `felt[3]` for an array of three felts.
+fn symbol_type_name(token: &Token, size: Option<usize>) -> String {
+    match size {
+        Some(len) => type_name(&BuiltIn(Array(Box::new(token.clone()), len))),
+        None => type_name(&BuiltIn(token.clone())),
+    }
+}
+
+// Name of the type of an assigned value, `Str` standing for any string.
+fn value_type_name(token: &Token) -> String {
+    match token {
+        Token::Str(_) => "string".to_string(),
+        _ => type_name(&BuiltIn(token.clone())),
+    }
+}
+
+// The type a value of type `token` widens to, as in arithmetic: an i32 to a
+// felt, the elements of an array alike.
+fn widened(token: &Token) -> Token {
+    match token {
+        Token::I32 => Felt,
+        Array(element, len) => Array(Box::new(widened(element)), *len),
+        _ => token.clone(),
+    }
+}
+
+// Number of elements of a literal, rows for a multi-dimensional one.
+fn literal_len(array: &ArrayNumNode) -> usize {
+    match array.rows.len() {
+        0 => array.values.len(),
+        rows => rows,
+    }
+}
+
+// Array type of a literal, its rows giving the inner dimensions.
+fn literal_type(array: &ArrayNumNode) -> Option<Token> {
+    let element = match array.rows.first() {
+        Some(row) => literal_type(row)?,
+        None => array.values.first()?.number_type(),
+    };
+    Some(Array(Box::new(element), literal_len(array)))
+}
+
+fn dimensions(token: &Token) -> usize {
+    match token {
+        Array(element, _) => 1 + dimensions(element),
+        _ => 0,
+    }
+}
+
+// Checks that the rows of a multi-dimensional literal all have the type of
+// the first one.
+fn check_array_rows(array: &ArrayNumNode) -> Result<(), String> {
+    for row in array.rows.iter() {
+        check_array_rows(row)?;
+    }
+    let first = match array.rows.first().and_then(literal_type) {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    for (index, row) in array.rows.iter().enumerate().skip(1) {
+        let row_type = match literal_type(row) {
+            Some(row_type) if row_type != first => row_type,
+            _ => continue,
+        };
+        let message = if dimensions(&row_type) != dimensions(&first) {
+            format!(
+                "row {} of the array literal has {} dimensions, row 0 has {}",
+                index,
+                dimensions(&row_type),
+                dimensions(&first)
+            )
+        } else if literal_len(row) != literal_len(&array.rows[0]) {
+            format!(
+                "ragged array literal, row {} has {} elements, row 0 has {}",
+                index,
+                literal_len(row),
+                literal_len(&array.rows[0])
+            )
+        } else if array_element(&row_type) != array_element(&first) {
+            format!(
+                "row {} of the array literal has {} elements, row 0 has {} elements",
+                index,
+                element_type_name(&row_type),
+                element_type_name(&first)
+            )
+        } else {
+            format!(
+                "ragged array literal, row {} is {}, row 0 is {}",
+                index,
+                type_name(&BuiltIn(row_type)),
+                type_name(&BuiltIn(first.clone()))
+            )
+        };
+        return Err(message);
+    }
+    Ok(())
+}
+
+/// Replacement proposed for the source text at a position, (0, 0) if the
+/// position is unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestedFix {
+    pub line: usize,
+    pub column: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+// Levenshtein distance between two identifiers.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// A declared variable the program never reads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnusedSymbol {
+    pub name: String,
+    /// Name of the scope declaring it.
+    pub scope: String,
+    pub array_param: bool,
+    /// A prophet ctx value.
+    pub context: bool,
+}
+
+/// Kinds of declarations `unused_symbols` leaves out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UnusedExclusions {
+    pub array_params: bool,
+    pub context: bool,
+}
+
+/// Whether a prophet input, output or ctx value is used by the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProphetUsage {
+    pub name: String,
+    /// "input", "output" or "ctx".
+    pub kind: &'static str,
+    pub read: bool,
+    pub written: bool,
+}
+
+#[derive(Clone)]
+pub struct SymTableGen {
+    current_scope: Arc<RwLock<SymbolTable>>,
+    options: SemaOptions,
+    current_function: Option<String>,
+    array_params: Vec<String>,
+    // Name of the array whose index expression is being analyzed.
+    index_of: Option<String>,
+    // Declared length and constant indices of the arrays of the function
+    // being analyzed.
+    array_accesses: BTreeMap<String, (usize, Vec<i128>)>,
+    // Size of the malloc region each variable of the current function points
+    // to, None once it is assigned anything else, and the constant indices
+    // it is accessed at.
+    malloc_regions: BTreeMap<String, Option<i128>>,
+    region_accesses: Vec<(String, i128)>,
+    // Types returned by the return statements of the function being
+    // analyzed so far, merged.
+    return_types: Option<Vec<Number>>,
+    // Number of loops enclosing the statement being analyzed.
+    loop_depth: usize,
+    // Kind of each prophet symbol seeded into the global scope, in seeding
+    // order.
+    prophet_symbols: Vec<(String, &'static str)>,
+    pub prophet_reads: BTreeSet<String>,
+    pub prophet_writes: BTreeSet<String>,
+    pub warnings: Vec<String>,
+    pub suggestions: Vec<SuggestedFix>,
+    pub type_annotations: Vec<TypeAnnotation>,
+    pub expression_types: Vec<ExpressionType>,
+    /// Functions called by each function, the entry block under "entry".
+    pub call_graph: BTreeMap<String, BTreeSet<String>>,
+    /// Informational findings, which unlike warnings point at nothing wrong.
+    pub notes: Vec<String>,
+    /// Variables never read, collected as their scopes are left.
+    pub unused: Vec<UnusedSymbol>,
+}
+
+impl SymTableGen {
+    pub fn new(prophet: &OlaProphet) -> Self {
+        Self::new_with_options(prophet, SemaOptions::default())
+    }
+
+    /// Builds an analyzer with an empty global scope, for source files that
+    /// come without prophet metadata. Prophet inputs, outputs and ctx values
+    /// are reported as undeclared.
+    pub fn new_standalone() -> Self {
+        Self::new_standalone_with_options(SemaOptions::default())
+    }
+
+    pub fn new_standalone_with_options(options: SemaOptions) -> Self {
+        SymTableGen {
+            current_scope: Arc::new(RwLock::new(SymbolTable::new(
+                "Prophet Scope".to_string(),
+                PROPHET_SCOPE_LEVEL,
+                None,
+            ))),
+            options,
+            current_function: None,
+            array_params: Vec::new(),
+            index_of: None,
+            array_accesses: BTreeMap::new(),
+            malloc_regions: BTreeMap::new(),
+            region_accesses: Vec::new(),
+            return_types: None,
+            loop_depth: 0,
+            prophet_symbols: Vec::new(),
+            prophet_reads: BTreeSet::new(),
+            prophet_writes: BTreeSet::new(),
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            type_annotations: Vec::new(),
+            expression_types: Vec::new(),
+            call_graph: BTreeMap::new(),
+            notes: Vec::new(),
+            unused: Vec::new(),
+        }
+    }
+
+    pub fn new_with_options(prophet: &OlaProphet, options: SemaOptions) -> Self {
+        let inputs: Vec<GlobalVariable> = prophet
+            .inputs
+            .iter()
+            .map(|input| GlobalVariable::new(&input.name, input.length))
+            .collect();
+        let ctx: Vec<String> = prophet.ctx.iter().map(|ctx| ctx.0.clone()).collect();
+        let outputs: Vec<GlobalVariable> = prophet
+            .outputs
+            .iter()
+            .map(|output| GlobalVariable::new(&output.name, output.length))
+            .collect();
+        Self::with_globals_and_options(&inputs, &ctx, &outputs, options)
+    }
+
+    /// Builds an analyzer whose global scope holds the given prophet inputs,
+    /// ctx values and outputs, like `new` does from an `OlaProphet`.
+    pub fn with_globals(
+        inputs: &[GlobalVariable],
+        ctx: &[String],
+        outputs: &[GlobalVariable],
+    ) -> Self {
+        Self::with_globals_and_options(inputs, ctx, outputs, SemaOptions::default())
+    }
+
+    pub fn with_globals_and_options(
+        inputs: &[GlobalVariable],
+        ctx: &[String],
+        outputs: &[GlobalVariable],
+        options: SemaOptions,
+    ) -> Self {
+        let mut gen = Self::new_standalone_with_options(options);
+        let mut current_scope = gen.current_scope.write().unwrap();
+        for input in inputs.iter() {
+            inf_var_insert!(input, current_scope);
+        }
+
+        for name in ctx.iter() {
+            let variable = IdentSymbol(name.to_string(), BuiltIn(Felt), None, false);
+            current_scope.insert(variable);
+        }
+
+        for output in outputs.iter() {
+            inf_var_insert!(output, current_scope);
+        }
+        drop(current_scope);
+        let inputs = inputs.iter().map(|input| (input.name.clone(), "input"));
+        let ctx = ctx.iter().map(|name| (name.clone(), "ctx"));
+        let outputs = outputs.iter().map(|output| (output.name.clone(), "output"));
+        gen.prophet_symbols = inputs.chain(ctx).chain(outputs).collect();
+        gen
+    }
+
+    // Looks `name` up from the current scope, through the scope's
+    // resolution cache unless it is turned off.
+    fn resolve(&self, name: &str) -> Option<Symbol> {
+        if self.options.resolution_cache {
+            self.current_scope.write().unwrap().resolve(name)
+        } else {
+            self.current_scope.read().unwrap().lookup(name)
+        }
+    }
+
+    // With `declaration_order` on, a variable may only be referenced after
+    // its declaration.
+    fn check_declared_before(&self, name: &str, span: (usize, usize)) -> Result<(), String> {
+        if !self.options.declaration_order || span == (0, 0) {
+            return Ok(());
+        }
+        match self.current_scope.read().unwrap().declaration_span(name) {
+            Some(declared) if declared > span => Err(format!(
+                "'{}' is used at {}:{} before its declaration at {}:{}",
+                name, span.0, span.1, declared.0, declared.1
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Analyzes the statements of a loop body, inside of which break and
+    // continue are allowed.
+    fn travel_loop_body(&mut self, consequences: &[Arc<RwLock<dyn Node>>]) -> NumberResult {
+        self.loop_depth += 1;
+        let result = consequences
+            .iter()
+            .try_for_each(|expr| self.travel(expr).map(|_| ()));
+        self.loop_depth -= 1;
+        result.map(|_| Single(Nil))
+    }
+
+    // Returns an analyzer for a single function body that shares nothing
+    // mutable with `self`.
+    fn fork(&self, scope: Arc<RwLock<SymbolTable>>) -> Self {
+        SymTableGen {
+            current_scope: scope,
+            options: self.options.clone(),
+            current_function: None,
+            array_params: Vec::new(),
+            index_of: None,
+            array_accesses: BTreeMap::new(),
+            malloc_regions: BTreeMap::new(),
+            region_accesses: Vec::new(),
+            return_types: None,
+            loop_depth: 0,
+            prophet_symbols: self.prophet_symbols.clone(),
+            prophet_reads: BTreeSet::new(),
+            prophet_writes: BTreeSet::new(),
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            type_annotations: Vec::new(),
+            expression_types: Vec::new(),
+            call_graph: BTreeMap::new(),
+            notes: Vec::new(),
+            unused: Vec::new(),
+        }
+    }
+
+    // Inserts the function symbol into the current scope and returns what is
+    // needed to analyze the function body.
+    fn register_function(&mut self, node: &mut FunctionNode) -> Option<FunctionScope> {
+        if let Id(func_name) = &node.func_name {
+            let mut param_symbols = Vec::new();
+            let mut param_scope = Vec::new();
+            let mut array_params = Vec::new();
+            for param_node in &node.params {
+                let mut param = param_node.write().unwrap();
+                let param = param
+                    .as_any_mut()
+                    .downcast_mut::<IdentDeclarationNode>()
+                    .unwrap();
+                let name = param.ident_node.identifier.to_string();
+
+                let ident_type = BuiltIn(param.type_node.token.clone());
+
+                let mut token_len = None;
+                if let Array(_token, len) = &param.type_node.token {
+                    token_len = Some(*len);
+                    param.ident_node.identifier = ArrayId(name.to_string());
+                    array_params.push(name.clone());
+                }
+
+                let ident = (
+                    param.ident_node.identifier.to_string(),
+                    BuiltIn(param.type_node.token.clone()),
+                );
+                param_symbols.push(ident);
+                let symbol = IdentSymbol(name.clone(), ident_type, token_len, false);
+                param_scope.push(symbol);
+            }
+            let returns = node
+                .returns
+                .iter()
+                .map(|ret| {
+                    let ret = ret.read().unwrap();
+                    let ret = ret.as_any().downcast_ref::<TypeNode>().unwrap();
+                    BuiltIn(ret.token.clone())
+                })
+                .collect();
+            let func_symbol = FuncSymbol(
+                func_name.to_string(),
+                param_symbols,
+                returns,
+                None,
+                node.block.clone(),
+            );
+            self.current_scope
+                .write()
+                .unwrap()
+                .symbols
+                .insert(func_name.to_string(), func_symbol);
+            Some(FunctionScope {
+                name: func_name.to_string(),
+                params: param_scope,
+                array_params,
+                returns_value: !node.returns.is_empty(),
+                block: node.block.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn analyze_function(&mut self, function: FunctionScope) -> NumberResult {
+        self.check_builtin_shadowing(&function.name)?;
+        let naming = self.options.naming_convention.clone();
+        self.check_naming("function", &function.name, naming.functions);
+        for symbol in function.params.iter() {
+            if let IdentSymbol(name, _, _, _) = symbol {
+                self.check_builtin_shadowing(name)?;
+                self.check_naming("parameter", name, naming.variables);
+            }
+        }
+        let cur = self.current_scope.clone();
+        let scope_level = cur.read().unwrap().scope_level;
+        let mut cur_scope = SymbolTable::new(function.name.clone(), scope_level + 1, Some(cur));
+        for symbol in function.params {
+            if let IdentSymbol(name, _, _, _) = &symbol {
+                self.check_case_collision(&cur_scope, name);
+            }
+            cur_scope.insert(symbol);
+        }
+        self.current_scope = Arc::new(RwLock::new(cur_scope));
+        self.current_function = Some(function.name);
+        self.array_params = function.array_params;
+        self.return_types = None;
+        let mut ret = self.travel(&function.block);
+        self.check_array_accesses();
+        let regions = self.check_malloc_regions();
+        ret = ret.and_then(|ret| regions.map(|_| ret).map_err(SemaError::from));
+        self.check_dead_stores(&body_statements(&function.block));
+        mark_tail_calls(&body_statements(&function.block), !function.returns_value);
+        if ret.is_ok() && function.returns_value && !always_returns(&function.block) {
+            ret = Err(format!(
+                "function {} does not return a value on all paths",
+                function.name
+            )
+            .into());
+        }
+        self.collect_unused(&self.current_scope.clone());
+        self.current_function = None;
+        self.array_params.clear();
+        let enclosing_scope = self.current_scope.read().unwrap().enclosing_scope.clone();
+        self.current_scope = enclosing_scope.unwrap();
+        if let Some(return_types) = self.return_types.take() {
+            self.set_inferred_returns(&function.name, number_ret(return_types));
+        }
+        ret
+    }
+
+    // Records the unread variables of `scope`. Prophet outputs are left out,
+    // they are written for the host.
+    fn collect_unused(&mut self, scope: &Arc<RwLock<SymbolTable>>) {
+        let scope = scope.read().unwrap();
+        for name in scope.unread_variables() {
+            let kind = self
+                .prophet_symbols
+                .iter()
+                .find(|(symbol, _)| *symbol == name)
+                .map(|(_, kind)| *kind);
+            let prophet_kind = kind.filter(|_| scope.scope_level == PROPHET_SCOPE_LEVEL);
+            if prophet_kind == Some("output") {
+                continue;
+            }
+            self.unused.push(UnusedSymbol {
+                array_param: self.array_params.contains(&name),
+                context: prophet_kind == Some("ctx"),
+                scope: scope.scope_name.clone(),
+                name,
+            });
+        }
+    }
+
+    /// Names of the declared variables that are never read, in the order
+    /// their scopes were left, without the kinds the options exclude.
+    pub fn unused_symbols(&self) -> Vec<String> {
+        let exclusions = self.options.unused_exclusions;
+        self.unused
+            .iter()
+            .filter(|symbol| !(exclusions.array_params && symbol.array_param))
+            .filter(|symbol| !(exclusions.context && symbol.context))
+            .map(|symbol| symbol.name.clone())
+            .collect()
+    }
+
+    // Records the return types inferred for function `name` in the scope
+    // declaring it.
+    fn set_inferred_returns(&self, name: &str, return_types: NumberRet) {
+        let mut scope = self.current_scope.write().unwrap();
+        if let Some(FuncSymbol(_, _, _, inferred, _)) = scope.symbols.get_mut(name) {
+            *inferred = Some(return_types);
+        }
+    }
+
+    // Registers all global symbols first, then analyzes every function body
+    // on its own thread with a private copy of the global scope. Warnings and
+    // the global accesses each copy counted are merged in declaration order.
+    fn travel_globals_parallel(&mut self, node: &mut EntryNode) -> Result<(), SemaError> {
+        let mut functions = Vec::new();
+        for declaration in node.global_declarations.iter() {
+            if is_node_type::<FunctionNode>(declaration) {
+                let mut declaration = declaration.write().unwrap();
+                let function = declaration
+                    .as_any_mut()
+                    .downcast_mut::<FunctionNode>()
+                    .unwrap();
+                functions.extend(self.register_function(function));
+            } else {
+                self.travel(declaration)?;
+            }
+        }
+
+        let base = self.current_scope.read().unwrap().clone();
+        let results: Vec<(String, NumberResult, SymTableGen)> = thread::scope(|s| {
+            let handles: Vec<_> = functions
+                .into_iter()
+                .map(|function| {
+                    let mut worker = self.fork(Arc::new(RwLock::new(base.clone())));
+                    let name = function.name.clone();
+                    s.spawn(move || {
+                        let ret = worker.analyze_function(function);
+                        (name, ret, worker)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|err| panic::resume_unwind(err))
+                })
+                .collect()
+        });
+
+        for (name, ret, worker) in results {
+            let copy = worker.current_scope.read().unwrap();
+            if let Some(FuncSymbol(_, _, _, Some(inferred), _)) = copy.lookup(&name) {
+                self.set_inferred_returns(&name, inferred);
+            }
+            self.current_scope
+                .write()
+                .unwrap()
+                .merge_access_counts(&copy, &base);
+            self.warnings.extend(worker.warnings);
+            self.suggestions.extend(worker.suggestions);
+            self.type_annotations.extend(worker.type_annotations);
+            self.expression_types.extend(worker.expression_types);
+            self.call_graph.extend(worker.call_graph);
+            self.notes.extend(worker.notes);
+            self.unused.extend(worker.unused);
+            self.prophet_reads.extend(worker.prophet_reads);
+            self.prophet_writes.extend(worker.prophet_writes);
+            ret?;
+        }
+        Ok(())
+    }
+
+    /// Returns the type of the identifier covering the given 1-based source
+    /// position, e.g. `felt` or `i32[4]`.
+    pub fn type_at(&self, line: usize, column: usize) -> Option<String> {
+        self.type_annotations
+            .iter()
+            .find(|a| a.line == line && a.column <= column && column < a.column + a.len)
+            .map(|a| a.type_name.clone())
+    }
+
+    /// Returns the deepest call chain reachable from the entry block, starting
+    /// with "entry". Its depth is the number of calls, one less than its
+    /// length.
+    pub fn deepest_call_chain(&self) -> Vec<String> {
+        fn deepest(
+            graph: &BTreeMap<String, BTreeSet<String>>,
+            name: &str,
+            visiting: &mut BTreeSet<String>,
+        ) -> Vec<String> {
+            let mut chain = Vec::new();
+            if visiting.insert(name.to_string()) {
+                for callee in graph.get(name).into_iter().flatten() {
+                    let sub_chain = deepest(graph, callee, visiting);
+                    if sub_chain.len() > chain.len() {
+                        chain = sub_chain;
+                    }
+                }
+                visiting.remove(name);
+            }
+            chain.insert(0, name.to_string());
+            chain
+        }
+        deepest(&self.call_graph, "entry", &mut BTreeSet::new())
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.deepest_call_chain().len() - 1
+    }
+
+    /// Reports for every prophet input, output and ctx value whether the
+    /// program reads or writes it.
+    pub fn prophet_usage(&self) -> Vec<ProphetUsage> {
+        self.prophet_symbols
+            .iter()
+            .map(|(name, kind)| ProphetUsage {
+                name: name.clone(),
+                kind,
+                read: self.prophet_reads.contains(name),
+                written: self.prophet_writes.contains(name),
+            })
+            .collect()
+    }
+
+    // Counts a read or write of `name` in the scope declaring it.
+    fn record_use(&mut self, name: &str, write: bool) {
+        self.current_scope
+            .write()
+            .unwrap()
+            .record_access(name, write);
+        self.record_prophet_use(name, write);
+    }
+
+    // Records a use of `name` if it resolves to a prophet symbol of the
+    // global scope.
+    fn record_prophet_use(&mut self, name: &str, write: bool) {
+        if !self
+            .prophet_symbols
+            .iter()
+            .any(|(symbol, _)| symbol == name)
+        {
+            return;
+        }
+        let mut scope = self.current_scope.clone();
+        loop {
+            let enclosing_scope = {
+                let table = scope.read().unwrap();
+                if table.symbols.contains_key(name) {
+                    if table.enclosing_scope.is_some() {
+                        return;
+                    }
+                    break;
+                }
+                table.enclosing_scope.clone()
+            };
+            match enclosing_scope {
+                Some(enclosing_scope) => scope = enclosing_scope,
+                None => return,
+            }
+        }
+        if write {
+            self.prophet_writes.insert(name.to_string());
+        } else {
+            self.prophet_reads.insert(name.to_string());
+        }
+    }
+
+    // Rejects function bodies, and programs, with more statements than the
+    // configured budgets allow.
+    fn check_statement_budget(&self, node: &EntryNode) -> Result<(), String> {
+        let mut bodies = Vec::new();
+        for declaration in node.global_declarations.iter() {
+            let declaration = declaration.read().unwrap();
+            if let Some(function) = declaration.as_any().downcast_ref::<FunctionNode>() {
+                bodies.push((function.func_name.to_string(), function.block.clone()));
+            }
+        }
+        bodies.push(("entry".to_string(), node.entry_block.clone()));
+        let mut total = 0;
+        for (name, body) in bodies.iter() {
+            let count = count_statements(body);
+            if count > self.options.function_statement_budget {
+                return Err(format!(
+                    "function '{}' has {} statements, above the budget of {}",
+                    name, count, self.options.function_statement_budget
+                ));
+            }
+            total += count;
+        }
+        if total > self.options.total_statement_budget {
+            return Err(format!(
+                "program has {} statements, above the budget of {}",
+                total, self.options.total_statement_budget
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_prophet_usage(&mut self) {
+        for usage in self.prophet_usage() {
+            if usage.kind == "input" && !usage.read && !usage.written {
+                self.warn(format!("prophet input '{}' is never used", usage.name));
+            } else if usage.kind == "output" && !usage.written {
+                self.warn(format!("prophet output '{}' is never written", usage.name));
+            }
+        }
+    }
+
+    /// Renders the recorded expression types, ordered by position.
+    pub fn dump_types(&self, format: TypeDump) -> String {
+        let mut types = self.expression_types.clone();
+        types.sort_by_key(|t| (t.line, t.column));
+        match format {
+            TypeDump::Off => String::new(),
+            TypeDump::Text => types
+                .iter()
+                .map(|t| {
+                    format!(
+                        "{}:{}: {}: {}\n",
+                        t.line, t.column, t.expression, t.type_name
+                    )
+                })
+                .collect(),
+            TypeDump::Json => serde_json::to_string_pretty(&types).unwrap_or_default(),
+        }
+    }
+
+    fn record_type(&mut self, span: (usize, usize), expression: String, result: &NumberResult) {
+        if self.options.dump_types == TypeDump::Off {
+            return;
+        }
+        if let Ok(Single(number)) = result {
+            self.expression_types.push(ExpressionType {
+                line: span.0,
+                column: span.1,
+                expression,
+                type_name: number_type_name(number).to_string(),
+            });
+        }
+    }
+
+    fn annotate(&mut self, span: (usize, usize), name: &str, type_name: String) {
+        if span != (0, 0) {
+            self.type_annotations.push(TypeAnnotation {
+                line: span.0,
+                column: span.1,
+                len: name.len(),
+                type_name,
+            });
+        }
+    }
+
+    fn check_strict_index(&self, name: &str, index: &NumberResult) -> Result<(), String> {
+        if let (true, Ok(Single(Number::Felt(_)))) = (self.options.strict_arithmetic, index) {
+            return Err(format!(
+                "array '{}' is indexed with a felt, strict mode requires an i32 index",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_magic_number(&mut self, name: &str, value: i128, role: &str) {
+        if let Some(threshold) = self.options.magic_number_threshold {
+            if value > threshold {
+                self.warn(format!(
+                    "array '{}' uses the magic number {} as {}, name it with a const",
+                    name, value, role
+                ));
+            }
+        }
+    }
+
+    fn record_array_access(&mut self, name: &str, index: &Arc<RwLock<dyn Node>>) {
+        if let Some(value) = literal_value(index) {
+            self.check_magic_number(name, value, "an index");
+        }
+        if let Some(value) = self.const_eval(index) {
+            let value = match value {
+                Number::I32(value) => value as i128,
+                Number::Felt(value) => value,
+                _ => return,
+            };
+            if let Some((_, indices)) = self.array_accesses.get_mut(name) {
+                indices.push(value);
+            }
+        }
+    }
+
+    // Tracks the malloc region `name` points to, keeping the largest size
+    // when it is assigned several.
+    fn record_region(&mut self, name: &str, size: Option<i128>) {
+        let region = self.malloc_regions.entry(name.to_string()).or_insert(size);
+        *region = match (*region, size) {
+            (Some(current), Some(size)) => Some(current.max(size)),
+            _ => None,
+        };
+    }
+
+    // Size of a `malloc` with a constant size.
+    fn malloc_size(&self, node: &Arc<RwLock<dyn Node>>) -> Option<i128> {
+        let node = node.read().unwrap();
+        let malloc = node.as_any().downcast_ref::<MallocNode>()?;
+        match self.const_eval(&malloc.num_bytes)? {
+            Number::I32(size) => Some(size as i128),
+            Number::Felt(size) => Some(size),
+            _ => None,
+        }
+    }
+
+    // Records a constant index into a variable that is not an array, which
+    // may point to a malloc region.
+    fn record_region_access(&mut self, name: &str, index: &Arc<RwLock<dyn Node>>) {
+        let symbol = self.current_scope.read().unwrap().lookup(name);
+        if !matches!(symbol, Some(IdentSymbol(_, _, None, _))) {
+            return;
+        }
+        let index = match self.const_eval(index) {
+            Some(Number::I32(index)) => index as i128,
+            Some(Number::Felt(index)) => index,
+            _ => return,
+        };
+        self.region_accesses.push((name.to_string(), index));
+    }
+
+    // Rejects constant indices outside the malloc region a variable provably
+    // points to, the largest one when it is assigned several.
+    fn check_malloc_regions(&mut self) -> Result<(), String> {
+        let regions = std::mem::take(&mut self.malloc_regions);
+        let accesses = std::mem::take(&mut self.region_accesses);
+        for (name, index) in accesses {
+            if let Some(Some(size)) = regions.get(&name) {
+                if index < 0 || index >= *size {
+                    return Err(format!(
+                        "index {} of '{}' is outside its malloc region of {} slots",
+                        index, name, size
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Warns about arrays whose constant indices are all out of bounds, which
+    // usually means the declared length is a typo.
+    fn check_array_accesses(&mut self) {
+        let accesses = std::mem::take(&mut self.array_accesses);
+        for (name, (len, indices)) in accesses {
+            let in_bounds = indices.iter().any(|i| *i >= 0 && (*i as usize) < len);
+            if !indices.is_empty() && !in_bounds {
+                let indices: Vec<String> = indices.iter().map(|i| i.to_string()).collect();
+                self.warn(format!(
+                    "array '{}' of length {} is only indexed out of range at [{}]",
+                    name,
+                    len,
+                    indices.join(", ")
+                ));
+            }
+        }
+    }
+
+    // Warns when the same array variable is passed as several arguments of a
+    // call, since writes through one parameter are then visible through the
+    // others.
+    fn check_array_aliasing(&mut self, node: &CallNode) {
+        let mut positions: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, param) in node.actual_params.iter().enumerate() {
+            let param = param.read().unwrap();
+            if let Some(IdentNode {
+                identifier: ArrayId(name),
+                ..
+            }) = param.as_any().downcast_ref::<IdentNode>()
+            {
+                positions.entry(name.clone()).or_default().push(index + 1);
+            }
+        }
+        for (name, positions) in positions {
+            if positions.len() > 1 {
+                let positions: Vec<String> = positions.iter().map(|p| p.to_string()).collect();
+                self.warn(format!(
+                    "call to '{}' passes array '{}' as arguments {}, they alias the same memory",
+                    node.func_name,
+                    name,
+                    positions.join(", ")
+                ));
+            }
+        }
+    }
+
+    // Declared variable or function name closest to `name`, if it is close
+    // enough to be a typo of it.
+    fn closest_symbol(&self, name: &str) -> Option<String> {
+        let mut best: Option<(usize, String)> = None;
+        let mut scope = Some(self.current_scope.clone());
+        while let Some(table) = scope {
+            let table = table.read().unwrap();
+            for (symbol_name, symbol) in table.symbols.iter() {
+                if matches!(symbol, BuiltInSymbol(_)) {
+                    continue;
+                }
+                let distance = edit_distance(name, symbol_name);
+                if distance > (name.len() / 3).max(1) {
+                    continue;
+                }
+                let closer = match &best {
+                    Some((d, n)) => (distance, symbol_name) < (*d, n),
+                    None => true,
+                };
+                if closer {
+                    best = Some((distance, symbol_name.clone()));
+                }
+            }
+            scope = table.enclosing_scope.clone();
+        }
+        best.map(|(_, name)| name)
+    }
+
+    /// Level of the scope `name` resolves to from the current scope.
+    pub fn scope_level_of(&self, name: &str) -> Option<u32> {
+        let mut scope = Some(self.current_scope.clone());
+        while let Some(current) = scope {
+            let table = current.read().unwrap();
+            if table.symbols.contains_key(name) {
+                return Some(table.scope_level);
+            }
+            scope = table.enclosing_scope.clone();
+        }
+        None
+    }
+
+    /// Returns the types inferred from the return statements of function
+    /// `name`, `None` if its body has not been analyzed or has no return
+    /// statement.
+    pub fn inferred_returns(&self, name: &str) -> Option<NumberRet> {
+        match self.current_scope.read().unwrap().lookup(name) {
+            Some(FuncSymbol(_, _, _, inferred, _)) => inferred,
+            _ => None,
+        }
+    }
+
+    // Type of the value `expr` evaluates to, given what traversing it
+    // returned, strings being any `Str`. `None` when it is unknown, e.g. for
+    // context identifiers or calls returning several values.
+    fn assigned_type(&self, expr: &Arc<RwLock<dyn Node>>, ret: &NumberRet) -> Option<Token> {
+        let expr = expr.read().unwrap();
+        let expr = expr.as_any();
+        if let Some(IdentNode {
+            identifier: Id(name) | ArrayId(name),
+            ..
+        }) = expr.downcast_ref::<IdentNode>()
+        {
+            return self
+                .current_scope
+                .read()
+                .unwrap()
+                .lookup(name)
+                .and_then(symbol_type);
+        }
+        if let Some(IdentIndexNode {
+            identifier: Id(name),
+            ..
+        }) = expr.downcast_ref::<IdentIndexNode>()
+        {
+            let symbol = self.current_scope.read().unwrap().lookup(name)?;
+            return indexed_type(&symbol);
+        }
+        if let Some(array) = expr.downcast_ref::<ArrayNumNode>() {
+            return literal_type(array);
+        }
+        if let Some(call) = expr.downcast_ref::<CallNode>() {
+            let symbol = call.func_symbol.as_ref()?.read().unwrap().clone();
+            return match symbol {
+                FuncSymbol(_, _, returns, _, _) if returns.len() == 1 => Some(returns[0].0.clone()),
+                _ => None,
+            };
+        }
+        match ret {
+            Single(value @ (Number::I32(_) | Number::Felt(_))) => Some(value.number_type()),
+            Single(Number::Str(_)) => Some(Token::Str(String::new())),
+            _ => None,
+        }
+    }
 
-pub mod symbol;
+    // Checks every target of a multi-assign against the return type of the
+    // called function at the same position.
+    fn check_multi_assign_types(
+        &self,
+        call: &Arc<RwLock<dyn Node>>,
+        targets: &[(String, Option<Token>, bool)],
+    ) -> Result<(), SemaError> {
+        let call = call.read().unwrap();
+        let func_name = match call.as_any().downcast_ref::<CallNode>() {
+            Some(call) => call.func_name.to_string(),
+            None => return Ok(()),
+        };
+        let returns = match self.current_scope.read().unwrap().lookup(&func_name) {
+            Some(FuncSymbol(_, _, returns, _, _)) => returns,
+            _ => return Ok(()),
+        };
+        if returns.len() != targets.len() {
+            return Err(SemaError::ArityMismatch {
+                func: func_name,
+                expected: returns.len(),
+                got: targets.len(),
+                arity: Arity::Returns,
+            });
+        }
+        for (index, ((name, target, context), BuiltIn(ret))) in
+            targets.iter().zip(returns.iter()).enumerate()
+        {
+            let target = match target {
+                Some(target) if target != ret => target,
+                _ => continue,
+            };
+            let message = if *context {
+                format!(
+                    "context identifier '{}' of type {} cannot receive the {} returned by '{}'",
+                    name, target, ret, func_name
+                )
+            } else {
+                format!(
+                    "multi-assign target '{}' has type {}, but '{}' returns {} at position {}",
+                    name, target, func_name, ret, index
+                )
+            };
+            return Err(SemaError::type_mismatch(
+                &target.to_string(),
+                &ret.to_string(),
+                message,
+            ));
+        }
+        Ok(())
+    }
 
-#[macro_export]
-macro_rules! inf_var_insert {
-    ($input: tt, $current_scope: tt) => {
-        if $input.length == 1 {
-            let variable = IdentSymbol($input.name.to_string(), BuiltIn(Felt), None);
-            $current_scope.insert(variable);
-        } else {
-            let variable = IdentSymbol(
-                $input.name.to_string(),
-                BuiltIn(Array(Box::new(Felt), $input.length)),
-                None,
+    // Analyzes the index of `name[index]`, which must be an i32 or a felt.
+    fn travel_index(
+        &mut self,
+        name: &str,
+        index: &Arc<RwLock<dyn Node>>,
+        span: (usize, usize),
+    ) -> Result<(), SemaError> {
+        let index_of = self.index_of.replace(name.to_string());
+        let value = self.travel(index);
+        self.index_of = index_of;
+        self.check_strict_index(name, &value)?;
+        let is_array = {
+            let index = index.read().unwrap();
+            match index.as_any().downcast_ref::<IdentNode>() {
+                Some(ident) => matches!(ident.identifier, ArrayId(_)),
+                None => false,
+            }
+        };
+        let index_type = match value? {
+            Single(Number::I32(_)) | Single(Number::Felt(_)) if !is_array => None,
+            Single(num) if !is_array => Some(number_type_name(&num).to_string()),
+            _ => Some("array".to_string()),
+        };
+        if let Some(index_type) = index_type {
+            let message = format!(
+                "index of array '{}' at {}:{} has type {}, expected i32 or felt",
+                name, span.0, span.1, index_type
             );
-            $current_scope.insert(variable);
+            return Err(SemaError::type_mismatch(
+                "i32 or felt",
+                &index_type,
+                message,
+            ));
         }
-    };
-}
+        Ok(())
+    }
 
-#[derive(Clone)]
-pub struct SymTableGen {
-    current_scope: Arc<RwLock<SymbolTable>>,
-}
+    // Type of a condition, the first value for a call returning several.
+    fn condition_type(&mut self, condition: &Arc<RwLock<dyn Node>>) -> Result<Number, SemaError> {
+        Ok(match self.travel(condition)? {
+            Single(number) => number,
+            Multiple(numbers) => numbers[0].clone(),
+        })
+    }
 
-impl SymTableGen {
-    pub fn new(prophet: &OlaProphet) -> Self {
-        let gen = SymTableGen {
-            current_scope: Arc::new(RwLock::new(SymbolTable::new(
-                "Global Scope".to_string(),
-                1,
-                None,
-            ))),
+    // Error for an undeclared variable, proposing the closest declared name.
+    fn undeclared(&mut self, context: &str, name: &str, span: (usize, usize)) -> SemaError {
+        let suggestion = self.closest_symbol(name);
+        if let Some(replacement) = &suggestion {
+            self.suggestions.push(SuggestedFix {
+                line: span.0,
+                column: span.1,
+                original: name.to_string(),
+                replacement: replacement.clone(),
+            });
+        }
+        SemaError::UndeclaredVariable {
+            name: name.to_string(),
+            context: context.to_string(),
+            suggestion,
+        }
+    }
+
+    fn check_naming(&mut self, kind: &str, name: &str, style: Option<NamingStyle>) {
+        if let Some(style) = style {
+            if !style.matches(name) {
+                self.warn(format!("{} '{}' is not {}", kind, name, style));
+            }
+        }
+    }
+
+    // Keywords are case-insensitive, so a name spelled like a builtin type
+    // in any case is rejected, and one spelled like a builtin function is
+    // warned about.
+    fn check_builtin_shadowing(&mut self, name: &str) -> Result<(), String> {
+        let symbol = self
+            .current_scope
+            .read()
+            .unwrap()
+            .lookup(&name.to_uppercase());
+        if let Some(BuiltInSymbol(builtin)) = symbol {
+            return Err(format!(
+                "identifier '{}' shadows the builtin type {}",
+                name, builtin
+            ));
+        }
+        if let Some(function) = BUILTIN_FUNCTIONS
+            .iter()
+            .find(|function| function.eq_ignore_ascii_case(name))
+        {
+            self.warn(format!(
+                "identifier '{}' shadows the builtin function {}",
+                name, function
+            ));
+        }
+        Ok(())
+    }
+
+    fn warn(&mut self, message: String) {
+        warn!("{}", message);
+        self.warnings.push(message);
+    }
+
+    fn note(&mut self, message: String) {
+        info!("{}", message);
+        self.notes.push(message);
+    }
+
+    /// Evaluates a constant expression, folding felt arithmetic with the
+    /// configured field modulus. Returns `None` if the expression is not
+    /// constant.
+    pub fn const_eval(&self, node: &Arc<RwLock<dyn Node>>) -> Option<Number> {
+        let field_params = &self.options.field_params;
+        let node = node.read().unwrap();
+        let node = node.as_any();
+        if let Some(num) = node.downcast_ref::<IntegerNumNode>() {
+            Some(Number::I32(num.value))
+        } else if let Some(num) = node.downcast_ref::<FeltNumNode>() {
+            Some(Number::Felt(field_params.reduce(num.value as i128)))
+        } else if let Some(num) = node.downcast_ref::<BoolNumNode>() {
+            Some(Number::Bool(num.value))
+        } else if let Some(string) = node.downcast_ref::<StringNode>() {
+            Some(Number::Str(string.value.clone()))
+        } else if let Some(unary) = node.downcast_ref::<UnaryOpNode>() {
+            let value = self.const_eval(&unary.expr)?;
+            match (&unary.operator, value) {
+                (Token::Plus, value) => Some(value),
+                (Token::Minus, Number::I32(value)) => Some(Number::I32(value.checked_neg()?)),
+                (Token::Minus, Number::Felt(value)) => {
+                    Some(Number::Felt(field_params.reduce(-value)))
+                }
+                _ => None,
+            }
+        } else if let Some(binop) = node.downcast_ref::<BinOpNode>() {
+            let lhs = self.const_eval(&binop.left)?;
+            let rhs = self.const_eval(&binop.right)?;
+            field_params.fold_binop(&lhs, &rhs, &binop.operator, self.options.overflow_mode)
+        } else {
+            None
+        }
+    }
+
+    fn check_i32_overflow(&self, node: &BinOpNode) -> Result<(), String> {
+        let is_arithmetic = is_arithmetic(&node.operator);
+        let operands = (self.const_eval(&node.left), self.const_eval(&node.right));
+        if let (true, Some(Number::I32(lhs)), Some(Number::I32(rhs))) =
+            (is_arithmetic, operands.0, operands.1)
+        {
+            if rhs != 0
+                && OverflowMode::Checked
+                    .i32_binop(lhs, rhs, &node.operator)
+                    .is_none()
+            {
+                return Err(format!(
+                    "i32 constant overflow in {} {} {}",
+                    lhs, node.operator, rhs
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_felt_overflow(&self, node: &BinOpNode) -> Result<(), String> {
+        let operands = (self.const_eval(&node.left), self.const_eval(&node.right));
+        if let (Some(lhs), Some(rhs)) = operands {
+            let checked = self
+                .options
+                .field_params
+                .checked_binop(&lhs, &rhs, &node.operator);
+            if let Some((Number::Felt(value), true)) = checked {
+                return Err(format!(
+                    "felt constant overflow in {} {} {}, wraps to {}",
+                    expression_text(&node.left),
+                    node.operator,
+                    expression_text(&node.right),
+                    value
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the variable name and constant of an `ident == const` comparison.
+    fn equality_operands(&self, condition: &Arc<RwLock<dyn Node>>) -> Option<(String, i128)> {
+        let condition = condition.read().unwrap();
+        let binop = condition.as_any().downcast_ref::<BinOpNode>()?;
+        if binop.operator != Token::Equal {
+            return None;
+        }
+        let (ident, value) = if is_node_type::<IdentNode>(&binop.left) {
+            (&binop.left, &binop.right)
+        } else if is_node_type::<IdentNode>(&binop.right) {
+            (&binop.right, &binop.left)
+        } else {
+            return None;
         };
+        let name = ident
+            .read()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<IdentNode>()?
+            .identifier
+            .to_string();
+        match self.const_eval(value)? {
+            Number::I32(value) => Some((name, value as i128)),
+            Number::Felt(value) => Some((name, value)),
+            _ => None,
+        }
+    }
 
-        let mut current_scope = gen.current_scope.write().unwrap();
-        for input in prophet.inputs.iter() {
-            inf_var_insert!(input, current_scope);
+    fn check_duplicate_conditions(&mut self, node: &CondStatNode) {
+        let conditions: Vec<_> = node
+            .branches()
+            .into_iter()
+            .map(|(condition, _)| condition.clone())
+            .collect();
+
+        let mut seen: Vec<(String, i128, usize)> = Vec::new();
+        for (index, condition) in conditions.iter().enumerate() {
+            if let Some((name, value)) = self.equality_operands(condition) {
+                let first = seen
+                    .iter()
+                    .find(|(seen_name, seen_value, _)| *seen_name == name && *seen_value == value)
+                    .map(|(_, _, branch)| *branch);
+                if let Some(first) = first {
+                    self.warn(format!(
+                        "unreachable branch {}: '{} == {}' is already matched by branch {}",
+                        index + 1,
+                        name,
+                        value,
+                        first
+                    ));
+                } else {
+                    seen.push((name, value, index + 1));
+                }
+            }
         }
+    }
 
-        for ctx in &prophet.ctx {
-            let variable = IdentSymbol(ctx.0.to_string(), BuiltIn(Felt), None);
-            current_scope.insert(variable);
+    // Warns about stores to a variable that the next store to it overwrites
+    // in the same straight-line statement list. A statement other than a
+    // plain store counts as reading every variable it mentions, and a call as
+    // reading every variable, so branches and loops never cause warnings.
+    fn check_dead_stores(&mut self, statements: &[Arc<RwLock<dyn Node>>]) {
+        let mut pending: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for statement in statements.iter() {
+            let store = {
+                let node = statement.read().unwrap();
+                node.as_any()
+                    .downcast_ref::<AssignNode>()
+                    .and_then(|assign| match &assign.identifier {
+                        Id(name) | ArrayId(name) | Cid(name) => {
+                            Some((name.clone(), assign.span, assign.expr.clone()))
+                        }
+                        _ => None,
+                    })
+            };
+            let (target, read_from) = match store {
+                Some((name, span, expr)) => (Some((name, span)), expr),
+                None => (None, statement.clone()),
+            };
+            let mut names = BTreeSet::new();
+            if mentioned_names(&read_from, &mut names) {
+                pending.clear();
+            }
+            for name in names.iter() {
+                pending.remove(name);
+            }
+            if let Some((name, span)) = target {
+                if let Some((line, column)) = pending.insert(name.clone(), span) {
+                    self.warn(format!(
+                        "value assigned to '{}' at {}:{} is overwritten before it is read",
+                        name, line, column
+                    ));
+                }
+            }
         }
+    }
 
-        for output in prophet.outputs.iter() {
-            inf_var_insert!(output, current_scope);
+    fn check_self_comparison(&mut self, node: &BinOpNode) {
+        let always = match node.operator {
+            Token::Equal | Token::LessEqual | Token::GreaterEqual => true,
+            Token::NotEqual | Token::LessThan | Token::GreaterThan => false,
+            _ => return,
+        };
+        if same_expression(&node.left, &node.right) {
+            self.warn(format!(
+                "comparison '{} {} {}' compares an expression with itself, it is always {}",
+                expression_text(&node.left),
+                node.operator,
+                expression_text(&node.right),
+                always
+            ));
+        }
+    }
+
+    fn check_felt_range(&self, value: i128) -> Result<(), String> {
+        let field_params = &self.options.field_params;
+        if field_params.in_range(value) {
+            Ok(())
+        } else {
+            Err(format!(
+                "felt constant {} out of range for field modulus {}",
+                value, field_params.modulus
+            ))
+        }
+    }
+
+    fn check_special_constant(&mut self, node: &FeltNumNode) {
+        if !self.options.special_constant_notes {
+            return;
+        }
+        let modulus = self.options.field_params.modulus;
+        if let Some(special) = special_field_value(node.value, modulus) {
+            self.note(format!("felt constant {} is {}", node.literal, special));
+        }
+    }
+
+    fn check_case_collision(&mut self, scope: &SymbolTable, name: &str) {
+        if !self.options.case_collision_lint {
+            return;
+        }
+        if let Some(existing) = scope.case_collision(name) {
+            self.warn(format!(
+                "identifier '{}' differs from '{}' only by case in scope '{}', possible typo",
+                name, existing, scope.scope_name
+            ));
         }
-        drop(current_scope);
-        gen
     }
 }
 
 impl Traversal for SymTableGen {
     fn travel_entry(&mut self, node: &mut EntryNode) -> NumberResult {
-        for declaration in node.global_declarations.iter() {
-            self.travel(declaration)?;
+        self.check_statement_budget(node)?;
+        // Global declarations get their own scope, so a function never
+        // replaces a prophet symbol of the same name.
+        let prophet_scope = self.current_scope.clone();
+        let global_scope = SymbolTable::new(
+            "Global Scope".to_string(),
+            GLOBAL_SCOPE_LEVEL,
+            Some(prophet_scope),
+        );
+        self.current_scope = Arc::new(RwLock::new(global_scope));
+        if self.options.parallel_functions {
+            self.travel_globals_parallel(node)?;
+        } else {
+            for declaration in node.global_declarations.iter() {
+                self.travel(declaration)?;
+            }
         }
-        self.travel(&node.entry_block)
+        let ret = self.travel(&node.entry_block);
+        self.check_dead_stores(&body_statements(&node.entry_block));
+        self.check_prophet_usage();
+        // The entry, global and prophet scopes are never left.
+        let mut scope = Some(self.current_scope.clone());
+        while let Some(current) = scope {
+            self.collect_unused(&current);
+            scope = current.read().unwrap().enclosing_scope.clone();
+        }
+        ret
     }
     fn travel_block(&mut self, node: &mut BlockNode) -> NumberResult {
         for declaration in node.declarations.iter() {
@@ -85,42 +1995,96 @@ impl Traversal for SymTableGen {
 
     fn travel_entry_block(&mut self, node: &mut EntryBlockNode) -> NumberResult {
         let cur = self.current_scope.clone();
-        let scope_level = cur.read().unwrap().scope_level;
-        let cur_scope = SymbolTable::new(Token::Entry.to_string(), scope_level + 1, Some(cur));
+        if cur.read().unwrap().scope_level != GLOBAL_SCOPE_LEVEL {
+            return Err(SemaError::Other(
+                "entry block must be analyzed from the global scope".to_string(),
+            ));
+        }
+        let cur_scope = SymbolTable::new(Token::Entry.to_string(), LOCAL_SCOPE_LEVEL, Some(cur));
 
         self.current_scope = Arc::new(RwLock::new(cur_scope));
         for declaration in node.declarations.iter() {
             self.travel(declaration)?;
         }
-        self.travel(&node.compound_statement)
+        let ret = self.travel(&node.compound_statement);
+        self.check_array_accesses();
+        let regions = self.check_malloc_regions();
+        ret.and_then(|ret| regions.map(|_| ret).map_err(SemaError::from))
     }
 
     fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult {
+        let felt_length = node.felt_length;
+        let constant = node.constant;
         let IdentDeclarationNode {
-            ident_node: IdentNode { identifier },
+            ident_node: IdentNode { identifier, span },
             type_node: TypeNode { token },
+            ..
         } = node;
 
         if let Id(name) = identifier {
-            if self.current_scope.read().unwrap().lookup(name).is_some() {
+            self.check_builtin_shadowing(name)?;
+            let current = self.current_scope.clone();
+            let scope = current.read().unwrap();
+            if scope.symbols.contains_key(name.as_str()) {
+                return Err(SemaError::DuplicateDeclaration { name: name.clone() });
+            }
+            if let Some(outer) = scope.enclosing_declaration(name) {
+                let message = format!(
+                    "declaration of '{}' shadows the one in scope '{}'",
+                    name, outer
+                );
+                match self.options.shadowing {
+                    ShadowPolicy::Deny => return Err(message.into()),
+                    ShadowPolicy::WarnLog => self.warn(message),
+                    ShadowPolicy::Allow => {}
+                }
+            }
+            drop(scope);
+            if felt_length && self.options.strict_arithmetic {
                 return Err(format!(
-                    "Found duplicate variable declaration for '{}'!",
+                    "array '{}' has a felt length, strict mode requires an i32 length",
                     name
-                ));
+                )
+                .into());
             }
             debug!("insert id name:{}", name);
+            let style = self.options.naming_convention.variables;
+            self.check_naming("variable", name, style);
+            let scope = self.current_scope.clone();
+            self.check_case_collision(&scope.read().unwrap(), name);
+            if let Array(_, len) = token {
+                if *len > self.options.max_array_length {
+                    return Err(format!(
+                        "array '{}' has length {}, above the maximum of {}",
+                        name, len, self.options.max_array_length
+                    )
+                    .into());
+                }
+                self.check_magic_number(name, *len as i128, "its length");
+            }
             let mut current_scope = self.current_scope.write().unwrap();
-            if let Array(builtin_token, len) = token {
-                if let BuiltInSymbol(builtin) = current_scope.get(&builtin_token) {
-                    let variable = IdentSymbol(name.to_string(), builtin, Some(*len));
+            if let Array(row, len) = token {
+                self.array_accesses
+                    .insert(name.to_string(), (*len, Vec::new()));
+                // The rows of a multi-dimensional array are arrays themselves.
+                if let BuiltInSymbol(_) = current_scope.get(array_element(row)) {
+                    let variable = IdentSymbol(
+                        name.to_string(),
+                        BuiltIn(*row.clone()),
+                        Some(*len),
+                        constant,
+                    );
                     current_scope.insert(variable);
                 }
             } else if let BuiltInSymbol(builtin) = current_scope.get(&token) {
-                let variable = IdentSymbol(name.to_string(), builtin, None);
+                let variable = IdentSymbol(name.to_string(), builtin, None, constant);
                 current_scope.insert(variable);
             } else {
                 panic!("Invalid builtin type {}", token);
             }
+            if *span != (0, 0) {
+                current_scope.declared_at.insert(name.to_string(), *span);
+            }
         }
         Ok(Single(Nil))
     }
@@ -136,11 +2100,34 @@ impl Traversal for SymTableGen {
         Ok(Single(Number::I32(0)))
     }
 
-    fn travel_felt(&mut self, _node: &mut FeltNumNode) -> NumberResult {
+    fn travel_felt(&mut self, node: &mut FeltNumNode) -> NumberResult {
+        let bits = literal_bits(&node.literal);
+        let hex_digits = node.literal.strip_prefix("0x").map_or(0, |hex| hex.len());
+        if bits > 64 || hex_digits >= 64 {
+            self.warn(format!(
+                "felt literal {} looks like an address, use the address type instead",
+                node.literal
+            ));
+        }
+        if bits > 64 {
+            return Err(format!(
+                "felt constant {} out of range for field modulus {}",
+                node.literal, self.options.field_params.modulus
+            )
+            .into());
+        }
+        self.check_felt_range(node.value as i128)?;
+        self.check_special_constant(node);
         Ok(Single(Number::Felt(0)))
     }
 
     fn travel_array(&mut self, node: &mut ArrayNumNode) -> NumberResult {
+        check_array_rows(node)?;
+        for value in node.values.iter() {
+            if let Number::Felt(value) = value {
+                self.check_felt_range(*value)?;
+            }
+        }
         Ok(Single(Number::from(&node.values[0].number_type())))
     }
 
@@ -148,19 +2135,39 @@ impl Traversal for SymTableGen {
         if let IdentIndexNode {
             identifier: Id(name),
             index,
+            span,
         } = node
         {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
+            let symbol = self.current_scope.read().unwrap().lookup(&name);
+            let name = name.to_string();
+            self.record_array_access(&name, index);
+            self.record_region_access(&name, index);
+            self.record_use(&name, false);
+            if symbol.is_none() {
+                Err(self.undeclared("identifier", &name, *span))
             } else {
-                let value = self.travel(index)?;
-                Ok(value)
+                if let Some(IdentSymbol(_, BuiltIn(token), Some(_), _)) = &symbol {
+                    self.annotate(*span, &name, element_type_name(token));
+                }
+                self.travel_index(&name, index, *span)?;
+                let element = match &symbol {
+                    Some(symbol @ IdentSymbol(_, BuiltIn(token), _, _)) => {
+                        Number::from(&indexed_type(symbol).unwrap_or_else(|| token.clone()))
+                    }
+                    _ => Nil,
+                };
+                if let Some(IdentSymbol(_, BuiltIn(token), Some(_), _)) = &symbol {
+                    let expression = format!("{}[{}]", name, expression_text(index));
+                    self.record_type(*span, expression, &Ok(Single(Number::from(token))));
+                }
+                Ok(Single(element))
             }
         } else {
             Err(format!(
                 "Invalid identifier found travel_context_ident{}",
                 node.identifier
-            ))
+            )
+            .into())
         }
     }
 
@@ -176,11 +2183,149 @@ impl Traversal for SymTableGen {
             Single(num) => num,
             Multiple(nums) => nums[0].clone(),
         };
-        let binop_type = left_type.binop_number_type(&right_type);
-        Ok(Single(Number::from(&binop_type)))
+        if self.options.overflow_mode == OverflowMode::Checked {
+            self.check_i32_overflow(node)?;
+        }
+        if self.options.felt_overflow_check {
+            self.check_felt_overflow(node)?;
+        }
+        let numeric = |number: &Number| matches!(number, Number::I32(_) | Number::Felt(_));
+        if is_arithmetic(&node.operator) && !(numeric(&left_type) && numeric(&right_type)) {
+            let (line, column) = leftmost_span(&node.left);
+            let found = format!(
+                "{} and {}",
+                number_type_name(&left_type),
+                number_type_name(&right_type)
+            );
+            let message = format!(
+                "operator {} at {}:{} expects felt or i32 operands, found {}",
+                node.operator, line, column, found
+            );
+            return Err(SemaError::type_mismatch("felt or i32", &found, message));
+        }
+        self.check_self_comparison(node);
+        let ret = if is_comparison(&node.operator) {
+            // Numbers compare with numbers, bools only for equality.
+            let bools = matches!(
+                (&left_type, &right_type),
+                (Number::Bool(_), Number::Bool(_))
+            );
+            let equality = matches!(node.operator, Token::Equal | Token::NotEqual);
+            if !(numeric(&left_type) && numeric(&right_type) || bools && equality) {
+                let (line, column) = leftmost_span(&node.left);
+                let found = format!(
+                    "{} and {}",
+                    number_type_name(&left_type),
+                    number_type_name(&right_type)
+                );
+                let message = format!(
+                    "operator {} at {}:{} cannot compare {}",
+                    node.operator, line, column, found
+                );
+                return Err(SemaError::type_mismatch(
+                    "comparable operands",
+                    &found,
+                    message,
+                ));
+            }
+            Ok(Single(Number::Bool(false)))
+        } else if matches!(node.operator, Token::And | Token::Or) {
+            if !matches!(
+                (&left_type, &right_type),
+                (Number::Bool(_), Number::Bool(_))
+            ) {
+                let (line, column) = leftmost_span(&node.left);
+                let found = format!(
+                    "{} and {}",
+                    number_type_name(&left_type),
+                    number_type_name(&right_type)
+                );
+                let message = format!(
+                    "operator {} at {}:{} expects bool operands, found {}",
+                    node.operator, line, column, found
+                );
+                return Err(SemaError::type_mismatch("bool", &found, message));
+            }
+            Ok(Single(Number::Bool(false)))
+        } else {
+            let binop_type = left_type.binop_number_type(&right_type);
+            if node.operator == Token::IntegerDivision && binop_type == Felt {
+                if let Some(array) = self.index_of.clone() {
+                    self.warn(format!(
+                        "felt division in the index of '{}' is a field inverse, not a truncating \
+                         division, use i32 operands",
+                        array
+                    ));
+                }
+            }
+            Ok(Single(Number::from(&binop_type)))
+        };
+        if self.options.dump_types != TypeDump::Off {
+            let span = match leftmost_span(&node.left) {
+                (0, 0) => leftmost_span(&node.right),
+                span => span,
+            };
+            let expression = format!(
+                "{} {} {}",
+                expression_text(&node.left),
+                node.operator,
+                expression_text(&node.right)
+            );
+            self.record_type(span, expression, &ret);
+        }
+        ret
     }
     fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
-        self.travel(&node.expr)
+        let ret = self.travel(&node.expr)?;
+        if let Single(Number::Str(_)) = ret {
+            let (line, column) = leftmost_span(&node.expr);
+            let message = format!(
+                "operator {} at {}:{} expects felt or i32 operands, found string",
+                node.operator, line, column
+            );
+            return Err(SemaError::type_mismatch("felt or i32", "string", message));
+        }
+        Ok(ret)
+    }
+
+    fn travel_ternary(&mut self, node: &mut TernaryNode) -> NumberResult {
+        let mut types = Vec::new();
+        for expr in [&node.condition, &node.then_expr, &node.else_expr] {
+            types.push(match self.travel(expr)? {
+                Single(number) => number,
+                Multiple(numbers) => numbers[0].clone(),
+            });
+        }
+        let (line, column) = leftmost_span(&node.condition);
+        if !matches!(types[0], Number::Bool(_)) {
+            let message = format!(
+                "ternary condition {} at {}:{} has type {}, expected bool",
+                expression_text(&node.condition),
+                line,
+                column,
+                number_type_name(&types[0])
+            );
+            return Err(SemaError::type_mismatch(
+                "bool",
+                number_type_name(&types[0]),
+                message,
+            ));
+        }
+        if number_type_name(&types[1]) != number_type_name(&types[2]) {
+            let message = format!(
+                "ternary branches at {}:{} have different types, {} and {}",
+                line,
+                column,
+                number_type_name(&types[1]),
+                number_type_name(&types[2])
+            );
+            return Err(SemaError::type_mismatch(
+                number_type_name(&types[1]),
+                number_type_name(&types[2]),
+                message,
+            ));
+        }
+        Ok(Single(types.swap_remove(1)))
     }
 
     fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult {
@@ -192,77 +2337,156 @@ impl Traversal for SymTableGen {
 
     fn travel_assign(&mut self, node: &mut AssignNode) -> NumberResult {
         debug!("sema assign id:{}", node.identifier);
+        let region = self.malloc_size(&node.expr);
+        let mut target = None;
         if let Id(name) = &mut node.identifier {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                return Err(format!("assign Undeclared variable {} found.", name));
+            let symbol = self.resolve(name);
+            if symbol.is_none() {
+                return Err(self.undeclared("assign", &name, node.span));
             } else {
-                let symbol = self.current_scope.read().unwrap().lookup(&name).unwrap();
-                if let IdentSymbol(_ident, BuiltIn(_token), size) = symbol {
+                let symbol = symbol.unwrap();
+                let name = name.to_string();
+                self.check_declared_before(&name, node.span)?;
+                self.record_use(&name, true);
+                self.record_region(&name, region);
+                if let IdentSymbol(_, _, size, constant) = &symbol {
+                    if *constant && !node.initializer {
+                        return Err(SemaError::ConstAssignment { name });
+                    }
                     if size.is_some() {
                         node.identifier = ArrayId(name.to_string());
                     }
+                    target = symbol_type(symbol).map(|token| (name, token));
                 }
             }
         } else if let Cid(name) = &node.identifier {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                return Err(format!("assign Undeclared variable {} found.", name));
+            if self.resolve(name).is_none() {
+                return Err(self.undeclared("assign", &name, node.span));
+            }
+            let name = name.to_string();
+            self.record_use(&name, true);
+        } else if let IndexId(name, index) = &node.identifier {
+            let symbol = self.resolve(name);
+            if symbol.is_none() {
+                return Err(self.undeclared("assign", name, node.span));
+            }
+            self.check_declared_before(name, node.span)?;
+            if let Some(IdentSymbol(_, _, _, true)) = symbol {
+                return Err(SemaError::ConstAssignment { name: name.clone() });
+            }
+            self.record_array_access(name, index);
+            self.record_region_access(name, index);
+            self.record_use(name, true);
+            self.travel_index(name, index, node.span)?;
+            if self.array_params.contains(name) {
+                let func_name = self.current_function.clone().unwrap_or_default();
+                self.warn(format!(
+                    "array parameter '{}' of function '{}' is written, arrays are passed by value",
+                    name, func_name
+                ));
+            }
+        }
+        let ret = self.travel(&node.expr)?;
+        if let Some((name, target)) = target {
+            if let Some(value) = self.assigned_type(&node.expr, &ret) {
+                if value != target && widened(&value) != target {
+                    let (value, target) = (value_type_name(&value), value_type_name(&target));
+                    let message = format!(
+                        "cannot assign {} to variable '{}' of type {}",
+                        value, name, target
+                    );
+                    return Err(SemaError::type_mismatch(&target, &value, message));
+                }
             }
         }
-        self.travel(&node.expr)
+        Ok(ret)
     }
 
     fn travel_ident(&mut self, node: &mut IdentNode) -> NumberResult {
         if let IdentNode {
             identifier: Id(name),
+            ..
         } = node
         {
-            let ident = self.current_scope.read().unwrap().lookup(&name);
+            let ident = self.resolve(name);
             if ident.is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
+                Err(self.undeclared("identifier", &name, node.span))
             } else {
-                if let Some(IdentSymbol(_ident, BuiltIn(token), size)) = ident {
+                if let Some(IdentSymbol(_ident, BuiltIn(token), size, _)) = ident {
+                    let type_name = symbol_type_name(&token, size);
+                    let name = name.to_string();
+                    self.check_declared_before(&name, node.span)?;
+                    self.record_use(&name, false);
+                    self.annotate(node.span, &name, type_name);
                     if size.is_some() {
                         node.identifier = ArrayId(name.to_string());
                     }
-                    if size.is_some() {
-                        return Ok(Single(number_from_token(&token, size.unwrap())));
-                    }
-                    Ok(Single(Number::from(&token)))
+                    let ret = match size {
+                        Some(len) => Ok(Single(number_from_token(&token, len))),
+                        None => Ok(Single(Number::from(&token))),
+                    };
+                    self.record_type(node.span, name, &ret);
+                    ret
                 } else {
-                    panic!("ident not support symbol type")
+                    let message = format!(
+                        "'{}' at {}:{} is not a variable",
+                        name, node.span.0, node.span.1
+                    );
+                    Err(SemaError::type_mismatch("variable", "function", message))
                 }
             }
         } else {
-            Err(format!(
-                "Invalid identifier found travel_ident{}",
-                node.identifier
-            ))
+            Err(format!("Invalid identifier found travel_ident{}", node.identifier).into())
         }
     }
 
     fn travel_context_ident(&mut self, node: &mut ContextIdentNode) -> NumberResult {
         if let ContextIdentNode {
             identifier: Cid(name),
+            span,
         } = node
         {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
+            if self.resolve(name).is_none() {
+                Err(self.undeclared("identifier", &name, *span))
             } else {
+                let name = name.to_string();
+                self.record_use(&name, false);
                 Ok(Single(Nil))
             }
         } else {
             Err(format!(
                 "Invalid identifier found travel_context_ident{}",
                 node.identifier
-            ))
+            )
+            .into())
         }
     }
 
     fn travel_cond(&mut self, node: &mut CondStatNode) -> NumberResult {
-        self.travel(&node.condition)?;
-
-        for expr in node.consequences.iter() {
-            self.travel(expr)?;
+        self.check_duplicate_conditions(node);
+        for (condition, consequences) in node.branches() {
+            let condition_type = match self.travel(condition)? {
+                Single(number) => number,
+                Multiple(numbers) => numbers[0].clone(),
+            };
+            if !matches!(condition_type, Number::Bool(_)) {
+                let (line, column) = leftmost_span(condition);
+                let message = format!(
+                    "if condition {} at {}:{} has type {}, expected bool",
+                    expression_text(condition),
+                    line,
+                    column,
+                    number_type_name(&condition_type)
+                );
+                return Err(SemaError::type_mismatch(
+                    "bool",
+                    number_type_name(&condition_type),
+                    message,
+                ));
+            }
+            for expr in consequences.iter() {
+                self.travel(expr)?;
+            }
         }
 
         for expr in node.alternatives.iter() {
@@ -274,55 +2498,82 @@ impl Traversal for SymTableGen {
 
     fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
         self.travel(&node.condition)?;
-        for expr in node.consequences.iter() {
-            self.travel(expr)?;
+        if let Some(invariant) = &node.invariant {
+            self.travel(invariant)?;
         }
+        self.travel_loop_body(&node.consequences)
+    }
 
-        Ok(Single(Nil))
+    fn travel_while(&mut self, node: &mut WhileStatNode) -> NumberResult {
+        let condition_type = self.condition_type(&node.condition)?;
+        if !matches!(condition_type, Number::Bool(_)) {
+            let (line, column) = leftmost_span(&node.condition);
+            let message = format!(
+                "while condition {} at {}:{} is not a bool condition",
+                expression_text(&node.condition),
+                line,
+                column
+            );
+            return Err(SemaError::type_mismatch(
+                "bool",
+                number_type_name(&condition_type),
+                message,
+            ));
+        }
+        self.travel_loop_body(&node.consequences)
     }
 
-    fn travel_function(&mut self, node: &mut FunctionNode) -> NumberResult {
-        if let Id(func_name) = &node.func_name {
-            let mut param_symbols = Vec::new();
-            let mut param_scope = HashMap::new();
-            for param_node in &node.params {
-                let mut param = param_node.write().unwrap();
-                let param = param
-                    .as_any_mut()
-                    .downcast_mut::<IdentDeclarationNode>()
-                    .unwrap();
-                let name = param.ident_node.identifier.to_string();
+    fn travel_break(&mut self, node: &mut BreakNode) -> NumberResult {
+        if self.loop_depth == 0 {
+            return Err(format!(
+                "break at {}:{} is outside of a loop",
+                node.span.0, node.span.1
+            )
+            .into());
+        }
+        Ok(Single(Nil))
+    }
 
-                let ident_type = BuiltIn(param.type_node.token.clone());
+    fn travel_continue(&mut self, node: &mut ContinueNode) -> NumberResult {
+        if self.loop_depth == 0 {
+            return Err(format!(
+                "continue at {}:{} is outside of a loop",
+                node.span.0, node.span.1
+            )
+            .into());
+        }
+        Ok(Single(Nil))
+    }
 
-                let mut token_len = None;
-                if let Array(_token, len) = &param.type_node.token {
-                    token_len = Some(*len);
-                    param.ident_node.identifier = ArrayId(name.to_string());
-                }
+    // An invariant must be a comparison or a logical combination of
+    // comparisons. Constant invariants are checked right away.
+    fn travel_invariant(&mut self, node: &mut InvariantNode) -> NumberResult {
+        let condition_type = self.condition_type(&node.condition)?;
+        let text = expression_text(&node.condition);
+        if !matches!(condition_type, Number::Bool(_)) {
+            let message = format!(
+                "loop invariant {} at {}:{} is not a bool condition",
+                text, node.span.0, node.span.1
+            );
+            return Err(SemaError::type_mismatch(
+                "bool",
+                number_type_name(&condition_type),
+                message,
+            ));
+        }
+        if let Some(Number::Bool(false)) = self.const_eval(&node.condition) {
+            return Err(format!(
+                "loop invariant {} at {}:{} never holds",
+                text, node.span.0, node.span.1
+            )
+            .into());
+        }
+        Ok(Single(Nil))
+    }
 
-                let ident = (
-                    param.ident_node.identifier.to_string(),
-                    BuiltIn(param.type_node.token.clone()),
-                );
-                param_symbols.push(ident);
-                let symbol = IdentSymbol(name.clone(), ident_type, token_len);
-                param_scope.insert(name.clone(), symbol);
-            }
-            let func_symbol = FuncSymbol(func_name.to_string(), param_symbols, node.block.clone());
-            self.current_scope
-                .write()
-                .unwrap()
-                .symbols
-                .insert(func_name.to_string(), func_symbol);
-            let cur = self.current_scope.clone();
-            let scope_level = cur.read().unwrap().scope_level;
-            let mut cur_scope = SymbolTable::new(func_name.to_string(), scope_level + 1, Some(cur));
-            cur_scope.symbols = param_scope;
-            self.current_scope = Arc::new(RwLock::new(cur_scope));
-            self.travel(&node.block)?;
-            let enclosing_scope = self.current_scope.read().unwrap().enclosing_scope.clone();
-            self.current_scope = enclosing_scope.unwrap();
+    fn travel_function(&mut self, node: &mut FunctionNode) -> NumberResult {
+        if let Some(function) = self.register_function(node) {
+            self.analyze_function(function)?;
         }
         Ok(Single(Nil))
     }
@@ -344,21 +2595,57 @@ impl Traversal for SymTableGen {
 
             actual_types.push(param_type);
         }
+        self.check_array_aliasing(node);
+        let caller = self
+            .current_function
+            .clone()
+            .unwrap_or_else(|| "entry".to_string());
+        self.call_graph
+            .entry(caller)
+            .or_default()
+            .insert(node.func_name.to_string());
         if let Some(func_symbol) = symbol {
-            if let FuncSymbol(name, params, body) = func_symbol {
-                for (index, item) in params.iter().enumerate() {
-                    if !Number::from(&item.1 .0).eq(&actual_types.get(index).unwrap()) {
-                        panic!("function params type not match")
+            if let FuncSymbol(name, params, returns, inferred, body) = func_symbol {
+                if params.len() != actual_types.len() {
+                    return Err(SemaError::ArityMismatch {
+                        func: name,
+                        expected: params.len(),
+                        got: actual_types.len(),
+                        arity: Arity::Args,
+                    });
+                }
+                for (index, (param, param_type)) in params.iter().enumerate() {
+                    let actual = &actual_types[index];
+                    // Values encode array lengths, so this also checks them.
+                    let numeric = matches!(actual, Number::I32(_) | Number::Felt(_));
+                    if !(numeric && Number::from(&param_type.0).eq(actual)) {
+                        let expected = type_name(param_type);
+                        let message = format!(
+                            "argument '{}' of function '{}' has type {}, expected {}",
+                            param,
+                            name,
+                            number_type_name(actual),
+                            expected
+                        );
+                        return Err(SemaError::type_mismatch(
+                            &expected,
+                            number_type_name(actual),
+                            message,
+                        ));
                     }
                 }
-                node.func_symbol = Some(Arc::new(RwLock::new(FuncSymbol(name, params, body))));
+                let ret = declared_return(&returns);
+                node.func_symbol = Some(Arc::new(RwLock::new(FuncSymbol(
+                    name, params, returns, inferred, body,
+                ))));
+                Ok(ret)
             } else {
-                panic!("not support symbol for function")
+                let message = format!("'{}' is called but is not a function", node.func_name);
+                Err(SemaError::type_mismatch("function", "variable", message))
             }
         } else {
-            panic!("not found function");
+            Err(self.undeclared("call", &node.func_name.to_string(), (0, 0)))
         }
-        Ok(Single(Nil))
     }
 
     fn travel_sqrt(&mut self, node: &mut SqrtNode) -> NumberResult {
@@ -366,6 +2653,7 @@ impl Traversal for SymTableGen {
     }
 
     fn travel_return(&mut self, node: &mut ReturnNode) -> NumberResult {
+        let mut return_types = Vec::new();
         for ret in &node.returns {
             if is_node_type::<IdentNode>(ret) {
                 let mut ident = ret.write().unwrap();
@@ -373,42 +2661,82 @@ impl Traversal for SymTableGen {
 
                 let name = ident.identifier.clone().to_string();
                 if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
+                    return Err(self.undeclared("assign", &name, ident.span));
                 } else {
-                    if let IdentSymbol(name, BuiltIn(_token), size) =
+                    self.record_use(&name, false);
+                    if let IdentSymbol(name, BuiltIn(token), size, _) =
                         self.current_scope.read().unwrap().lookup(&name).unwrap()
                     {
                         if size.is_some() {
                             ident.identifier = ArrayId(name.to_string());
                         }
+                        return_types.push(match size {
+                            Some(len) => number_from_token(&token, len),
+                            None => Number::from(&token),
+                        });
                     }
                 }
+            } else {
+                return_types.push(match self.travel(ret)? {
+                    Single(num) => num,
+                    Multiple(nums) => number_from_token(&nums[0].number_type(), nums.len()),
+                });
             }
         }
+        if let Some(function) = self.current_function.clone() {
+            self.return_types = Some(match self.return_types.take() {
+                Some(previous) => merge_return_types(&function, previous, return_types)?,
+                None => return_types,
+            });
+        }
         Ok(Single(Nil))
     }
 
     fn travel_multi_assign(&mut self, node: &mut MultiAssignNode) -> NumberResult {
+        let mut targets = Vec::new();
         for node in node.identifier.iter() {
-            if is_node_type::<IdentNode>(node) {
-                let ident = &safe_downcast_ref::<IdentNode>(node).identifier.clone();
-                let name = ident.to_string();
-                if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
+            // Name, span and whether it is a context identifier, for targets
+            // that are not declarations.
+            let ident = {
+                let target = node.read().unwrap();
+                let target = target.as_any();
+                if let Some(ident) = target.downcast_ref::<IdentNode>() {
+                    Some((ident.identifier.to_string(), ident.span, false))
+                } else {
+                    target
+                        .downcast_ref::<ContextIdentNode>()
+                        .map(|ident| (ident.identifier.to_string(), ident.span, true))
                 }
-            } else if is_node_type::<ContextIdentNode>(node) {
-                let ident = &safe_downcast_ref::<ContextIdentNode>(node)
-                    .identifier
-                    .clone();
-                let name = ident.to_string();
-                if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
+            };
+            if let Some((name, span, context)) = ident {
+                let symbol = self.current_scope.read().unwrap().lookup(&name);
+                if symbol.is_none() {
+                    return Err(self.undeclared("assign", &name, span));
+                }
+                if let Some(IdentSymbol(_, _, _, true)) = symbol {
+                    if !context {
+                        return Err(SemaError::ConstAssignment { name });
+                    }
+                }
+                self.record_use(&name, true);
+                if !context {
+                    self.record_region(&name, None);
                 }
+                targets.push((name, symbol.and_then(symbol_type), context));
             } else {
                 self.travel(node)?;
+                let node = node.read().unwrap();
+                if let Some(declaration) = node.as_any().downcast_ref::<IdentDeclarationNode>() {
+                    targets.push((
+                        declaration.ident_node.identifier.to_string(),
+                        Some(declaration.type_node.token.clone()),
+                        false,
+                    ));
+                }
             }
         }
         self.travel(&node.call)?;
+        self.check_multi_assign_types(&node.call, &targets)?;
         Ok(Single(Nil))
     }
 
@@ -416,8 +2744,20 @@ impl Traversal for SymTableGen {
         self.travel(&node.num_bytes)
     }
 
+    // The flag selects how the value is formatted, so it must be known when
+    // the program is compiled.
     fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult {
         self.travel(&node.flag)?;
+        if self.const_eval(&node.flag).is_none() {
+            let (line, column) = leftmost_span(&node.flag);
+            return Err(format!(
+                "printf format flag {} at {}:{} is not a constant",
+                expression_text(&node.flag),
+                line,
+                column
+            )
+            .into());
+        }
         let ret = self.travel(&node.val_addr);
         ret
     }
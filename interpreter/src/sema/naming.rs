@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// Spelling of a multi-word identifier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamingStyle {
+    /// `max_supply`
+    SnakeCase,
+    /// `maxSupply`
+    CamelCase,
+    /// `MaxSupply`
+    PascalCase,
+}
+
+impl NamingStyle {
+    pub fn matches(&self, name: &str) -> bool {
+        let first = match name.chars().next() {
+            Some(first) => first,
+            None => return true,
+        };
+        match self {
+            NamingStyle::SnakeCase => !name.chars().any(|c| c.is_ascii_uppercase()),
+            NamingStyle::CamelCase => !first.is_ascii_uppercase() && !name.contains('_'),
+            NamingStyle::PascalCase => first.is_ascii_uppercase() && !name.contains('_'),
+        }
+    }
+}
+
+impl fmt::Display for NamingStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NamingStyle::SnakeCase => "snake_case",
+            NamingStyle::CamelCase => "camelCase",
+            NamingStyle::PascalCase => "PascalCase",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Style each kind of declared name must follow, `None` leaving the kind
+/// unchecked. Violations are warnings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NamingConvention {
+    /// Local variables and function parameters.
+    pub variables: Option<NamingStyle>,
+    pub functions: Option<NamingStyle>,
+}
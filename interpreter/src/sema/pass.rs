@@ -0,0 +1,77 @@
+use crate::parser::node::Node;
+use crate::parser::traversal::Traversal;
+use crate::sema::error::SemaError;
+use crate::sema::SymTableGen;
+use std::sync::{Arc, RwLock};
+
+/// A custom analysis run over the AST once `SymTableGen` has analyzed it.
+/// The traversal methods a pass does not override visit the children, so it
+/// only implements the nodes it inspects.
+pub trait AnalysisPass: Traversal {
+    fn name(&self) -> &str;
+
+    /// Called with the finished analysis before the pass travels the AST.
+    fn prepare(&mut self, _gen: &SymTableGen) {}
+
+    /// Findings of the pass, taken once it has travelled the AST.
+    fn diagnostics(&mut self) -> Vec<String>;
+}
+
+/// A finding of the analysis, with the pass reporting it, "sema" for the
+/// warnings of `SymTableGen`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassDiagnostic {
+    pub pass: String,
+    pub message: String,
+}
+
+/// Runs `SymTableGen` and then the registered passes, in registration order.
+#[derive(Default)]
+pub struct PassDriver {
+    passes: Vec<Box<dyn AnalysisPass>>,
+}
+
+impl PassDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pass(mut self, pass: Box<dyn AnalysisPass>) -> Self {
+        self.passes.push(pass);
+        self
+    }
+
+    /// Analyzes `root` with `gen`, then runs every pass over it. Returns the
+    /// warnings of `gen` followed by the diagnostics of each pass, or the
+    /// first error.
+    pub fn run(
+        &mut self,
+        root: &Arc<RwLock<dyn Node>>,
+        gen: &mut SymTableGen,
+    ) -> Result<Vec<PassDiagnostic>, SemaError> {
+        gen.travel(root)?;
+        let mut diagnostics: Vec<PassDiagnostic> = gen
+            .warnings
+            .iter()
+            .map(|message| PassDiagnostic {
+                pass: "sema".to_string(),
+                message: message.clone(),
+            })
+            .collect();
+        for pass in self.passes.iter_mut() {
+            pass.prepare(gen);
+            pass.travel(root)
+                .map_err(|err| SemaError::Other(format!("pass {}: {}", pass.name(), err)))?;
+            let name = pass.name().to_string();
+            diagnostics.extend(
+                pass.diagnostics()
+                    .into_iter()
+                    .map(|message| PassDiagnostic {
+                        pass: name.clone(),
+                        message,
+                    }),
+            );
+        }
+        Ok(diagnostics)
+    }
+}
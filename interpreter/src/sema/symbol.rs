@@ -1,10 +1,15 @@
 use crate::lexer::token::Token;
 use crate::parser::node::Node;
 use crate::sema::symbol::Symbol::{BuiltInSymbol, FuncSymbol, IdentSymbol};
+use crate::utils::number::NumberRet;
+use serde_json::json;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::{Arc, RwLock};
 
+/// Builtin functions, spelled in lowercase.
+pub const BUILTIN_FUNCTIONS: &[&str] = &["sqrt", "malloc", "printf"];
+
 #[derive(Clone, PartialEq)]
 pub struct BuiltIn(pub Token);
 
@@ -20,8 +25,18 @@ impl BuiltIn {
 #[derive(Clone)]
 pub enum Symbol {
     BuiltInSymbol(BuiltIn),
-    IdentSymbol(String, BuiltIn, Option<usize>),
-    FuncSymbol(String, Vec<(String, BuiltIn)>, Arc<RwLock<dyn Node>>),
+    /// Name, type, array length and whether the variable is const.
+    IdentSymbol(String, BuiltIn, Option<usize>, bool),
+    /// Name, parameters, declared return types, return types inferred from
+    /// the return statements once the body is analyzed, and body of a
+    /// function.
+    FuncSymbol(
+        String,
+        Vec<(String, BuiltIn)>,
+        Vec<BuiltIn>,
+        Option<NumberRet>,
+        Arc<RwLock<dyn Node>>,
+    ),
 }
 
 #[derive(Clone)]
@@ -29,6 +44,16 @@ pub struct SymbolTable {
     pub scope_name: String,
     pub scope_level: u32,
     pub symbols: HashMap<String, Symbol>,
+    pub canonical_names: HashMap<String, String>,
+    /// Reads and writes of the variables declared in this scope.
+    pub read_counts: HashMap<String, usize>,
+    pub write_counts: HashMap<String, usize>,
+    /// Source position of the variables declared in this scope, for those
+    /// declared in the source.
+    pub declared_at: HashMap<String, (usize, usize)>,
+    /// Variables of enclosing scopes that names used in this scope resolved
+    /// to. A name is dropped when this scope declares it.
+    pub resolved: HashMap<String, Symbol>,
     pub enclosing_scope: Option<Arc<RwLock<SymbolTable>>>,
 }
 
@@ -43,6 +68,11 @@ impl SymbolTable {
             scope_name,
             scope_level,
             symbols,
+            canonical_names: HashMap::new(),
+            read_counts: HashMap::new(),
+            write_counts: HashMap::new(),
+            declared_at: HashMap::new(),
+            resolved: HashMap::new(),
             enclosing_scope,
         };
         symbol_table.initialise_builtins();
@@ -63,7 +93,11 @@ impl SymbolTable {
         }
     }
     pub fn insert(&mut self, symbol: Symbol) {
-        if let IdentSymbol(key, _kind, _) = symbol.clone() {
+        if let IdentSymbol(key, _kind, _, _) = symbol.clone() {
+            self.canonical_names
+                .entry(key.to_lowercase())
+                .or_insert(key.clone());
+            self.resolved.remove(&key);
             self.symbols.insert(key, symbol);
         } else {
             panic!("{}", format!("Error, Invalid Symbol! {}", symbol));
@@ -82,6 +116,135 @@ impl SymbolTable {
             Some(symbol) => Some(symbol.clone()),
         }
     }
+    // Looks `key` up like `lookup`, memoizing variables found in enclosing
+    // scopes. Enclosing scopes are complete by the time a nested scope is
+    // analyzed, so a memoized variable only goes stale when this scope
+    // declares the name.
+    pub fn resolve(&mut self, key: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.symbols.get(key).or_else(|| self.resolved.get(key)) {
+            return Some(symbol.clone());
+        }
+        let symbol = self.enclosing_scope.as_ref()?.read().unwrap().lookup(key)?;
+        if let IdentSymbol(..) = symbol {
+            self.resolved.insert(key.to_string(), symbol.clone());
+        }
+        Some(symbol)
+    }
+    // Returns the name of the nearest enclosing scope declaring `key`.
+    pub fn enclosing_declaration(&self, key: &str) -> Option<String> {
+        let enclosing = self.enclosing_scope.as_ref()?.read().unwrap();
+        if enclosing.symbols.contains_key(key) {
+            Some(enclosing.scope_name.clone())
+        } else {
+            enclosing.enclosing_declaration(key)
+        }
+    }
+    // Returns where the nearest scope declaring `key` declares it, if known.
+    pub fn declaration_span(&self, key: &str) -> Option<(usize, usize)> {
+        if self.symbols.contains_key(key) {
+            self.declared_at.get(key).copied()
+        } else {
+            self.enclosing_scope
+                .as_ref()?
+                .read()
+                .unwrap()
+                .declaration_span(key)
+        }
+    }
+    // Counts a read or write of `key` in the nearest scope declaring it.
+    pub fn record_access(&mut self, key: &str, write: bool) {
+        if self.symbols.contains_key(key) {
+            let counts = if write {
+                &mut self.write_counts
+            } else {
+                &mut self.read_counts
+            };
+            *counts.entry(key.to_string()).or_default() += 1;
+        } else if let Some(enclosing) = &self.enclosing_scope {
+            enclosing.write().unwrap().record_access(key, write);
+        }
+    }
+    // Adds the accesses `copy` counted since it was cloned from `base` to this
+    // scope's counts.
+    pub fn merge_access_counts(&mut self, copy: &SymbolTable, base: &SymbolTable) {
+        let pairs = [
+            (&mut self.read_counts, &copy.read_counts, &base.read_counts),
+            (
+                &mut self.write_counts,
+                &copy.write_counts,
+                &base.write_counts,
+            ),
+        ];
+        for (counts, copied, based) in pairs {
+            for (name, count) in copied {
+                let added = count - based.get(name).copied().unwrap_or_default();
+                if added > 0 {
+                    *counts.entry(name.clone()).or_default() += added;
+                }
+            }
+        }
+    }
+    // Returns the variables declared in this scope that are never read, sorted.
+    pub fn unread_variables(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .symbols
+            .iter()
+            .filter(|(name, symbol)| {
+                matches!(symbol, IdentSymbol(..)) && !self.read_counts.contains_key(*name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+    // Returns the name declared in this scope that differs from `key` only by case.
+    pub fn case_collision(&self, key: &str) -> Option<String> {
+        match self.canonical_names.get(&key.to_lowercase()) {
+            Some(exact) if exact != key => Some(exact.clone()),
+            _ => None,
+        }
+    }
+    // Returns the symbols of this scope, sorted by name. Arrays report their
+    // element token and length, the rows of a multi-dimensional array being
+    // its elements.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut names: Vec<&String> = self.symbols.keys().collect();
+        names.sort();
+        let symbols: Vec<serde_json::Value> = names
+            .into_iter()
+            .map(|name| {
+                let (kind, builtin, array_length) = match &self.symbols[name] {
+                    BuiltInSymbol(builtin) => ("BuiltInSymbol", Some(&builtin.0), None),
+                    IdentSymbol(_, BuiltIn(Token::Array(element, len)), None, _) => {
+                        ("IdentSymbol", Some(element.as_ref()), Some(*len))
+                    }
+                    IdentSymbol(_, builtin, len, _) => ("IdentSymbol", Some(&builtin.0), *len),
+                    FuncSymbol(..) => ("FuncSymbol", None, None),
+                };
+                json!({
+                    "name": name,
+                    "kind": kind,
+                    "builtin": builtin.map(|token| token.to_string()),
+                    "array_length": array_length,
+                })
+            })
+            .collect();
+        json!({
+            "scope_name": self.scope_name,
+            "scope_level": self.scope_level,
+            "symbols": symbols,
+        })
+    }
+    // Returns `to_json` of this scope with its enclosing scopes nested under
+    // "enclosing_scope", null for the outermost one.
+    pub fn to_json_recursive(&self) -> serde_json::Value {
+        let mut value = self.to_json();
+        value["enclosing_scope"] = match &self.enclosing_scope {
+            Some(enclosing) => enclosing.read().unwrap().to_json_recursive(),
+            None => serde_json::Value::Null,
+        };
+        value
+    }
     fn initialise_builtins(&mut self) {
         let u32_type = BuiltIn::new(Token::I32);
         let felt_type = BuiltIn::new(Token::Felt);
@@ -103,8 +266,8 @@ impl fmt::Display for Symbol {
             "{}",
             match self {
                 BuiltInSymbol(symbol) => symbol.to_string(),
-                IdentSymbol(key, symbol, size) => format!("{}: {},size:{:?}", key, symbol, size),
-                FuncSymbol(func_name, params, _) => {
+                IdentSymbol(key, symbol, size, _) => format!("{}: {},size:{:?}", key, symbol, size),
+                FuncSymbol(func_name, params, _, _, _) => {
                     let mut output: String = String::new();
                     for param in params {
                         let (name, kind) = param;
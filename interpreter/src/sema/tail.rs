@@ -0,0 +1,94 @@
+use crate::lexer::token::Token::Id;
+use crate::parser::node::{
+    AssignNode, CallNode, CondStatNode, IdentNode, LoopStatNode, MultiAssignNode, Node, ReturnNode,
+    WhileStatNode,
+};
+use std::sync::{Arc, RwLock};
+
+fn mark(call: &Arc<RwLock<dyn Node>>) -> bool {
+    let mut call = call.write().unwrap();
+    match call.as_any_mut().downcast_mut::<CallNode>() {
+        Some(call) => {
+            call.tail_call = true;
+            true
+        }
+        None => false,
+    }
+}
+
+fn ident_names(nodes: &[Arc<RwLock<dyn Node>>]) -> Option<Vec<String>> {
+    nodes
+        .iter()
+        .map(|node| {
+            let node = node.read().unwrap();
+            let ident = node.as_any().downcast_ref::<IdentNode>()?;
+            Some(ident.identifier.to_string())
+        })
+        .collect()
+}
+
+// Values returned by `next`: `Some(vec![])` for a bare return, `None` if it
+// is not a return of plain identifiers.
+fn returned_names(next: Option<&Arc<RwLock<dyn Node>>>) -> Option<Vec<String>> {
+    let next = next?.read().unwrap();
+    let ret = next.as_any().downcast_ref::<ReturnNode>()?;
+    ident_names(&ret.returns)
+}
+
+/// Flags the calls in tail position among `statements`, those whose result,
+/// if any, is returned right away: `return f(x);`, `f(x); return;`,
+/// `y = f(x); return y;`, `(a, b) = f(x); return (a, b);` and, when
+/// `falls_off_end`, a call statement ending the body of a function that
+/// returns nothing. Calls at the end of a loop body loop back and are never
+/// in tail position. Returns the number of flagged calls.
+pub fn mark_tail_calls(statements: &[Arc<RwLock<dyn Node>>], falls_off_end: bool) -> usize {
+    let mut marked = 0;
+    for (index, statement) in statements.iter().enumerate() {
+        let next = statements.get(index + 1);
+        let last = next.is_none();
+        // The call in tail position, if any. Locks are released before it
+        // is flagged, as it may be the statement itself.
+        let call = {
+            let node = statement.read().unwrap();
+            let node = node.as_any();
+            if let Some(ret) = node.downcast_ref::<ReturnNode>() {
+                match ret.returns.as_slice() {
+                    [call] => Some(call.clone()),
+                    _ => None,
+                }
+            } else if node.is::<CallNode>() {
+                let returns_nothing = returned_names(next) == Some(Vec::new());
+                (returns_nothing || (last && falls_off_end)).then(|| statement.clone())
+            } else if let Some(assign) = node.downcast_ref::<AssignNode>() {
+                let target = match &assign.identifier {
+                    Id(name) => Some(vec![name.clone()]),
+                    _ => None,
+                };
+                (target.is_some() && returned_names(next) == target).then(|| assign.expr.clone())
+            } else if let Some(assign) = node.downcast_ref::<MultiAssignNode>() {
+                let targets = ident_names(&assign.identifier);
+                (targets.is_some() && returned_names(next) == targets).then(|| assign.call.clone())
+            } else if let Some(cond) = node.downcast_ref::<CondStatNode>() {
+                for (_, consequences) in cond.branches() {
+                    marked += mark_tail_calls(consequences, last && falls_off_end);
+                }
+                marked += mark_tail_calls(&cond.alternatives, last && falls_off_end);
+                None
+            } else if let Some(lp) = node.downcast_ref::<LoopStatNode>() {
+                marked += mark_tail_calls(&lp.consequences, false);
+                None
+            } else if let Some(lp) = node.downcast_ref::<WhileStatNode>() {
+                marked += mark_tail_calls(&lp.consequences, false);
+                None
+            } else {
+                None
+            }
+        };
+        if let Some(call) = call {
+            if mark(&call) {
+                marked += 1;
+            }
+        }
+    }
+    marked
+}
@@ -0,0 +1,2399 @@
+use crate::ir::{lower, IrOp, IrType};
+use crate::lexer::token::Token;
+use crate::lexer::Lexer;
+use crate::parser::node::{
+    AssignNode, BinOpNode, BoolNumNode, CallNode, CompoundNode, CondStatNode, EntryBlockNode,
+    EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode,
+    IntegerNumNode, Node, ReturnNode, TernaryNode, TypeNode, UnaryOpNode, WhileStatNode,
+};
+use crate::parser::traversal::{is_node_type, Traversal};
+use crate::parser::Parser;
+use crate::sema::abi::{abi_from_entry, entry_abi, validate_abi_signatures};
+use crate::sema::artifact::AnalysisArtifact;
+use crate::sema::codes::{diagnostic, error_code, lookup};
+use crate::sema::const_fold::ConstFolder;
+use crate::sema::diff::{diff_programs, SignatureChange};
+use crate::sema::error::{Arity, SemaError};
+use crate::sema::fold::{body_statements, fold_constant_arrays};
+use crate::sema::naming::{NamingConvention, NamingStyle};
+use crate::sema::pass::{AnalysisPass, PassDiagnostic, PassDriver};
+use crate::sema::symbol::Symbol::IdentSymbol;
+use crate::sema::symbol::{BuiltIn, SymbolTable};
+use crate::sema::{
+    GlobalVariable, SemaOptions, ShadowPolicy, SymTableGen, TypeDump, UnusedExclusions,
+    GLOBAL_SCOPE_LEVEL, LOCAL_SCOPE_LEVEL, PROPHET_SCOPE_LEVEL,
+};
+use crate::utils::number::{FieldParams, Number, NumberResult, NumberRet, OverflowMode};
+use core::program::binary_program::{OlaProphet, OlaProphetInput, OlaProphetOutput};
+use ola_lang_abi::{Type, Value};
+use std::sync::{Arc, RwLock};
+
+fn empty_prophet() -> OlaProphet {
+    OlaProphet {
+        host: 0,
+        code: String::new(),
+        ctx: Vec::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+    }
+}
+
+fn analyze(code: &str, options: SemaOptions) -> (SymTableGen, NumberResult) {
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_with_options(&empty_prophet(), options);
+    let res = root.write().unwrap().traverse(&mut gen);
+    (gen, res)
+}
+
+#[test]
+fn test_case_collision_lint() {
+    let code = "entry() { felt x; felt X; x = 1; X = 2; }";
+    let options = SemaOptions {
+        case_collision_lint: true,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert!(gen.warnings[0].contains("'X'"));
+    assert!(gen.warnings[0].contains("'x'"));
+
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_array_param_mutation_warning() {
+    let code = "function fill(felt[2] arr, felt[2] other) { arr[0] = 1; }
+        entry() { }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert!(gen.warnings[0].contains("'arr'"));
+    assert!(gen.warnings[0].contains("'fill'"));
+}
+
+#[test]
+fn test_toy_field_prime() {
+    let options = SemaOptions {
+        field_params: FieldParams::new(17),
+        ..Default::default()
+    };
+    let code = "entry() { felt x; x = 4294967296; }";
+    let (_, res) = analyze(code, options.clone());
+    assert!(res.is_err());
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let gen = SymTableGen::new_with_options(&empty_prophet(), options);
+    let felt = |value| -> Arc<RwLock<dyn Node>> { Arc::new(RwLock::new(FeltNumNode::new(value))) };
+    let sum: Arc<RwLock<dyn Node>> =
+        Arc::new(RwLock::new(BinOpNode::new(felt(10), felt(9), Token::Plus)));
+    assert_eq!(gen.const_eval(&sum), Some(Number::Felt(2)));
+    let product: Arc<RwLock<dyn Node>> = Arc::new(RwLock::new(BinOpNode::new(
+        felt(5),
+        felt(7),
+        Token::Multiply,
+    )));
+    assert_eq!(gen.const_eval(&product), Some(Number::Felt(1)));
+    let neg: Arc<RwLock<dyn Node>> = Arc::new(RwLock::new(UnaryOpNode::new(Token::Minus, felt(3))));
+    assert_eq!(gen.const_eval(&neg), Some(Number::Felt(14)));
+}
+
+#[test]
+fn test_duplicate_condition_branch() {
+    let code = "entry() {
+            felt x;
+            felt y;
+            x = 3;
+            if x == 3 {
+                y = 1;
+            } else if x == 4 {
+                y = 2;
+            } else if x == 3 {
+                y = 3;
+            }
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert!(gen.warnings[0].contains("branch 3"));
+    assert!(gen.warnings[0].contains("branch 1"));
+}
+
+#[test]
+fn test_abi_from_source() {
+    let code = "function add(felt a, i32[2] b) -> felt { return a; }
+        entry() { }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new(&empty_prophet());
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let root = root.read().unwrap();
+    let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+    let abi = abi_from_entry(entry).unwrap();
+    let func = abi.functions.iter().find(|f| f.name == "add").unwrap();
+    assert_eq!(func.signature(), "add(field,u32[2])");
+
+    let params = [
+        Value::Field(7),
+        Value::FixedArray(vec![Value::U32(1), Value::U32(2)], Type::U32),
+    ];
+    let calldata = abi
+        .encode_input_with_signature(func.signature().as_str(), &params)
+        .unwrap();
+    assert!(calldata.contains(&7));
+}
+
+#[test]
+fn test_abi_incompatible_signatures() {
+    let input = |name: &str, length: usize| OlaProphetInput {
+        name: name.to_string(),
+        length,
+        is_ref: false,
+        is_input_output: false,
+    };
+    let prophet = OlaProphet {
+        inputs: vec![input("a", 1), input("b", 4)],
+        ..empty_prophet()
+    };
+    let abi = entry_abi(&prophet).unwrap();
+    assert_eq!(abi.functions[0].signature(), "entry(field,field[4])");
+
+    let root = Parser::new("entry() { }").parse();
+    let root = root.read().unwrap();
+    let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+    assert!(validate_abi_signatures(entry, &prophet).is_ok());
+
+    let prophet = OlaProphet {
+        inputs: vec![input("a", 1), input("empty", 0)],
+        ..empty_prophet()
+    };
+    assert_eq!(
+        validate_abi_signatures(entry, &prophet).err().unwrap(),
+        "entry input 'empty': empty values have no abi mapping"
+    );
+
+    let root = Parser::new("function f(felt[0] x) { } entry() { }").parse();
+    let root = root.read().unwrap();
+    let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+    assert_eq!(
+        validate_abi_signatures(entry, &empty_prophet())
+            .err()
+            .unwrap(),
+        "function 'f' cannot be called through the abi: type Array(FELT[0]) is an empty array"
+    );
+}
+
+#[test]
+fn test_parallel_function_analysis() {
+    let code = "function first(felt[2] arr) { arr[0] = 1; }
+        function second(felt[2] buf) { buf[1] = 2; }
+        entry() { }";
+    let (sequential, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    let options = SemaOptions {
+        parallel_functions: true,
+        ..Default::default()
+    };
+    let (parallel, res) = analyze(code, options.clone());
+    assert!(res.is_ok());
+    assert_eq!(parallel.warnings, sequential.warnings);
+
+    let code = "function broken() { felt x; y = 1; }
+        entry() { }";
+    let (_, res) = analyze(code, options);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_address_shaped_felt_literal() {
+    let code = "entry() {
+            felt x;
+            x = 0x00000000000000000000000000000000000000000000000000000000f4ce6ab8;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert!(gen.warnings[0].contains("address"));
+
+    // A full 32-byte address warns before being rejected as out of range.
+    let code = "entry() {
+            felt x;
+            x = 0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266f39fd6e51aad88f6f4ce6ab8;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.err().unwrap().to_string().contains("out of range"));
+    assert!(gen.warnings[0].contains("address"));
+
+    // Shorter zero-padded literals are not addresses.
+    let code = "entry() {
+            felt x;
+            x = 0x00000000000000000000000000000000f4ce6ab8;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+
+    let code = "entry() {
+            felt x;
+            x = 100000000000000000000000;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_err());
+    assert!(gen.warnings[0].contains("address"));
+
+    let (gen, res) = analyze("entry() { felt x; x = 0xffff; }", SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_missing_return_path() {
+    let complete = "function sign(felt x) -> felt {
+            if x == 0 {
+                return 0;
+            } else {
+                return 1;
+            }
+        }
+        entry() { }";
+    let (_, res) = analyze(complete, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let missing = "function sign(felt x) -> felt {
+            if x == 0 {
+                return 0;
+            } else {
+                x = 1;
+            }
+        }
+        entry() { }";
+    let (_, res) = analyze(missing, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "function sign does not return a value on all paths"
+    );
+}
+
+#[test]
+fn test_standalone_analysis() {
+    let code = "function double(i32 x) -> i32 {
+            return x + x;
+        }
+        entry() {
+            i32 y;
+            y = double(3);
+        }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let root = Parser::new("entry() { felt y; y = cid.x; }").parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_err());
+}
+
+#[test]
+fn test_type_at_identifier() {
+    let code = "entry() {
+    felt[2] arr;
+    i32 count;
+    felt total;
+    count = 1;
+    total = arr[0] + total;
+}";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.type_at(6, 13), Some("felt".to_string()));
+    assert_eq!(gen.type_at(6, 22), Some("felt".to_string()));
+    assert_eq!(gen.type_at(6, 1), None);
+}
+
+#[test]
+fn test_i32_overflow_modes() {
+    let max = Number::I32(i32::MAX);
+    let one = Number::I32(1);
+    let field = FieldParams::default();
+    let fold = |mode| field.fold_binop(&max, &one, &Token::Plus, mode);
+    assert_eq!(fold(OverflowMode::Wrap), Some(Number::I32(i32::MIN)));
+    assert_eq!(fold(OverflowMode::Saturate), Some(Number::I32(i32::MAX)));
+    assert_eq!(fold(OverflowMode::Checked), None);
+    assert_eq!(
+        OverflowMode::Checked.i32_binop(i32::MAX - 1, 1, &Token::Plus),
+        Some(i32::MAX)
+    );
+
+    let code = "entry() { i32 x; x = 2147483647 + 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "i32 constant overflow in 2147483647 + 1"
+    );
+    for overflow_mode in [OverflowMode::Wrap, OverflowMode::Saturate] {
+        let options = SemaOptions {
+            overflow_mode,
+            ..Default::default()
+        };
+        let (_, res) = analyze(code, options);
+        assert!(res.is_ok());
+    }
+}
+
+#[test]
+fn test_array_only_indexed_out_of_range() {
+    let code = "entry() {
+            felt[2] arr;
+            felt x;
+            arr[5] = 1;
+            x = arr[5];
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec!["array 'arr' of length 2 is only indexed out of range at [5, 5]".to_string()]
+    );
+
+    let code = "entry() {
+            felt[2] arr;
+            arr[1] = 1;
+            arr[5] = 1;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_felt_division_in_index() {
+    let code = "entry() {
+            felt[4] arr;
+            felt total;
+            felt count;
+            felt x;
+            total = 6;
+            count = 3;
+            x = arr[total / count];
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert!(gen.warnings[0].contains("felt division in the index of 'arr'"));
+
+    let code = "entry() {
+            felt[4] arr;
+            i32 total;
+            i32 count;
+            felt x;
+            total = 6;
+            count = 3;
+            x = arr[total / count];
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_dump_binop_type() {
+    let code = "entry() {
+    i32 a;
+    i32 b;
+    i32 c;
+    c = a * b;
+}";
+    let options = SemaOptions {
+        dump_types: TypeDump::Text,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    let binop = gen
+        .expression_types
+        .iter()
+        .find(|t| t.expression == "a * b")
+        .unwrap();
+    assert_eq!((binop.line, binop.column), (5, 9));
+    assert_eq!(binop.type_name, "i32");
+    assert!(gen.dump_types(TypeDump::Text).contains("5:9: a * b: i32"));
+}
+
+#[test]
+fn test_array_argument_aliasing() {
+    let code = "function pair(felt[2] x, felt[2] y) { }
+        entry() {
+            felt[2] a;
+            felt[2] b;
+            pair(a, a);
+            pair(a, b);
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings.len(), 1);
+    assert_eq!(
+        gen.warnings[0],
+        "call to 'pair' passes array 'a' as arguments 1, 2, they alias the same memory"
+    );
+}
+
+#[test]
+fn test_max_call_depth() {
+    let code = "function leaf() { }
+        function middle() { leaf(); }
+        function top() { middle(); leaf(); }
+        entry() {
+            leaf();
+            top();
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.max_call_depth(), 3);
+    assert_eq!(gen.deepest_call_chain(), ["entry", "top", "middle", "leaf"]);
+
+    let (gen, res) = analyze("entry() { }", SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(gen.max_call_depth(), 0);
+}
+
+#[test]
+fn test_magic_number_lint() {
+    let code = "entry() {
+            felt[42] buf;
+            felt[2] pair;
+            buf[7] = 1;
+            pair[1] = 1;
+        }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+
+    let options = SemaOptions {
+        magic_number_threshold: Some(2),
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        [
+            "array 'buf' uses the magic number 42 as its length, name it with a const",
+            "array 'buf' uses the magic number 7 as an index, name it with a const",
+        ]
+    );
+}
+
+#[test]
+fn test_analysis_artifact_round_trip() {
+    let code = "function scale(felt x) -> felt { return x; }
+        entry() {
+            felt[2] arr;
+            felt y;
+            y = arr[0] + arr[0];
+            scale(y);
+        }";
+    let options = SemaOptions {
+        dump_types: TypeDump::Json,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    let artifact = AnalysisArtifact::from_analysis(code, &gen);
+    assert_eq!(artifact.symbols["scale"], "function(felt)");
+
+    let bytes = artifact.to_bytes().unwrap();
+    let loaded = AnalysisArtifact::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, artifact);
+    assert_eq!(AnalysisArtifact::load_for(&bytes, code), Some(artifact));
+    assert_eq!(AnalysisArtifact::load_for(&bytes, "entry() { }"), None);
+
+    let mut restored = SymTableGen::new_standalone();
+    loaded.restore(&mut restored);
+    assert_eq!(restored.expression_types, gen.expression_types);
+    assert_eq!(restored.call_graph, gen.call_graph);
+}
+
+#[test]
+fn test_strict_arithmetic() {
+    let strict = SemaOptions {
+        strict_arithmetic: true,
+        ..Default::default()
+    };
+    let code = "entry() { felt[0x4] buf; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    let (_, res) = analyze(code, strict.clone());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "array 'buf' has a felt length, strict mode requires an i32 length"
+    );
+
+    let code = "entry() { felt[4] arr; felt i; felt x; i = 1; x = arr[i]; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    let (_, res) = analyze(code, strict.clone());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "array 'arr' is indexed with a felt, strict mode requires an i32 index"
+    );
+
+    let code = "entry() { felt[4] arr; felt i; i = 1; arr[i] = 2; }";
+    let (_, res) = analyze(code, strict.clone());
+    assert!(res.is_err());
+
+    let code = "entry() { felt[4] arr; i32 i; felt x; i = 1; x = arr[i]; arr[i] = x; }";
+    let (_, res) = analyze(code, strict);
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_unused_prophet_input() {
+    let input = |name: &str| OlaProphetInput {
+        name: name.to_string(),
+        length: 1,
+        is_ref: false,
+        is_input_output: false,
+    };
+    let prophet = OlaProphet {
+        inputs: vec![input("a"), input("b")],
+        outputs: vec![OlaProphetOutput {
+            name: "c".to_string(),
+            length: 1,
+            is_ref: false,
+            is_input_output: false,
+        }],
+        ..empty_prophet()
+    };
+    let root = Parser::new("entry() { c = a * 2; }").parse();
+    let mut gen = SymTableGen::new(&prophet);
+    let res = root.write().unwrap().traverse(&mut gen);
+    assert!(res.is_ok());
+    assert_eq!(gen.warnings, vec!["prophet input 'b' is never used"]);
+    let usage = gen.prophet_usage();
+    assert_eq!(usage.len(), 3);
+    assert!(usage[0].read && !usage[0].written);
+    assert_eq!((usage[2].kind, usage[2].written), ("output", true));
+}
+
+#[test]
+fn test_scope_levels() {
+    let prophet = OlaProphet {
+        inputs: vec![OlaProphetInput {
+            name: "a".to_string(),
+            length: 1,
+            is_ref: false,
+            is_input_output: false,
+        }],
+        ..empty_prophet()
+    };
+    let code = "function b(felt y) -> felt { return y; } entry() { felt x; x = a; b(x); }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new(&prophet);
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+    assert_eq!(gen.scope_level_of("a"), Some(PROPHET_SCOPE_LEVEL));
+    assert_eq!(gen.scope_level_of("b"), Some(GLOBAL_SCOPE_LEVEL));
+    assert_eq!(gen.scope_level_of("x"), Some(LOCAL_SCOPE_LEVEL));
+    assert_eq!(gen.scope_level_of("y"), None);
+}
+
+#[test]
+fn test_multi_assign_types() {
+    let function = "function pair() -> (felt, i32) {
+            felt a;
+            i32 b;
+            a = 1;
+            b = 2;
+            return (a, b);
+        }";
+    let code = format!("{} entry() {{ felt x; i32 y; (x, y) = pair(); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = format!("{} entry() {{ felt x; i32 y; (y, x) = pair(); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "multi-assign target 'y' has type I32, but 'pair' returns FELT at position 0"
+    );
+}
+
+#[test]
+fn test_statement_budget() {
+    let code = "function fill() {
+            felt x;
+            x = 1;
+            if x == 1 {
+                x = 2;
+                x = 3;
+            }
+        }
+        entry() { fill(); }";
+    let options = SemaOptions {
+        function_statement_budget: 4,
+        ..Default::default()
+    };
+    let (_, res) = analyze(code, options.clone());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "function 'fill' has 5 statements, above the budget of 4"
+    );
+
+    let options = SemaOptions {
+        function_statement_budget: 5,
+        ..options
+    };
+    let (_, res) = analyze(code, options.clone());
+    assert!(res.is_ok());
+
+    let options = SemaOptions {
+        total_statement_budget: 5,
+        ..options
+    };
+    let (_, res) = analyze(code, options);
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "program has 6 statements, above the budget of 5"
+    );
+}
+
+#[test]
+fn test_tail_calls() {
+    let code = "function g(felt x) -> felt { return x; }
+        function tail(felt x) -> felt { return g(x); }
+        function not_tail(felt x) -> felt {
+            felt y;
+            y = g(x) + 1;
+            return y;
+        }
+        entry() { felt a; a = tail(1); a = not_tail(a); }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let root = root.read().unwrap();
+    let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+    let statements = |index: usize| {
+        let function = entry.global_declarations[index].read().unwrap();
+        let function = function.as_any().downcast_ref::<FunctionNode>().unwrap();
+        body_statements(&function.block)
+    };
+    let is_tail = |call: &Arc<RwLock<dyn Node>>| {
+        let call = call.read().unwrap();
+        call.as_any().downcast_ref::<CallNode>().unwrap().tail_call
+    };
+
+    let tail = statements(1);
+    let ret = tail[0].read().unwrap();
+    let ret = ret.as_any().downcast_ref::<ReturnNode>().unwrap();
+    assert!(is_tail(&ret.returns[0]));
+
+    let not_tail = statements(2);
+    let assign = not_tail[1].read().unwrap();
+    let assign = assign.as_any().downcast_ref::<AssignNode>().unwrap();
+    let sum = assign.expr.read().unwrap();
+    let sum = sum.as_any().downcast_ref::<BinOpNode>().unwrap();
+    assert!(!is_tail(&sum.left));
+}
+
+#[test]
+fn test_loop_invariant() {
+    let code = |invariant: &str| {
+        format!(
+            "entry() {{
+                i32 i;
+                i = 0;
+                while i < 10 invariant({}) {{
+                    i = i + 1;
+                }}
+            }}",
+            invariant
+        )
+    };
+    let (_, res) = analyze(&code("i <= 10"), SemaOptions::default());
+    assert!(res.is_ok());
+
+    let (_, res) = analyze(&code("i + 1"), SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "loop invariant (i + 1) at 4:30 is not a bool condition"
+    );
+    let (_, res) = analyze(&code("i > 0 ? i <= 10 : true"), SemaOptions::default());
+    assert!(res.is_ok());
+
+    let (_, res) = analyze(&code("1 > 2"), SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "loop invariant (1 > 2) at 4:30 never holds"
+    );
+}
+
+#[test]
+fn test_fold_constant_array_access() {
+    let code = "entry() { felt[3] arr; felt x; arr = [10, 20, 30]; x = arr[1]; }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+    assert_eq!(fold_constant_arrays(&gen, &root), 1);
+
+    let root = root.read().unwrap();
+    let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+    let block = entry.entry_block.read().unwrap();
+    let block = block.as_any().downcast_ref::<EntryBlockNode>().unwrap();
+    let compound = block.compound_statement.read().unwrap();
+    let compound = compound.as_any().downcast_ref::<CompoundNode>().unwrap();
+    let assign = compound.children[1].read().unwrap();
+    let assign = assign.as_any().downcast_ref::<AssignNode>().unwrap();
+    let value = assign.expr.read().unwrap();
+    assert_eq!(
+        value
+            .as_any()
+            .downcast_ref::<IntegerNumNode>()
+            .unwrap()
+            .value,
+        20
+    );
+
+    let code = "entry() { felt[3] arr; felt x; arr = [10, 20, 30]; arr[1] = 5; x = arr[1]; }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+    assert_eq!(fold_constant_arrays(&gen, &root), 0);
+}
+
+#[test]
+fn test_self_comparison_warning() {
+    let code = "entry() { felt a; felt b; a = 1; b = 2; if a == a { b = 3; } }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec!["comparison 'a == a' compares an expression with itself, it is always true"]
+    );
+
+    let code = "entry() { felt a; felt b; a = 1; b = 2; if a == b { b = 3; } }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_undeclared_variable_suggestion() {
+    let code = "entry() {
+    felt length;
+    felt x;
+    length = 3;
+    x = lenght;
+}";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "identifier Undeclared variable lenght found. Did you mean 'length'?"
+    );
+    assert_eq!(gen.suggestions.len(), 1);
+    assert_eq!(gen.suggestions[0].original, "lenght");
+    assert_eq!(gen.suggestions[0].replacement, "length");
+    assert_eq!(gen.suggestions[0].line, 5);
+
+    let (gen, res) = analyze("entry() { felt x; x = total; }", SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "identifier Undeclared variable total found."
+    );
+    assert!(gen.suggestions.is_empty());
+
+    let (gen, res) = analyze("entry() { felt total; totl = 1; }", SemaOptions::default());
+    assert!(res.is_err());
+    assert_eq!(
+        (gen.suggestions[0].line, gen.suggestions[0].column),
+        (1, 23)
+    );
+
+    let code = "function one() -> felt { return 1; } entry() { felt total; (totl) = one(); }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_err());
+    assert_eq!(gen.suggestions[0].replacement, "total");
+    assert_eq!(
+        (gen.suggestions[0].line, gen.suggestions[0].column),
+        (1, 61)
+    );
+}
+
+#[test]
+fn test_max_array_length() {
+    let options = SemaOptions {
+        max_array_length: 16,
+        ..Default::default()
+    };
+    let (_, res) = analyze("entry() { felt[16] buf; }", options.clone());
+    assert!(res.is_ok());
+    let (_, res) = analyze("entry() { felt[100000000] buf; }", options);
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "array 'buf' has length 100000000, above the maximum of 16"
+    );
+    let (_, res) = analyze("entry() { felt[100000000] buf; }", SemaOptions::default());
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_lower_function_to_ir() {
+    let code = "function double(felt a) -> felt {
+    felt b;
+    b = a + a;
+    return b;
+}
+entry() { }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let program = lower(&root).unwrap();
+    let double = &program.functions[0];
+    assert_eq!(double.name, "double");
+    assert_eq!(double.returns, vec![IrType::Felt]);
+    assert_eq!(double.params[0].offset, 0);
+    assert_eq!(double.frame_size, 2);
+    assert_eq!(
+        double.ops,
+        vec![
+            IrOp::Alloc {
+                ty: IrType::Felt,
+                offset: 1
+            },
+            IrOp::Load {
+                dst: 0,
+                ty: IrType::Felt,
+                offset: 0
+            },
+            IrOp::Load {
+                dst: 1,
+                ty: IrType::Felt,
+                offset: 0
+            },
+            IrOp::Binary {
+                dst: 2,
+                ty: IrType::Felt,
+                op: "+".to_string(),
+                lhs: 0,
+                rhs: 1
+            },
+            IrOp::Store {
+                ty: IrType::Felt,
+                offset: 1,
+                src: 2
+            },
+            IrOp::Load {
+                dst: 3,
+                ty: IrType::Felt,
+                offset: 1
+            },
+            IrOp::Return(vec![3]),
+        ]
+    );
+    assert_eq!(program.functions[1].name, "entry");
+    assert!(program.functions[1].ops.is_empty());
+}
+
+#[test]
+fn test_dead_store_warning() {
+    let code = "entry() {
+    felt x;
+    felt y;
+    x = 1;
+    x = 2;
+    y = x;
+}";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec!["value assigned to 'x' at 4:5 is overwritten before it is read"]
+    );
+
+    let code = "entry() {
+    felt x;
+    felt y;
+    x = 1;
+    if y == 0 {
+        x = 2;
+    }
+    y = x;
+}";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+}
+
+#[test]
+fn test_error_codes() {
+    let (_, res) = analyze("entry() { felt x; x = total; }", SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert_eq!(error_code(&err).unwrap().code, "E0001");
+    assert_eq!(
+        diagnostic(&err),
+        "error[E0001]: identifier Undeclared variable total found."
+    );
+
+    let (_, res) = analyze("entry() { felt x; i32 x; x = 1; }", SemaOptions::default());
+    assert_eq!(
+        error_code(&res.err().unwrap().to_string()).unwrap().name,
+        "duplicate-declaration"
+    );
+
+    assert_eq!(lookup("E0002").unwrap().name, "type-mismatch");
+    assert!(lookup("E9999").is_none());
+    assert_eq!(diagnostic("something else"), "error: something else");
+}
+
+#[test]
+fn test_call_arity_mismatch() {
+    let function = "function add(felt a, felt b) -> felt { return a; }";
+    let code = format!("{} entry() {{ felt x; x = add(1); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert_eq!(err, "function 'add' expects 2 args, got 1");
+    assert_eq!(error_code(&err).unwrap().code, "E0004");
+}
+
+#[test]
+fn test_return_type_inference() {
+    let code = "function pick(felt a, i32 b) -> felt {
+            if b == 0 {
+                return 1;
+            }
+            return a;
+        }
+        function pair() -> (felt, i32) {
+            felt a;
+            i32 b;
+            a = 1;
+            b = 2;
+            return (a, b);
+        }
+        entry() { felt x; x = pick(1, 2) + 1; }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    match gen.inferred_returns("pick") {
+        Some(NumberRet::Single(Number::Felt(_))) => {}
+        other => panic!("unexpected return types {:?}", other),
+    }
+    match gen.inferred_returns("pair") {
+        Some(NumberRet::Multiple(types)) => {
+            assert_eq!(types.len(), 2);
+            assert_eq!(types[0].number_type(), Token::Felt);
+            assert_eq!(types[1].number_type(), Token::I32);
+        }
+        other => panic!("unexpected return types {:?}", other),
+    }
+
+    let code = "function f(felt a) -> felt {
+            if a == 0 {
+                return (a, a);
+            }
+            return a;
+        }
+        entry() { }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "function 'f' returns 1 values, 2 at an earlier return"
+    );
+}
+
+#[test]
+fn test_builtin_shadowing() {
+    // Builtin names are keywords to the lexer, so declare them directly.
+    let declare = |name: &str| {
+        let mut gen = SymTableGen::new_standalone();
+        let mut declaration = IdentDeclarationNode::new(
+            IdentNode::new(Token::Id(name.to_string())),
+            TypeNode::new(Token::Felt),
+        );
+        let res = declaration.traverse(&mut gen);
+        (gen, res)
+    };
+
+    let (_, res) = declare("felt");
+    let err = res.err().unwrap().to_string();
+    assert_eq!(err, "identifier 'felt' shadows the builtin type FELT");
+    assert_eq!(error_code(&err).unwrap().code, "E0014");
+
+    let (gen, res) = declare("malloc");
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec!["identifier 'malloc' shadows the builtin function malloc".to_string()]
+    );
+}
+
+#[test]
+fn test_shadowing_policy() {
+    let code = "function count() -> felt { felt r; r = 1; return r; }
+        entry() { felt count; count = 2; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "declaration of 'count' shadows the one in scope 'Global Scope'"
+    );
+
+    let options = SemaOptions {
+        shadowing: ShadowPolicy::WarnLog,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec!["declaration of 'count' shadows the one in scope 'Global Scope'".to_string()]
+    );
+
+    let options = SemaOptions {
+        shadowing: ShadowPolicy::Allow,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+
+    // Redeclaring in the same scope is an error whatever the policy.
+    let options = SemaOptions {
+        shadowing: ShadowPolicy::Allow,
+        ..Default::default()
+    };
+    let (_, res) = analyze("entry() { felt x; felt x; }", options);
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "Found duplicate variable declaration for 'x'!"
+    );
+}
+
+#[test]
+fn test_naming_convention_lint() {
+    let code = "function addOne(felt myValue) -> felt { felt total_sum; total_sum = myValue + 1; return total_sum; }
+        entry() { felt maxSupply; felt max_supply; maxSupply = 1; max_supply = addOne(maxSupply); }";
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.warnings.is_empty());
+
+    let options = SemaOptions {
+        naming_convention: NamingConvention {
+            variables: Some(NamingStyle::SnakeCase),
+            functions: None,
+        },
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.warnings,
+        vec![
+            "parameter 'myValue' is not snake_case".to_string(),
+            "variable 'maxSupply' is not snake_case".to_string(),
+        ]
+    );
+
+    let options = SemaOptions {
+        naming_convention: NamingConvention {
+            variables: None,
+            functions: Some(NamingStyle::SnakeCase),
+        },
+        ..Default::default()
+    };
+    let (gen, _) = analyze(code, options);
+    assert_eq!(
+        gen.warnings,
+        vec!["function 'addOne' is not snake_case".to_string()]
+    );
+
+    assert!(NamingStyle::CamelCase.matches("maxSupply"));
+    assert!(!NamingStyle::CamelCase.matches("max_supply"));
+    assert!(NamingStyle::PascalCase.matches("MaxSupply"));
+}
+
+#[test]
+fn test_else_if_chain() {
+    let code = "entry() {
+            felt x;
+            felt y;
+            x = 3;
+            if x == 1 {
+                y = 1;
+            } else if x == 2 {
+                y = 2;
+            } else if x == 3 {
+                y = 3;
+            } else {
+                y = 4;
+            }
+        }";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    {
+        let statements = body_statements(&block);
+        let cond = statements.last().unwrap().read().unwrap();
+        let cond = cond.as_any().downcast_ref::<CondStatNode>().unwrap();
+        assert_eq!(cond.else_ifs.len(), 2);
+        assert_eq!(cond.alternatives.len(), 1);
+        assert_eq!(cond.branches().len(), 3);
+    }
+
+    let mut gen = SymTableGen::new(&empty_prophet());
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let code = "entry() {
+            felt x;
+            x = 3;
+            if x == 1 {
+                x = 2;
+            } else if z == 2 {
+                x = 4;
+            }
+        }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "identifier Undeclared variable z found."
+    );
+}
+
+#[test]
+fn test_while_statement() {
+    let code = "entry() {
+            i32 i;
+            i = 0;
+            while (i < 3) {
+                i = i + 1;
+            }
+        }";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    assert!(is_node_type::<WhileStatNode>(
+        body_statements(&block).last().unwrap()
+    ));
+    let mut gen = SymTableGen::new(&empty_prophet());
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let code = "entry() {
+            felt x;
+            x = 1;
+            while (x) {
+                x = 0;
+            }
+        }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert!(err.starts_with("while condition x at"));
+    assert!(err.ends_with("is not a bool condition"));
+
+    // The type of the condition counts, not its form.
+    let code = "entry() {
+            felt x;
+            x = 1;
+            while (x > 0 ? true : false) {
+                x = 0;
+            }
+        }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_program_diff() {
+    let old = "function add(felt a, felt b) -> felt { return a; }
+        entry() { }";
+    let new = "function add(felt a, i32 b) -> felt { return a; }
+        function double(felt a) -> felt { return a; }
+        entry() { }";
+    let analyzed = |code: &str| {
+        let root = Parser::new(code).parse();
+        let mut gen = SymTableGen::new(&empty_prophet());
+        assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+        root
+    };
+    let (old, new) = (analyzed(old), analyzed(new));
+    let old = old.read().unwrap();
+    let new = new.read().unwrap();
+    let diff = diff_programs(
+        old.as_any().downcast_ref::<EntryNode>().unwrap(),
+        &empty_prophet(),
+        new.as_any().downcast_ref::<EntryNode>().unwrap(),
+        &empty_prophet(),
+    );
+    assert_eq!(
+        diff.added_functions,
+        vec!["double(felt a) -> felt".to_string()]
+    );
+    assert!(diff.removed_functions.is_empty());
+    assert_eq!(
+        diff.changed_functions,
+        vec![SignatureChange {
+            name: "add".to_string(),
+            old: "add(felt a, felt b) -> felt".to_string(),
+            new: "add(felt a, i32 b) -> felt".to_string(),
+        }]
+    );
+    assert!(diff.entry.is_none());
+    assert_eq!(
+        diff.to_string(),
+        "+ function double(felt a) -> felt\n\
+         ~ function add(felt a, felt b) -> felt => add(felt a, i32 b) -> felt\n"
+    );
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["added_functions"][0], "double(felt a) -> felt");
+}
+
+#[test]
+fn test_printf_constant_flag() {
+    let code = "entry() { felt x; x = 5; printf(x, 3); printf(x, 1 + 2); }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = "entry() { felt x; felt flag; x = 5; flag = 3; printf(x, flag); }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert!(err.starts_with("printf format flag flag at"));
+    assert!(err.ends_with("is not a constant"));
+    assert_eq!(error_code(&err).unwrap().code, "E0015");
+}
+
+#[test]
+fn test_index_type_validation() {
+    let code = "entry() {
+    felt[3] arr;
+    felt[2] other;
+    felt x;
+    x = arr[other];
+}";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "index of array 'arr' at 5:9 has type array, expected i32 or felt"
+    );
+
+    let code = "entry() {
+    felt[3] arr;
+    i32 i;
+    felt x;
+    i = 1;
+    x = arr[i] + i;
+}";
+    let options = SemaOptions {
+        dump_types: TypeDump::Text,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    let sum = gen
+        .expression_types
+        .iter()
+        .find(|t| t.expression == "arr[i] + i")
+        .unwrap();
+    assert_eq!(sum.type_name, "felt");
+}
+
+#[test]
+fn test_globals_without_prophet() {
+    let code = "entry() {
+    felt sum;
+    sum = cid + xs[1];
+    out[0] = sum;
+}";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::with_globals(
+        &[GlobalVariable::new("xs", 2)],
+        &["cid".to_string()],
+        &[GlobalVariable::new("out", 3)],
+    );
+    let res = root.write().unwrap().traverse(&mut gen);
+    assert!(res.is_ok());
+    assert!(gen.prophet_reads.contains("cid"));
+    assert!(gen.prophet_reads.contains("xs"));
+
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::with_globals(&[], &[], &[]);
+    let res = root.write().unwrap().traverse(&mut gen);
+    assert!(res
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("Undeclared variable"));
+}
+
+#[test]
+fn test_symbol_table_json() {
+    let prophet = OlaProphet {
+        ctx: vec![("cid".to_string(), 0)],
+        inputs: vec![OlaProphetInput {
+            name: "xs".to_string(),
+            length: 4,
+            is_ref: false,
+            is_input_output: false,
+        }],
+        ..empty_prophet()
+    };
+    let root = Parser::new("function f() -> felt { return 1; } entry() { i32 n; n = 1; }").parse();
+    let mut gen = SymTableGen::new(&prophet);
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let scopes = gen.current_scope.read().unwrap().to_json_recursive();
+    assert_eq!(scopes["scope_level"], LOCAL_SCOPE_LEVEL);
+    let n = scopes["symbols"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|symbol| symbol["name"] == "n")
+        .unwrap();
+    assert_eq!(n["kind"], "IdentSymbol");
+    assert_eq!(n["builtin"], "I32");
+
+    let global = &scopes["enclosing_scope"];
+    assert_eq!(global["scope_level"], GLOBAL_SCOPE_LEVEL);
+    assert!(global["symbols"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|symbol| symbol["name"] == "f" && symbol["kind"] == "FuncSymbol"));
+
+    let prophet_scope = &global["enclosing_scope"];
+    assert_eq!(prophet_scope["scope_level"], PROPHET_SCOPE_LEVEL);
+    assert!(prophet_scope["enclosing_scope"].is_null());
+    let names: Vec<&str> = prophet_scope["symbols"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["FELT", "I32", "cid", "xs"]);
+    let xs = &prophet_scope["symbols"][3];
+    assert_eq!(xs["builtin"], "FELT");
+    assert_eq!(xs["array_length"], 4);
+    assert_eq!(
+        prophet_scope["symbols"][2]["array_length"],
+        serde_json::Value::Null
+    );
+}
+
+#[test]
+fn test_special_constant_notes() {
+    let code = "entry() {
+    felt x;
+    felt y;
+    x = 18446744069414584320;
+    y = 9876543210123;
+}";
+    let options = SemaOptions {
+        special_constant_notes: true,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    assert_eq!(
+        gen.notes,
+        vec!["felt constant 18446744069414584320 is p-1, the field element -1"]
+    );
+    assert!(gen.warnings.is_empty());
+
+    let (gen, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+    assert!(gen.notes.is_empty());
+}
+
+#[test]
+fn test_unused_symbols() {
+    let code = "function first(felt[2] ys, felt y) -> felt {
+    return y;
+}
+entry() {
+    felt a;
+    felt b;
+    felt c;
+    a = 1;
+    b = a + xs[0];
+    c = first(xs, b);
+}";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::with_globals(
+        &[GlobalVariable::new("xs", 2)],
+        &["cid".to_string()],
+        &[GlobalVariable::new("out", 1)],
+    );
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+    assert_eq!(gen.unused_symbols(), vec!["ys", "c", "cid"]);
+    assert!(gen.unused[0].array_param);
+    assert_eq!(gen.unused[0].scope, "first");
+    assert!(gen.unused[2].context);
+
+    let root = Parser::new(code).parse();
+    let options = SemaOptions {
+        unused_exclusions: UnusedExclusions {
+            array_params: true,
+            context: true,
+        },
+        ..Default::default()
+    };
+    let mut gen = SymTableGen::with_globals_and_options(
+        &[GlobalVariable::new("xs", 2)],
+        &["cid".to_string()],
+        &[GlobalVariable::new("out", 1)],
+        options,
+    );
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+    assert_eq!(gen.unused_symbols(), vec!["c"]);
+
+    // Globals only used in function bodies are counted by the function workers.
+    let code = "felt count;
+felt total;
+function get() -> felt { return count; }
+entry() { felt a; a = get(); total = a; }";
+    let parallel = SemaOptions {
+        parallel_functions: true,
+        ..SemaOptions::default()
+    };
+    let (gen, res) = analyze(code, parallel);
+    assert!(res.is_ok());
+    assert!(!gen.unused_symbols().contains(&"count".to_string()));
+    assert!(gen.unused_symbols().contains(&"total".to_string()));
+}
+
+#[test]
+fn test_felt_overflow_check() {
+    let field = FieldParams::default();
+    let p_minus_one = Number::Felt(18446744069414584320);
+    let (sum, wrapped) = field
+        .checked_binop(&p_minus_one, &Number::I32(2), &Token::Plus)
+        .unwrap();
+    assert_eq!(sum, Number::Felt(1));
+    assert!(wrapped);
+    let (product, wrapped) = field
+        .checked_binop(&Number::Felt(3), &Number::Felt(4), &Token::Multiply)
+        .unwrap();
+    assert_eq!(product, Number::Felt(12));
+    assert!(!wrapped);
+    assert!(field
+        .checked_binop(&Number::I32(3), &Number::I32(4), &Token::Plus)
+        .is_none());
+    let (product, wrapped) = FieldParams::new(7)
+        .checked_binop(&Number::Felt(3), &Number::Felt(4), &Token::Multiply)
+        .unwrap();
+    assert_eq!(product, Number::Felt(5));
+    assert!(wrapped);
+
+    let code = "entry() {
+    felt x;
+    x = 18446744069414584320 + 2;
+}";
+    let options = SemaOptions {
+        felt_overflow_check: true,
+        ..Default::default()
+    };
+    let (_, res) = analyze(code, options);
+    let err = res.err().unwrap().to_string();
+    assert_eq!(
+        err,
+        "felt constant overflow in 18446744069414584320 + 2, wraps to 1"
+    );
+    assert_eq!(error_code(&err).unwrap().code, "E0008");
+
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    // The check wraps around the configured field, not the Goldilocks one.
+    let options = SemaOptions {
+        felt_overflow_check: true,
+        field_params: FieldParams::new(7),
+        ..Default::default()
+    };
+    let (_, res) = analyze("entry() { felt x; x = 0x3 * 0x4; }", options);
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "felt constant overflow in 0x3 * 0x4, wraps to 5"
+    );
+}
+
+#[test]
+fn test_malloc_region_access() {
+    let code = "entry() {
+    felt p;
+    felt x;
+    p = malloc(4);
+    x = p[8];
+}";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert_eq!(
+        err,
+        "index 8 of 'p' is outside its malloc region of 4 slots"
+    );
+    assert_eq!(error_code(&err).unwrap().code, "E0016");
+
+    let code = "entry() {
+    felt p;
+    felt x;
+    p = malloc(4);
+    x = p[3];
+    p[0] = x;
+}";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    // Once p may point elsewhere, the access is not provably out of region.
+    let code = "entry() {
+    felt p;
+    felt x;
+    p = malloc(4);
+    p = x;
+    x = p[8];
+}";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+}
+
+// Flags every declaration of a variable with a given name.
+struct ForbiddenName {
+    name: &'static str,
+    found: Vec<String>,
+}
+
+impl Traversal for ForbiddenName {
+    fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult {
+        if node.ident_node.identifier.to_string() == self.name {
+            self.found
+                .push(format!("variable '{}' is not allowed", self.name));
+        }
+        Ok(NumberRet::Single(Number::Nil))
+    }
+}
+
+impl AnalysisPass for ForbiddenName {
+    fn name(&self) -> &str {
+        "forbidden-name"
+    }
+
+    fn diagnostics(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.found)
+    }
+}
+
+#[test]
+fn test_custom_analysis_pass() {
+    let code = "function f() { felt tmp; tmp = 1; } entry() { felt x; felt tmp; x = 1; tmp = x; }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new(&empty_prophet());
+    let mut driver = PassDriver::new().with_pass(Box::new(ForbiddenName {
+        name: "tmp",
+        found: Vec::new(),
+    }));
+    let diagnostics = driver.run(&root, &mut gen).unwrap();
+    let found: Vec<&PassDiagnostic> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.pass == "forbidden-name")
+        .collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].message, "variable 'tmp' is not allowed");
+}
+
+#[test]
+fn test_const_folder() {
+    let code = "entry() { felt x; i32 y; i32 z; y = 3; z = (2 + 3) * y; \
+                x = 18446744069414584320 + 2; y = -4; z = 7 / 0; }";
+    let root = Parser::new(code).parse();
+    let mut folder = ConstFolder::default();
+    root.write().unwrap().traverse(&mut folder).unwrap();
+    assert_eq!(folder.folded, 3);
+
+    let entry_block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    let statements = body_statements(&entry_block);
+    let expr = |index: usize| {
+        let statement = statements[index].read().unwrap();
+        let assign = statement.as_any().downcast_ref::<AssignNode>().unwrap();
+        assign.expr.clone()
+    };
+
+    // `y` is not a constant, only the parenthesized sum is folded.
+    let product = expr(4);
+    let product = product.read().unwrap();
+    let product = product.as_any().downcast_ref::<BinOpNode>().unwrap();
+    let left = product.left.read().unwrap();
+    assert_eq!(
+        left.as_any()
+            .downcast_ref::<IntegerNumNode>()
+            .unwrap()
+            .value,
+        5
+    );
+    assert!(is_node_type::<IdentNode>(&product.right));
+
+    // A felt operand makes the result a felt, wrapping around the modulus.
+    let sum = expr(5);
+    let sum = sum.read().unwrap();
+    assert_eq!(sum.as_any().downcast_ref::<FeltNumNode>().unwrap().value, 1);
+
+    let negation = expr(6);
+    let negation = negation.read().unwrap();
+    assert_eq!(
+        negation
+            .as_any()
+            .downcast_ref::<IntegerNumNode>()
+            .unwrap()
+            .value,
+        -4
+    );
+
+    // Division by zero is left for the analysis to report.
+    assert!(is_node_type::<BinOpNode>(&expr(7)));
+}
+
+#[test]
+fn test_assign_type_mismatch() {
+    let code = "entry() { felt[3] arr; felt x; i32 i; x = 1; arr = [10, 20, 30]; x = arr[i]; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let (_, res) = analyze(
+        "entry() { felt[3] arr; felt x; x = arr; }",
+        SemaOptions::default(),
+    );
+    let message = res.err().unwrap().to_string();
+    assert_eq!(
+        message,
+        "cannot assign felt[3] to variable 'x' of type felt"
+    );
+    assert_eq!(error_code(&message).unwrap().code, "E0002");
+
+    let (_, res) = analyze("entry() { felt x; i32 i; i = x; }", SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign felt to variable 'i' of type i32"
+    );
+
+    let (_, res) = analyze(
+        "entry() { felt[2] arr; arr = [1, 2, 3]; }",
+        SemaOptions::default(),
+    );
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign i32[3] to variable 'arr' of type felt[2]"
+    );
+}
+
+#[test]
+fn test_multi_dimensional_arrays() {
+    let code = "entry() { felt[3][2] grid; felt[3][2] copy; grid = [[1, 2, 3], [4, 5, 6]]; \
+                copy = grid; }";
+    let root = Parser::new(code).parse();
+    let statements = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        body_statements(&entry.entry_block)
+    };
+    {
+        let declaration = statements[0].read().unwrap();
+        let declaration = declaration
+            .as_any()
+            .downcast_ref::<IdentDeclarationNode>()
+            .unwrap();
+        let rows = Token::Array(Box::new(Token::Felt), 3);
+        assert!(declaration.type_node.token == Token::Array(Box::new(rows), 2));
+    }
+    let mut gen = SymTableGen::new(&empty_prophet());
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let (_, res) = analyze(
+        "entry() { felt[3][2] grid; grid = [[1, 2, 3], [4, 5]]; }",
+        SemaOptions::default(),
+    );
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "ragged array literal, row 1 has 2 elements, row 0 has 3"
+    );
+
+    let (_, res) = analyze(
+        "entry() { felt[3][2] grid; grid = [[1, 2, 3], [4, 5, 6], [7, 8, 9]]; }",
+        SemaOptions::default(),
+    );
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign i32[3][3] to variable 'grid' of type felt[3][2]"
+    );
+
+    // Indexing the outer dimension gives a row.
+    let (_, res) = analyze(
+        "entry() { felt[3][2] grid; felt[3] row; row = grid[1]; }",
+        SemaOptions::default(),
+    );
+    assert!(res.is_ok());
+    let (_, res) = analyze(
+        "entry() { felt[3][2] grid; felt x; x = grid[1]; }",
+        SemaOptions::default(),
+    );
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign felt[3] to variable 'x' of type felt"
+    );
+
+    // Prophet inputs can be multi-dimensional too.
+    let root = Parser::new("entry() { felt[3][2] copy; copy = grid; }").parse();
+    let mut gen = SymTableGen::with_globals(&[GlobalVariable::array("grid", &[2, 3])], &[], &[]);
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+}
+
+#[test]
+fn test_bool_literals() {
+    let mut lexer = Lexer::new("true False truth");
+    assert!(lexer.get_next_token().unwrap() == Token::Bool(true));
+    assert!(lexer.get_next_token().unwrap() == Token::Bool(false));
+    assert!(lexer.get_next_token().unwrap() == Token::Id("truth".to_string()));
+
+    let code = "entry() {
+            felt x;
+            x = 1;
+            if true {
+                x = 2;
+            }
+            while (false) {
+                x = 3;
+            }
+        }";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    {
+        let statements = body_statements(&block);
+        let cond = statements[statements.len() - 2].read().unwrap();
+        let cond = cond.as_any().downcast_ref::<CondStatNode>().unwrap();
+        assert!(is_node_type::<BoolNumNode>(&cond.condition));
+
+        struct Walker;
+        impl Traversal for Walker {}
+        assert!(matches!(
+            Walker.travel(&cond.condition),
+            Ok(NumberRet::Single(Number::Bool(true)))
+        ));
+    }
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_hex_felt_literals() {
+    let mut lexer = Lexer::new("0xff 0xFFFFFFFF00000000");
+    assert!(lexer.get_next_token().unwrap() == Token::FeltConst("0xff".to_string()));
+    let max = lexer.get_next_token().unwrap();
+    assert!(max == Token::FeltConst("0xFFFFFFFF00000000".to_string()));
+    let node = FeltNumNode::from_literal(&max.to_string());
+    assert_eq!(node.value, 18446744069414584320);
+
+    let (_, res) = analyze("entry() { felt x; x = 0x1f + 1; }", SemaOptions::default());
+    assert!(res.is_ok());
+
+    // The modulus itself and anything wider than the field are rejected
+    // by the analysis.
+    for literal in [
+        "0xFFFFFFFF00000001",
+        "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+    ] {
+        let mut lexer = Lexer::new(literal);
+        assert!(lexer.get_next_token().unwrap() == Token::FeltConst(literal.to_string()));
+        let code = format!("entry() {{ felt x; x = {}; }}", literal);
+        let (_, res) = analyze(&code, SemaOptions::default());
+        assert!(res.err().unwrap().to_string().contains("out of range"));
+    }
+    let lexed = std::panic::catch_unwind(|| {
+        Lexer::new("0x").get_next_token();
+    });
+    assert!(lexed.is_err());
+}
+
+#[test]
+fn test_comments() {
+    let tokens = |code: &str| {
+        let mut lexer = Lexer::new(code);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.get_next_token().unwrap();
+            if token == Token::EOF {
+                break;
+            }
+            tokens.push(token.to_string());
+        }
+        tokens
+    };
+    assert_eq!(
+        tokens("x = a / b; // halve"),
+        ["x", "=", "a", "/", "b", ";"]
+    );
+    assert_eq!(tokens("x / /* divisor */ y"), ["x", "/", "y"]);
+    assert_eq!(
+        tokens("x /* outer /* inner */ still outer */ = 1"),
+        ["x", "=", "1"]
+    );
+    assert_eq!(tokens("x # old style"), ["x"]);
+    assert!(tokens("// only a comment").is_empty());
+
+    let code = "entry() {
+            felt x;
+            /* set x
+               to a quotient */
+            x = 6 / 2; // no trailing newline follows
+        } // end";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let unterminated = std::panic::catch_unwind(|| {
+        let mut lexer = Lexer::new("x /* never closed");
+        lexer.get_next_token();
+        lexer.get_next_token();
+    });
+    assert!(unterminated.is_err());
+}
+
+#[test]
+fn test_modulo_operator() {
+    let code = "entry() {
+    felt a;
+    felt b;
+    felt c;
+    i32 i;
+    i32 j;
+    a = 7;
+    b = 3;
+    c = a % b;
+    i = 7;
+    j = i % 3;
+}";
+    let options = SemaOptions {
+        dump_types: TypeDump::Text,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    let type_of = |expression: &str| {
+        gen.expression_types
+            .iter()
+            .find(|t| t.expression == expression)
+            .unwrap()
+            .type_name
+            .clone()
+    };
+    assert_eq!(type_of("a % b"), "felt");
+    assert_eq!(type_of("i % 3"), "i32");
+
+    let (_, res) = analyze(
+        "entry() { felt a; felt c; a = 7; c = a % true; }",
+        SemaOptions::default(),
+    );
+    let err = res.err().unwrap().to_string();
+    assert!(err.contains("operator % at"));
+    assert!(err.ends_with("expects felt or i32 operands, found felt and bool"));
+    assert_eq!(error_code(&err).unwrap().code, "E0002");
+}
+
+#[test]
+fn test_comparisons_are_bool() {
+    let code = "entry() {
+    felt a;
+    i32 i;
+    a = 1;
+    i = 2;
+    if a < i {
+        a = 2;
+    } else if (a != 3) == true {
+        a = 3;
+    }
+}";
+    let options = SemaOptions {
+        dump_types: TypeDump::Text,
+        ..Default::default()
+    };
+    let (gen, res) = analyze(code, options);
+    assert!(res.is_ok());
+    let binop = gen
+        .expression_types
+        .iter()
+        .find(|t| t.expression == "a < i")
+        .unwrap();
+    assert_eq!(binop.type_name, "bool");
+
+    let (_, res) = analyze(
+        "entry() { felt a; a = 1; if a { a = 2; } }",
+        SemaOptions::default(),
+    );
+    let err = res.err().unwrap().to_string();
+    assert!(err.starts_with("if condition a at"));
+    assert!(err.ends_with("has type felt, expected bool"));
+    assert_eq!(error_code(&err).unwrap().code, "E0002");
+
+    let (_, res) = analyze(
+        "entry() { felt a; a = 1; if a < true { a = 2; } }",
+        SemaOptions::default(),
+    );
+    let err = res.err().unwrap().to_string();
+    assert!(err.ends_with("cannot compare felt and bool"));
+    assert_eq!(error_code(&err).unwrap().code, "E0002");
+}
+
+#[test]
+fn test_logical_operators() {
+    let code = "entry() {
+    felt a;
+    felt b;
+    felt c;
+    a = 1;
+    b = 2;
+    c = 3;
+    if (a == 1) && (b > c) || true {
+        a = 2;
+    }
+}";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    {
+        // `||` binds looser than `&&`, which binds looser than comparisons.
+        let statements = body_statements(&block);
+        let cond = statements.last().unwrap().read().unwrap();
+        let cond = cond.as_any().downcast_ref::<CondStatNode>().unwrap();
+        let or = cond.condition.read().unwrap();
+        let or = or.as_any().downcast_ref::<BinOpNode>().unwrap();
+        assert!(or.operator == Token::Or);
+        let and = or.left.read().unwrap();
+        assert!(and.as_any().downcast_ref::<BinOpNode>().unwrap().operator == Token::And);
+    }
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = "entry() { felt b; felt c; b = 2; c = 3; if true && (b > c) { b = 1; } }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = "entry() { felt b; felt c; b = 2; c = 3; if (b > c) && 5 { b = 1; } }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert!(err.ends_with("expects bool operands, found bool and i32"));
+    assert_eq!(error_code(&err).unwrap().code, "E0002");
+}
+
+#[test]
+fn test_ternary_expression() {
+    let code = "entry() {
+    felt a;
+    felt b;
+    a = 1;
+    b = a > 0 ? a * 2 : 0 ? 1 : 3;
+}";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res
+        .err()
+        .unwrap()
+        .to_string()
+        .starts_with("ternary condition 0 at"));
+
+    let code = "entry() {
+    felt a;
+    felt b;
+    a = 1;
+    b = a > 0 ? a * 2 : (a < 0 ? a : a + 3);
+}";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    {
+        let statements = body_statements(&block);
+        let assign = statements.last().unwrap().read().unwrap();
+        let assign = assign.as_any().downcast_ref::<AssignNode>().unwrap();
+        assert!(is_node_type::<TernaryNode>(&assign.expr));
+    }
+    let mut gen = SymTableGen::new(&empty_prophet());
+    let res = root.write().unwrap().traverse(&mut gen);
+    assert!(res.is_ok());
+
+    let code = "entry() { felt a; felt b; a = 1; b = a > 0 ? a : true; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert!(err.ends_with("have different types, felt and bool"));
+    assert_eq!(error_code(&err).unwrap().code, "E0002");
+}
+
+#[test]
+fn test_string_literals() {
+    let mut lexer = Lexer::new("printf(x, \"x = {}\");");
+    lexer.get_next_token();
+    lexer.get_next_token();
+    lexer.get_next_token();
+    lexer.get_next_token();
+    let format = lexer.get_next_token().unwrap();
+    assert!(format == Token::Str("x = {}".to_string()));
+    assert!(format != Token::Id("x = {}".to_string()));
+
+    let code = "entry() { felt x; x = 5; printf(x, \"x = {}\"); }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = "entry() { felt x; x = \"five\" + 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res
+        .err()
+        .unwrap()
+        .to_string()
+        .ends_with("expects felt or i32 operands, found string and i32"));
+
+    let code = "entry() { felt x; x = -\"five\"; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.err().unwrap().to_string().ends_with("found string"));
+
+    let code = "entry() { felt x; x = \"five\"; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign string to variable 'x' of type felt"
+    );
+
+    let unterminated = std::panic::catch_unwind(|| {
+        Lexer::new("\"never closed").get_next_token();
+    });
+    assert!(unterminated.is_err());
+}
+
+#[test]
+fn test_break_and_continue() {
+    let code = "function count(felt n) -> felt {
+    felt i;
+    i = 0;
+    while (i < n) {
+        i = i + 1;
+        if i == 5 {
+            break;
+        }
+        continue;
+    }
+    return i;
+}
+entry() { }";
+    let root = Parser::new(code).parse();
+    let mut gen = SymTableGen::new_standalone();
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    // The loop's start and end labels come first, so break jumps to 1 and
+    // continue, like the back edge, to 0.
+    let program = lower(&root).unwrap();
+    let ops = &program.functions[0].ops;
+    assert!(ops.contains(&IrOp::Jump(1)));
+    assert_eq!(ops.iter().filter(|op| **op == IrOp::Jump(0)).count(), 2);
+
+    let code = "entry() { felt a; a = 1; if a == 1 { break; } }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert!(err.starts_with("break at"));
+    assert!(err.ends_with("is outside of a loop"));
+    assert_eq!(error_code(&err).unwrap().code, "E0017");
+
+    let code = "entry() { felt a; a = 1; while (a < 3) { a = a + 1; } continue; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.err().unwrap().to_string().starts_with("continue at"));
+}
+
+#[test]
+fn test_compound_assignment() {
+    let mut lexer = Lexer::new("x -= 1");
+    lexer.get_next_token();
+    assert!(lexer.get_next_token().unwrap() == Token::MinusAssign);
+
+    let code = "entry() {
+    felt[2] arr;
+    felt x;
+    i32 i;
+    x = 1;
+    i = 1;
+    x *= 3;
+    arr[i] -= x + 1;
+}";
+    let root = Parser::new(code).parse();
+    let block = {
+        let root = root.read().unwrap();
+        let entry = root.as_any().downcast_ref::<EntryNode>().unwrap();
+        entry.entry_block.clone()
+    };
+    {
+        let statements = body_statements(&block);
+        let assign = statements.last().unwrap().read().unwrap();
+        let assign = assign.as_any().downcast_ref::<AssignNode>().unwrap();
+        assert!(assign.operator == Token::Assign);
+        let target_index = match &assign.identifier {
+            Token::IndexId(_, index) => index.clone(),
+            _ => panic!("expected an array element target"),
+        };
+        let binop = assign.expr.read().unwrap();
+        let binop = binop.as_any().downcast_ref::<BinOpNode>().unwrap();
+        assert!(binop.operator == Token::Minus);
+        let element = binop.left.read().unwrap();
+        let element = element.as_any().downcast_ref::<IdentIndexNode>().unwrap();
+        assert!(Arc::ptr_eq(&element.index, &target_index));
+    }
+    let mut gen = SymTableGen::new(&empty_prophet());
+    assert!(root.write().unwrap().traverse(&mut gen).is_ok());
+
+    let code = "entry() { i32 i; felt f; i = 1; f = 2; i += f; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res
+        .err()
+        .unwrap()
+        .to_string()
+        .starts_with("cannot assign felt to variable 'i' of type i32"));
+
+    // Array element targets are checked like the other targets.
+    let code = "entry() { felt x; x = 1; y[0] += x; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(matches!(res.err().unwrap(),
+        SemaError::UndeclaredVariable { name, .. } if name == "y"));
+    let code = "entry() { felt[3] a; a[k] = 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(matches!(res.err().unwrap(),
+        SemaError::UndeclaredVariable { name, .. } if name == "k"));
+    let code = "entry() { felt[3] a; a[1 < 2] = 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(matches!(res.err().unwrap(),
+        SemaError::TypeMismatch { got, .. } if got == "bool"));
+}
+
+#[test]
+fn test_const_declarations() {
+    let code = "entry() { const felt limit = 10; felt x; x = limit + 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert!(res.is_ok());
+
+    let code = "entry() { const felt limit = 10; limit = 3; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap().to_string();
+    assert_eq!(err, "cannot assign to const 'limit'");
+    assert_eq!(error_code(&err).unwrap().code, "E0018");
+
+    let code = "entry() { const i32 step = 2; step += 1; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign to const 'step'"
+    );
+
+    let code = "entry() { const felt[3] arr = [10, 20, 30]; arr[1] = 5; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap().to_string(),
+        "cannot assign to const 'arr'"
+    );
+
+    let code = "function pair() -> (felt, felt) { return (1, 2); }
+        entry() { const felt a = 1; felt b; (a, b) = pair(); }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    assert_eq!(res.err().unwrap().to_string(), "cannot assign to const 'a'");
+
+    let missing = std::panic::catch_unwind(|| {
+        Parser::new("entry() { const felt x; }").parse();
+    });
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_declaration_order() {
+    let code = "function bump() {
+    count = count + 1;
+}
+felt count;
+entry() { bump(); }";
+    let parallel = SemaOptions {
+        parallel_functions: true,
+        ..SemaOptions::default()
+    };
+    let (_, res) = analyze(code, parallel.clone());
+    assert!(res.is_ok());
+
+    let ordered = SemaOptions {
+        declaration_order: true,
+        ..parallel
+    };
+    let (_, res) = analyze(code, ordered.clone());
+    let err = res.err().unwrap().to_string();
+    assert_eq!(err, "'count' is used at 2:5 before its declaration at 4:6");
+    assert_eq!(error_code(&err).unwrap().code, "E0019");
+
+    let code = "felt count;
+function bump() {
+    count = count + 1;
+}
+entry() { bump(); }";
+    let (_, res) = analyze(code, ordered);
+    assert!(res.is_ok());
+}
+
+#[test]
+fn test_resolution_cache() {
+    let global = SymbolTable::new("Global Scope".to_string(), GLOBAL_SCOPE_LEVEL, None);
+    let global = Arc::new(RwLock::new(global));
+    let count = IdentSymbol("count".to_string(), BuiltIn(Token::Felt), None, false);
+    global.write().unwrap().insert(count);
+    let mut local = SymbolTable::new("bump".to_string(), LOCAL_SCOPE_LEVEL, Some(global));
+    assert!(local.resolve("count").is_some());
+    assert!(local.resolved.contains_key("count"));
+    assert!(local.resolve("missing").is_none());
+
+    // Declaring the name in the scope itself drops the memoized variable.
+    let count = IdentSymbol("count".to_string(), BuiltIn(Token::I32), None, false);
+    local.insert(count);
+    assert!(local.resolved.is_empty());
+    assert!(matches!(
+        local.resolve("count"),
+        Some(IdentSymbol(_, BuiltIn(Token::I32), None, false))
+    ));
+
+    let code = "felt count;
+function bump() -> felt { felt x; x = count + 1; count = x; return x; }
+entry() { felt y; y = bump(); count = y; }";
+    let warnings: Vec<Vec<String>> = [true, false]
+        .into_iter()
+        .map(|resolution_cache| {
+            let options = SemaOptions {
+                resolution_cache,
+                ..SemaOptions::default()
+            };
+            let (gen, res) = analyze(code, options);
+            assert!(res.is_ok());
+            gen.warnings
+        })
+        .collect();
+    assert_eq!(warnings[0], warnings[1]);
+}
+
+#[test]
+fn test_structured_errors() {
+    let (_, res) = analyze(
+        "entry() { felt total; felt x; x = totl; }",
+        SemaOptions::default(),
+    );
+    let err = res.err().unwrap();
+    assert_eq!(
+        err,
+        SemaError::UndeclaredVariable {
+            name: "totl".to_string(),
+            context: "identifier".to_string(),
+            suggestion: Some("total".to_string()),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "identifier Undeclared variable totl found. Did you mean 'total'?"
+    );
+    assert_eq!(err.code().unwrap().code, "E0001");
+
+    let (_, res) = analyze("entry() { felt x; i32 x; x = 1; }", SemaOptions::default());
+    let err = res.err().unwrap();
+    assert_eq!(
+        err,
+        SemaError::DuplicateDeclaration {
+            name: "x".to_string()
+        }
+    );
+    assert_eq!(err.code().unwrap().code, "E0003");
+
+    let function = "function add(felt a, felt b) -> felt { return a; }";
+    let code = format!("{} entry() {{ felt x; x = add(1); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    let err = res.err().unwrap();
+    assert_eq!(
+        err,
+        SemaError::ArityMismatch {
+            func: "add".to_string(),
+            expected: 2,
+            got: 1,
+            arity: Arity::Args,
+        }
+    );
+    assert_eq!(err.to_string(), "function 'add' expects 2 args, got 1");
+    assert_eq!(err.code().unwrap().code, "E0004");
+
+    let code = "entry() { felt a; a = 1; if a { a = 2; } }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    match res.err().unwrap() {
+        SemaError::TypeMismatch {
+            expected,
+            got,
+            message,
+        } => {
+            assert_eq!(expected, "bool");
+            assert_eq!(got, "felt");
+            assert!(message.starts_with("if condition a at"));
+        }
+        err => panic!("expected a type mismatch, got {:?}", err),
+    }
+
+    let code = "entry() { const felt limit = 10; limit = 3; }";
+    let (_, res) = analyze(code, SemaOptions::default());
+    let err = res.err().unwrap();
+    assert_eq!(
+        err,
+        SemaError::ConstAssignment {
+            name: "limit".to_string()
+        }
+    );
+    assert_eq!(
+        err.diagnostic(),
+        "error[E0018]: cannot assign to const 'limit'"
+    );
+
+    // Errors without a structured kind keep their message and code.
+    let (_, res) = analyze("entry() { felt x; break; }", SemaOptions::default());
+    let err = res.err().unwrap();
+    assert!(matches!(err, SemaError::Other(_)));
+    assert_eq!(err.code().unwrap().code, "E0017");
+}
+
+#[test]
+fn test_call_errors() {
+    let function = "function add(felt a, felt b) -> felt { return a; }";
+    let code = format!("{} entry() {{ felt x; x = add(1, 1 < 2); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    let err = res.err().unwrap();
+    assert!(matches!(&err, SemaError::TypeMismatch { expected, got, .. }
+        if expected == "felt" && got == "bool"));
+    assert_eq!(
+        err.to_string(),
+        "argument 'b' of function 'add' has type bool, expected felt"
+    );
+
+    let code = format!("{} entry() {{ felt x; x = sub(1, 2); }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    assert_eq!(
+        res.err().unwrap(),
+        SemaError::UndeclaredVariable {
+            name: "sub".to_string(),
+            context: "call".to_string(),
+            suggestion: None,
+        }
+    );
+
+    let code = format!("{} entry() {{ felt x; x = add; }}", function);
+    let (_, res) = analyze(&code, SemaOptions::default());
+    let err = res.err().unwrap();
+    assert!(matches!(&err, SemaError::TypeMismatch { expected, .. } if expected == "variable"));
+    assert_eq!(err.diagnostic(), format!("error[E0002]: {}", err));
+}
@@ -1,4 +1,5 @@
 use crate::lexer::token::Token;
+use crate::sema::error::SemaError;
 use crate::utils::number::Number::{Bool, Felt, Nil, I32};
 use crate::utils::number::NumberRet::{Multiple, Single};
 use regex::Regex;
@@ -58,6 +59,7 @@ pub enum Number {
     I32(i32),
     Felt(i128),
     Bool(bool),
+    Str(String),
 }
 
 #[derive(Debug, Clone)]
@@ -88,7 +90,191 @@ impl NumberRet {
     }
 }
 
-pub type NumberResult = Result<NumberRet, String>;
+pub type NumberResult = Result<NumberRet, SemaError>;
+
+/// Parses a decimal or `0x` prefixed hex felt literal. Returns `None` if the
+/// literal does not fit in 64 bits.
+pub fn parse_felt_literal(literal: &str) -> Option<u64> {
+    if let Some(hex) = literal.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        literal.parse::<u64>().ok()
+    }
+}
+
+/// Returns the number of significant bits of a decimal or hex literal.
+pub fn literal_bits(literal: &str) -> usize {
+    if let Some(hex) = literal.strip_prefix("0x") {
+        let hex = hex.trim_start_matches('0');
+        match hex.chars().next() {
+            Some(first) => {
+                (hex.len() - 1) * 4 + (32 - first.to_digit(16).unwrap().leading_zeros()) as usize
+            }
+            None => 0,
+        }
+    } else if let Ok(value) = literal.parse::<u128>() {
+        (128 - value.leading_zeros()) as usize
+    } else {
+        // log2(10) ~= 3.322 bits per decimal digit
+        (literal.trim_start_matches('0').len() * 3322 + 999) / 1000
+    }
+}
+
+pub const GOLDILOCKS_PRIME: u64 = 18446744069414584321; // 2^64-2^32+1
+
+/// How i32 arithmetic behaves when the result does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OverflowMode {
+    /// Wrap around in two's complement.
+    Wrap,
+    /// Report overflow as an error.
+    #[default]
+    Checked,
+    /// Clamp to `i32::MIN` or `i32::MAX`.
+    Saturate,
+}
+
+impl OverflowMode {
+    /// Applies an i32 arithmetic operator. Returns `None` on division by zero,
+    /// on overflow in checked mode, or if `operator` is not arithmetic.
+    pub fn i32_binop(&self, lhs: i32, rhs: i32, operator: &Token) -> Option<i32> {
+        use OverflowMode::{Checked, Saturate, Wrap};
+        let ret = match (self, operator) {
+            (_, Token::IntegerDivision) | (_, Token::Mod) if rhs == 0 => return None,
+            (Wrap, Token::Plus) => lhs.wrapping_add(rhs),
+            (Wrap, Token::Minus) => lhs.wrapping_sub(rhs),
+            (Wrap, Token::Multiply) => lhs.wrapping_mul(rhs),
+            (Wrap, Token::IntegerDivision) => lhs.wrapping_div(rhs),
+            (Checked, Token::Plus) => lhs.checked_add(rhs)?,
+            (Checked, Token::Minus) => lhs.checked_sub(rhs)?,
+            (Checked, Token::Multiply) => lhs.checked_mul(rhs)?,
+            (Checked, Token::IntegerDivision) => lhs.checked_div(rhs)?,
+            (Checked, Token::Mod) => lhs.checked_rem(rhs)?,
+            (Saturate, Token::Plus) => lhs.saturating_add(rhs),
+            (Saturate, Token::Minus) => lhs.saturating_sub(rhs),
+            (Saturate, Token::Multiply) => lhs.saturating_mul(rhs),
+            (Saturate, Token::IntegerDivision) => lhs.saturating_div(rhs),
+            // i32::MIN % -1 is 0, only the intermediate overflows
+            (_, Token::Mod) => lhs.wrapping_rem(rhs),
+            _ => return None,
+        };
+        Some(ret)
+    }
+}
+
+/// Parameters of the field felt values live in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldParams {
+    pub modulus: u64,
+}
+
+impl Default for FieldParams {
+    fn default() -> Self {
+        FieldParams {
+            modulus: GOLDILOCKS_PRIME,
+        }
+    }
+}
+
+impl FieldParams {
+    pub fn new(modulus: u64) -> Self {
+        assert!(modulus > 1, "invalid field modulus {}", modulus);
+        FieldParams { modulus }
+    }
+
+    pub fn in_range(&self, value: i128) -> bool {
+        value >= 0 && value < self.modulus as i128
+    }
+
+    pub fn reduce(&self, value: i128) -> i128 {
+        value.rem_euclid(self.modulus as i128)
+    }
+
+    /// Folds a binary operation over two constants. Felt operands wrap around
+    /// the modulus, i32 operands follow `overflow`.
+    pub fn fold_binop(
+        &self,
+        lhs: &Number,
+        rhs: &Number,
+        operator: &Token,
+        overflow: OverflowMode,
+    ) -> Option<Number> {
+        let ret = match (lhs, rhs) {
+            (I32(left), I32(right)) => match operator {
+                Token::Plus
+                | Token::Minus
+                | Token::Multiply
+                | Token::IntegerDivision
+                | Token::Mod => I32(overflow.i32_binop(*left, *right, operator)?),
+                _ => self.fold_cmp(lhs, rhs, operator)?,
+            },
+            (I32(_), Felt(_)) | (Felt(_), I32(_)) | (Felt(_), Felt(_)) => {
+                let left = self.felt_value(lhs);
+                let right = self.felt_value(rhs);
+                match operator {
+                    Token::Plus => Felt(self.reduce(left + right)),
+                    Token::Minus => Felt(self.reduce(left - right)),
+                    Token::Multiply => {
+                        let product = (left as u128 * right as u128) % self.modulus as u128;
+                        Felt(product as i128)
+                    }
+                    Token::IntegerDivision if right != 0 => Felt(left / right),
+                    Token::Mod if right != 0 => Felt(left % right),
+                    _ => self.fold_cmp(&Felt(left), &Felt(right), operator)?,
+                }
+            }
+            _ => return None,
+        };
+        Some(ret)
+    }
+
+    /// Folds `+`, `-` or `*` over two constants, at least one a felt, like
+    /// `fold_binop`. Also returns whether the exact integer result fell
+    /// outside the field and so wrapped around when reduced. Returns `None`
+    /// for other operands and operators.
+    pub fn checked_binop(
+        &self,
+        lhs: &Number,
+        rhs: &Number,
+        operator: &Token,
+    ) -> Option<(Number, bool)> {
+        let (left, right) = match (lhs, rhs) {
+            (I32(_), Felt(_)) | (Felt(_), I32(_)) | (Felt(_), Felt(_)) => {
+                (self.felt_value(lhs), self.felt_value(rhs))
+            }
+            _ => return None,
+        };
+        let wrapped = match operator {
+            Token::Plus => left + right >= self.modulus as i128,
+            Token::Minus => left < right,
+            Token::Multiply => left as u128 * right as u128 >= self.modulus as u128,
+            _ => return None,
+        };
+        let value = self.fold_binop(lhs, rhs, operator, OverflowMode::default())?;
+        Some((value, wrapped))
+    }
+
+    fn felt_value(&self, number: &Number) -> i128 {
+        match number {
+            I32(value) => self.reduce(*value as i128),
+            Felt(value) => self.reduce(*value),
+            _ => panic!("{:?} is not a felt value", number),
+        }
+    }
+
+    fn fold_cmp(&self, lhs: &Number, rhs: &Number, operator: &Token) -> Option<Number> {
+        let ret = match operator {
+            Token::Equal => Bool(lhs == rhs),
+            Token::NotEqual => Bool(lhs != rhs),
+            Token::LessThan => Bool(lhs < rhs),
+            Token::GreaterThan => Bool(lhs > rhs),
+            Token::LessEqual => Bool(lhs <= rhs),
+            Token::GreaterEqual => Bool(lhs >= rhs),
+            _ => return None,
+        };
+        Some(ret)
+    }
+}
 
 fn convert(text: &str) -> Number {
     let reg = Regex::new(r"^I32\((?P<u32>[-+]?\d+)\)|^Felt\((?P<felt>[-+]?\d+)\)").unwrap();
@@ -121,6 +307,7 @@ impl ToString for Number {
             I32(value) => format!("I32({})", value),
             Felt(value) => format!("Felt({})", value),
             Bool(value) => format!("Bool({})", value),
+            Number::Str(value) => format!("Str({:?})", value),
         }
     }
 }
@@ -134,6 +321,7 @@ impl ops::Neg for Number {
             I32(value) => I32(-value),
             Felt(value) => Felt(value.not()),
             Bool(value) => Bool(value.not()),
+            Number::Str(_) => panic!("neg not use string"),
         }
     }
 }
@@ -232,6 +420,7 @@ pub fn number_from_token(token: &Token, len: usize) -> Number {
     match token {
         Token::Felt => Number::Felt(len as i128),
         Token::I32 => Number::I32(len as i32),
+        Token::Array(element, _) => number_from_token(element, len),
         _ => panic!("wrong type"),
     }
 }
@@ -242,7 +431,7 @@ impl Number {
             Felt(_) => Token::Felt,
             I32(_) => Token::I32,
             Bool(_) => Token::I32,
-            Nil => panic!("wrong type"),
+            Nil | Number::Str(_) => panic!("wrong type"),
         }
     }
 
@@ -262,6 +451,7 @@ impl Number {
                 Bool(_) => Token::Felt,
                 _ => panic!("bool op {:?} not support", rhs),
             },
+            Number::Str(_) => panic!("string op {:?} not support", rhs),
             Nil => panic!("Nil not support"),
         }
     }
@@ -271,7 +461,7 @@ impl Number {
             Felt(num) => *num as usize,
             I32(num) => *num as usize,
             Bool(num) => *num as usize,
-            Nil => panic!("wrong type"),
+            Nil | Number::Str(_) => panic!("wrong type"),
         };
         value
     }
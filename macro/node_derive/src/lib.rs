@@ -12,9 +12,12 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
     let quot = match name.to_string().as_str() {
         "IntegerNumNode" => quote!(travel.travel_integer(self)),
         "FeltNumNode" => quote!(travel.travel_felt(self)),
+        "BoolNumNode" => quote!(travel.travel_bool(self)),
+        "StringNode" => quote!(travel.travel_string(self)),
         "ArrayNumNode" => quote!(travel.travel_array(self)),
         "BinOpNode" => quote!(travel.travel_binop(self)),
         "UnaryOpNode" => quote!(travel.travel_unary_op(self)),
+        "TernaryNode" => quote!(travel.travel_ternary(self)),
         "IdentNode" => quote!(travel.travel_ident(self)),
         "IdentIndexNode" => quote!(travel.travel_ident_index(self)),
         "ContextIdentNode" => quote!(travel.travel_context_ident(self)),
@@ -27,6 +30,10 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
         "CompoundNode" => quote!(travel.travel_compound(self)),
         "CondStatNode" => quote!(travel.travel_cond(self)),
         "LoopStatNode" => quote!(travel.travel_loop(self)),
+        "WhileStatNode" => quote!(travel.travel_while(self)),
+        "InvariantNode" => quote!(travel.travel_invariant(self)),
+        "BreakNode" => quote!(travel.travel_break(self)),
+        "ContinueNode" => quote!(travel.travel_continue(self)),
         "EntryNode" => quote!(travel.travel_entry(self)),
         "FunctionNode" => quote!(travel.travel_function(self)),
         "CallNode" => quote!(travel.travel_call(self)),
@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use zk_vm::PausedTx;
+
+/// A value read from the paused vm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Register(usize),
+    Memory(u64),
+    Pc,
+    Const(u64),
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let number = |n: &str| match n.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => n.parse(),
+        };
+        if s == "pc" {
+            Ok(Operand::Pc)
+        } else if let Some(index) = s.strip_prefix('r') {
+            index
+                .parse()
+                .map(Operand::Register)
+                .map_err(|_| format!("invalid register '{}'", s))
+        } else if let Some(addr) = s.strip_prefix("mem[").and_then(|s| s.strip_suffix(']')) {
+            number(addr.trim())
+                .map(Operand::Memory)
+                .map_err(|_| format!("invalid memory address '{}'", s))
+        } else {
+            number(s)
+                .map(Operand::Const)
+                .map_err(|_| format!("invalid operand '{}'", s))
+        }
+    }
+}
+
+impl Operand {
+    fn read(&self, tx: &PausedTx) -> Option<u64> {
+        match self {
+            Operand::Register(index) => tx.registers().get(*index).map(|fe| fe.0),
+            Operand::Memory(addr) => tx.memory(*addr).map(|fe| fe.0),
+            Operand::Pc => Some(tx.pc()),
+            Operand::Const(value) => Some(*value),
+        }
+    }
+}
+
+/// `lhs op rhs` comparisons joined by `&&`, all of which must hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakCondition {
+    pub clauses: Vec<(Operand, String, Operand)>,
+}
+
+const COMPARISONS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+impl FromStr for BreakCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut clauses = Vec::new();
+        for clause in s.split("&&") {
+            let (lhs, op, rhs) = COMPARISONS
+                .iter()
+                .find_map(|op| {
+                    clause
+                        .split_once(op)
+                        .map(|(lhs, rhs)| (lhs, op.to_string(), rhs))
+                })
+                .ok_or(format!("'{}' is not a comparison", clause.trim()))?;
+            clauses.push((lhs.parse()?, op, rhs.parse()?));
+        }
+        Ok(BreakCondition { clauses })
+    }
+}
+
+impl BreakCondition {
+    /// Whether every clause holds in `tx`. A clause reading memory that was
+    /// never written does not hold.
+    pub fn holds(&self, tx: &PausedTx) -> bool {
+        self.clauses.iter().all(|(lhs, op, rhs)| {
+            let (lhs, rhs) = match (lhs.read(tx), rhs.read(tx)) {
+                (Some(lhs), Some(rhs)) => (lhs, rhs),
+                _ => return false,
+            };
+            match op.as_str() {
+                "==" => lhs == rhs,
+                "!=" => lhs != rhs,
+                "<=" => lhs <= rhs,
+                ">=" => lhs >= rhs,
+                "<" => lhs < rhs,
+                _ => lhs > rhs,
+            }
+        })
+    }
+}
+
+/// A pc to pause before, optionally only when a condition holds, written as
+/// `12` or `12 if r3 == 5 && mem[0x100] != 0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub pc: u64,
+    pub condition: Option<BreakCondition>,
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pc, condition) = match s.split_once(" if ") {
+            Some((pc, condition)) => (pc, Some(condition.parse()?)),
+            None => (s, None),
+        };
+        let pc = match pc.trim().parse()? {
+            Operand::Const(pc) => pc,
+            _ => return Err(format!("invalid breakpoint pc '{}'", pc.trim())),
+        };
+        Ok(Breakpoint { pc, condition })
+    }
+}
+
+impl Breakpoint {
+    /// Whether execution paused in `tx` stops at this breakpoint.
+    pub fn triggers(&self, tx: &PausedTx) -> bool {
+        if tx.pc() != self.pc {
+            return false;
+        }
+        match &self.condition {
+            Some(condition) => condition.holds(tx),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BreakCondition, Breakpoint, Operand};
+
+    #[test]
+    fn test_parse_breakpoint() {
+        let bp: Breakpoint = "12".parse().unwrap();
+        assert_eq!((bp.pc, bp.condition), (12, None));
+
+        let bp: Breakpoint = "0x10 if r3 == 5 && mem[0x100] != 0".parse().unwrap();
+        assert_eq!(bp.pc, 16);
+        assert_eq!(
+            bp.condition,
+            Some(BreakCondition {
+                clauses: vec![
+                    (Operand::Register(3), "==".to_string(), Operand::Const(5)),
+                    (Operand::Memory(256), "!=".to_string(), Operand::Const(0)),
+                ]
+            })
+        );
+        assert!("12 if r3".parse::<Breakpoint>().is_err());
+        assert!("r3".parse::<Breakpoint>().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use core::{program::instruction::Opcode, trace::trace::Step, types::Field};
+use std::collections::BTreeMap;
+
+use serde_derive::Serialize;
+use zk_vm::OlaVM;
+
+use crate::utils::u64_array_to_h256;
+
+/// The parts of an execution step coverage looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutedInstruction {
+    pub addr_code: [u64; 4],
+    pub pc: u64,
+    pub opcode: u64,
+    pub op0: u64,
+    pub op1: u64,
+}
+
+impl From<&Step> for ExecutedInstruction {
+    fn from(step: &Step) -> Self {
+        ExecutedInstruction {
+            addr_code: step.addr_code.map(|fe| fe.0),
+            pc: step.pc,
+            opcode: step.opcode.0,
+            op0: step.register_selector.op0.0,
+            op1: step.register_selector.op1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BranchCoverage {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+/// Execution counts for the code of one contract. Functions are keyed by
+/// their entry pc, pc 0 being the contract entry; branches are the
+/// conditional jumps, keyed by their pc. Compiled programs carry no source
+/// map, so counts stay keyed by pc rather than by source span.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ContractCoverage {
+    pub functions: BTreeMap<u64, u64>,
+    pub branches: BTreeMap<u64, BranchCoverage>,
+    pub instructions: BTreeMap<u64, u64>,
+}
+
+/// Coverage of every contract a tx ran, keyed by code address.
+pub fn collect_coverage(
+    executed: impl IntoIterator<Item = ExecutedInstruction>,
+) -> BTreeMap<String, ContractCoverage> {
+    let call = 1 << Opcode::CALL as u8;
+    let cjmp = 1 << Opcode::CJMP as u8;
+    let mut report: BTreeMap<String, ContractCoverage> = BTreeMap::new();
+    for inst in executed {
+        let address = format!("0x{}", hex::encode(u64_array_to_h256(&inst.addr_code).0));
+        let coverage = report.entry(address).or_default();
+        *coverage.instructions.entry(inst.pc).or_default() += 1;
+        if inst.pc == 0 {
+            *coverage.functions.entry(0).or_default() += 1;
+        }
+        if inst.opcode == call {
+            *coverage.functions.entry(inst.op1).or_default() += 1;
+        } else if inst.opcode == cjmp {
+            let branch = coverage.branches.entry(inst.pc).or_default();
+            if inst.op0 == 1 {
+                branch.taken += 1;
+            } else {
+                branch.not_taken += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Coverage of the tx `vm` has just executed.
+pub fn tx_coverage(vm: &OlaVM) -> BTreeMap<String, ContractCoverage> {
+    collect_coverage(
+        vm.ola_state
+            .txs_trace
+            .values()
+            .flat_map(|trace| trace.exec.iter())
+            .filter(|step| step.is_ext_line.is_zero())
+            .map(ExecutedInstruction::from),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use core::program::instruction::Opcode;
+
+    use super::{collect_coverage, BranchCoverage, ExecutedInstruction};
+
+    fn inst(pc: u64, opcode: Opcode, op0: u64, op1: u64) -> ExecutedInstruction {
+        ExecutedInstruction {
+            addr_code: [0, 0, 0, 1],
+            pc,
+            opcode: 1 << opcode as u8,
+            op0,
+            op1,
+        }
+    }
+
+    #[test]
+    fn test_only_taken_branch_covered() {
+        // The function takes the jump at 10 and skips 11, then falls through
+        // the jump at 20 to 21.
+        let report = collect_coverage(vec![
+            inst(0, Opcode::MOV, 0, 0),
+            inst(1, Opcode::CALL, 0, 10),
+            inst(10, Opcode::CJMP, 1, 12),
+            inst(12, Opcode::MOV, 0, 0),
+            inst(20, Opcode::CJMP, 0, 30),
+            inst(21, Opcode::RET, 0, 0),
+            inst(2, Opcode::END, 0, 0),
+        ]);
+        let coverage = &report[&format!("0x{}1", "0".repeat(63))];
+        assert_eq!(coverage.functions.get(&0), Some(&1));
+        assert_eq!(coverage.functions.get(&10), Some(&1));
+        assert_eq!(
+            coverage.branches.get(&10),
+            Some(&BranchCoverage {
+                taken: 1,
+                not_taken: 0
+            })
+        );
+        assert_eq!(
+            coverage.branches.get(&20),
+            Some(&BranchCoverage {
+                taken: 0,
+                not_taken: 1
+            })
+        );
+        let executed: Vec<u64> = coverage.instructions.keys().copied().collect();
+        assert_eq!(executed, vec![0, 1, 2, 10, 12, 20, 21]);
+        assert!(coverage.instructions.values().all(|count| *count == 1));
+    }
+}
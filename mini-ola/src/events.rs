@@ -0,0 +1,177 @@
+use std::{fs::File, io::Write, path::Path, str::FromStr};
+
+use core::vm::types::{Event, Hash};
+use ola_lang_abi::{Abi, FixedArray4};
+use serde_derive::Deserialize;
+use serde_json::{json, Map};
+
+use crate::{subcommands::parser::FromValue, utils::u64_array_to_h256};
+
+/// How the events emitted by a tx are printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventsFormat {
+    /// One debug line per event.
+    Text,
+    /// One JSON object per line, for log pipelines.
+    Ndjson,
+}
+
+impl FromStr for EventsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(EventsFormat::Text),
+            "ndjson" => Ok(EventsFormat::Ndjson),
+            _ => Err(format!(
+                "unknown events format '{}', expected text or ndjson",
+                s
+            )),
+        }
+    }
+}
+
+fn hex_words(words: &[u64; 4]) -> String {
+    format!("0x{}", hex::encode(u64_array_to_h256(words).0))
+}
+
+/// An event as a node reports it, the contract address and topics as
+/// four-word hashes.
+#[derive(Debug, Deserialize)]
+struct RawEvent {
+    address: Hash,
+    topics: Vec<Hash>,
+    data: Vec<u64>,
+}
+
+/// Reads a JSON array of `{address, topics, data}` events, numbered in the
+/// order of the file.
+pub fn read_events(path: &Path) -> anyhow::Result<Vec<Event>> {
+    let raw: Vec<RawEvent> = serde_json::from_reader(File::open(path)?)?;
+    Ok(raw
+        .into_iter()
+        .enumerate()
+        .map(|(index, event)| Event {
+            batch_number: 0,
+            index_in_batch: index as u64,
+            address: event.address,
+            topics: event.topics,
+            data: event.data,
+        })
+        .collect())
+}
+
+/// JSON record of an event: the emitting contract, and the event name with
+/// its decoded fields, or the raw topics and data when no abi event matches.
+pub fn event_record(abi: &Abi, event: &Event) -> serde_json::Value {
+    let address = hex_words(&event.address);
+    let topics: Vec<FixedArray4> = event.topics.iter().map(|t| FixedArray4(*t)).collect();
+    match abi.decode_log_from_slice(&topics, &event.data) {
+        Ok((abi_event, params)) => {
+            let mut fields = Map::new();
+            for param in params.iter() {
+                fields.insert(
+                    param.param.name.clone(),
+                    json!(FromValue::parse_input(param.value.clone())),
+                );
+            }
+            json!({
+                "address": address,
+                "event": abi_event.name,
+                "fields": fields,
+            })
+        }
+        Err(_) => json!({
+            "address": address,
+            "topics": event.topics.iter().map(hex_words).collect::<Vec<_>>(),
+            "data": event.data,
+        }),
+    }
+}
+
+/// Writes `events` to `out` in the given format, one line per event.
+pub fn write_events(
+    out: &mut impl Write,
+    abi: &Abi,
+    events: &[Event],
+    format: EventsFormat,
+) -> anyhow::Result<()> {
+    for event in events.iter() {
+        match format {
+            EventsFormat::Text => writeln!(out, "{}", event)?,
+            EventsFormat::Ndjson => writeln!(out, "{}", event_record(abi, event))?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::vm::types::Event;
+    use std::{fs::File, path::PathBuf};
+
+    use ola_lang_abi::Abi;
+
+    use super::{event_record, read_events, write_events, EventsFormat};
+
+    #[test]
+    fn test_decoded_event() {
+        let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../executor/test");
+        let abi: Abi = serde_json::from_reader(
+            File::open(test_dir.join("contracts-abi/erc20_abi.json")).unwrap(),
+        )
+        .unwrap();
+        let transfer = abi.events.iter().find(|e| e.name == "Transfer").unwrap();
+        let events_file = std::env::temp_dir().join("mini_ola_decoded_event.json");
+        std::fs::write(
+            &events_file,
+            serde_json::json!([{
+                "address": [0, 0, 0, 7],
+                "topics": [transfer.topic().0, [0, 0, 0, 1], [0, 0, 0, 2]],
+                "data": [5],
+            }])
+            .to_string(),
+        )
+        .unwrap();
+
+        let events = read_events(events_file.as_path()).unwrap();
+        assert_eq!(events.len(), 1);
+        let record = event_record(&abi, &events[0]);
+        assert_eq!(record["event"], "Transfer");
+        assert_eq!(record["fields"]["value"], "5");
+        assert_eq!(
+            record["fields"]["to"],
+            "0000000000000000000000000000000000000000000000000000000000000002"
+        );
+        assert!(record.get("topics").is_none());
+        let _ = std::fs::remove_file(events_file);
+    }
+
+    #[test]
+    fn test_events_as_ndjson() {
+        let abi: Abi = serde_json::from_str("[]").unwrap();
+        let events: Vec<Event> = (0..2)
+            .map(|index| Event {
+                batch_number: 1,
+                index_in_batch: index,
+                address: [0, 0, 0, 7],
+                topics: vec![[index, 0, 0, 0]],
+                data: vec![index, 42],
+            })
+            .collect();
+        let mut out = Vec::new();
+        write_events(&mut out, &abi, &events, EventsFormat::Ndjson).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (index, line) in lines.iter().enumerate() {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(
+                record["address"],
+                "0x0000000000000000000000000000000000000000000000000000000000000007"
+            );
+            assert_eq!(record["data"][0], index as u64);
+            assert_eq!(record["data"][1], 42);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use ola_lang_abi::{Param, Value};
+
+use crate::{
+    subcommands::{call::format_output, layout::type_name},
+    utils::u64_array_to_h256,
+};
+
+/// Renders an output value for display. Returns `None` for values it does
+/// not apply to, leaving them to the next formatter.
+pub trait ValueFormatter {
+    fn format(&self, value: &Value) -> Option<String>;
+}
+
+/// Numbers, hashes and addresses as 0x-prefixed hex.
+pub struct HexFormatter;
+
+impl ValueFormatter for HexFormatter {
+    fn format(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::U32(n) | Value::Field(n) => Some(format!("{:#x}", n)),
+            Value::Address(words) | Value::Hash(words) => {
+                Some(format!("0x{}", hex::encode(u64_array_to_h256(&words.0).0)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Numbers as ISO 8601 UTC date-times, read as seconds since the unix epoch.
+pub struct IsoTimestampFormatter;
+
+impl ValueFormatter for IsoTimestampFormatter {
+    fn format(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::U32(secs) | Value::Field(secs) => Some(iso_timestamp(*secs)),
+            _ => None,
+        }
+    }
+}
+
+pub fn iso_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Civil date from days since 1970-01-01, proleptic gregorian calendar.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+pub fn builtin_formatter(name: &str) -> anyhow::Result<Box<dyn ValueFormatter>> {
+    match name {
+        "hex" => Ok(Box::new(HexFormatter)),
+        "iso" => Ok(Box::new(IsoTimestampFormatter)),
+        _ => anyhow::bail!("unknown formatter '{}', expected hex or iso", name),
+    }
+}
+
+// Output names that hold a point in time.
+fn is_timestamp_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["time", "deadline", "date", "expir"]
+        .iter()
+        .any(|part| name.contains(part))
+}
+
+/// Formatters picked per output parameter. A selector matches an output by
+/// its name, by its type name, or, for `timestamp`, by a name that holds a
+/// point in time. The first formatter that applies wins.
+#[derive(Default)]
+pub struct OutputFormatters {
+    rules: Vec<(String, Box<dyn ValueFormatter>)>,
+}
+
+impl OutputFormatters {
+    /// Parses a `selector:formatter` list such as `address:hex,timestamp:iso`.
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let mut formatters = OutputFormatters::default();
+        for rule in spec.split(',').filter(|rule| !rule.is_empty()) {
+            let (selector, name) = match rule.split_once(':') {
+                Some(parts) => parts,
+                None => anyhow::bail!("invalid formatter '{}', expected selector:formatter", rule),
+            };
+            formatters.register(selector, builtin_formatter(name)?);
+        }
+        Ok(formatters)
+    }
+
+    pub fn register(&mut self, selector: &str, formatter: Box<dyn ValueFormatter>) {
+        self.rules.push((selector.to_string(), formatter));
+    }
+
+    fn selects(selector: &str, param: &Param) -> bool {
+        selector == param.name
+            || selector == type_name(&param.type_)
+            || (selector == "timestamp" && is_timestamp_name(&param.name))
+    }
+
+    /// Formats the value of output `param`, falling back to `format_output`.
+    pub fn format(&self, param: &Param, value: Value, max_elements: Option<usize>) -> String {
+        for (selector, formatter) in self.rules.iter() {
+            if Self::selects(selector, param) {
+                if let Some(formatted) = formatter.format(&value) {
+                    return formatted;
+                }
+            }
+        }
+        format_output(value, max_elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::{Param, Type, Value};
+
+    use super::OutputFormatters;
+
+    #[test]
+    fn test_deadline_as_iso_date() {
+        let formatters = OutputFormatters::parse("address:hex,timestamp:iso").unwrap();
+        let deadline = Param {
+            name: "deadline".to_string(),
+            type_: Type::Field,
+            indexed: None,
+        };
+        assert_eq!(
+            formatters.format(&deadline, Value::Field(1700000000), None),
+            "2023-11-14T22:13:20Z"
+        );
+
+        let amount = Param {
+            name: "amount".to_string(),
+            ..deadline
+        };
+        assert_eq!(formatters.format(&amount, Value::Field(42), None), "42");
+        assert!(OutputFormatters::parse("timestamp:rfc").is_err());
+    }
+}
@@ -1,7 +1,16 @@
 use clap::{CommandFactory, Parser, Subcommand};
 use colored::Colorize;
-use subcommands::{call::Call, deploy::Deploy, invoke::Invoke};
+use subcommands::{
+    abi_self_test::AbiSelfTest, batch::Batch, call::Call, check::Check, decode::Decode,
+    deploy::Deploy, encode::Encode, invoke::Invoke, layout::Layout, replay::Replay, serve::Serve,
+};
 
+mod breakpoint;
+mod coverage;
+mod events;
+mod formatter;
+mod metrics;
+mod preset;
 mod subcommands;
 mod utils;
 
@@ -22,6 +31,22 @@ enum Subcommands {
     Invoke(Invoke),
     #[clap(about = "Make a state query.")]
     Call(Call),
+    #[clap(about = "Invoke a batch of transactions and report the aggregate state changes.")]
+    Batch(Batch),
+    #[clap(about = "Replay a recorded call and check it behaves the same.")]
+    Replay(Replay),
+    #[clap(about = "Check that every function of an ABI encodes and decodes.")]
+    AbiSelfTest(AbiSelfTest),
+    #[clap(about = "Serve call and query over JSON-RPC.")]
+    Serve(Serve),
+    #[clap(about = "Print the calldata layout of a function.")]
+    Layout(Layout),
+    #[clap(about = "Lex, parse and analyze a source file without running it.")]
+    Check(Check),
+    #[clap(about = "Decode raw return data of a function against its ABI.")]
+    Decode(Decode),
+    #[clap(about = "Print the calldata of a function call without executing it.")]
+    Encode(Encode),
 }
 
 fn main() {
@@ -42,6 +67,14 @@ fn run_command(cli: Cli) -> anyhow::Result<()> {
             Subcommands::Deploy(cmd) => cmd.run(),
             Subcommands::Invoke(cmd) => cmd.run(),
             Subcommands::Call(cmd) => cmd.run(),
+            Subcommands::Batch(cmd) => cmd.run(),
+            Subcommands::Replay(cmd) => cmd.run(),
+            Subcommands::AbiSelfTest(cmd) => cmd.run(),
+            Subcommands::Serve(cmd) => cmd.run(),
+            Subcommands::Layout(cmd) => cmd.run(),
+            Subcommands::Check(cmd) => cmd.run(),
+            Subcommands::Decode(cmd) => cmd.run(),
+            Subcommands::Encode(cmd) => cmd.run(),
         },
     }
 }
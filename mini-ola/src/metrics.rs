@@ -0,0 +1,116 @@
+use core::vm::memory::MemoryFootprint;
+use std::{fmt::Write as _, fs::File, io::Write, path::Path, time::Duration};
+
+use zk_vm::OlaVM;
+
+/// Counters of one executed tx, exported in the Prometheus text format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionMetrics {
+    /// Instructions executed by every contract of the tx.
+    pub steps: u64,
+    pub memory: MemoryFootprint,
+    pub max_call_depth: usize,
+    pub events: usize,
+    pub wall_clock: Duration,
+}
+
+impl ExecutionMetrics {
+    /// Metrics of the tx `vm` has just executed, which took `wall_clock`.
+    pub fn from_vm(vm: &OlaVM, wall_clock: Duration) -> Self {
+        ExecutionMetrics {
+            steps: vm.executed_steps(),
+            memory: vm.memory_footprint,
+            max_call_depth: vm.max_call_depth,
+            events: vm.events.len(),
+            wall_clock,
+        }
+    }
+
+    /// One `# HELP`, `# TYPE` and sample line per metric. Memory addresses
+    /// the tx never used are left out.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        gauge(
+            "ola_call_steps",
+            "Instructions executed by the call.",
+            self.steps.to_string(),
+        );
+        if let Some(addr) = self.memory.max_stack_addr {
+            gauge(
+                "ola_call_memory_max_stack_addr",
+                "Highest stack address the call wrote.",
+                addr.to_string(),
+            );
+        }
+        if let Some(offset) = self.memory.max_heap_offset {
+            gauge(
+                "ola_call_memory_max_heap_offset",
+                "Highest heap offset the call wrote.",
+                offset.to_string(),
+            );
+        }
+        gauge(
+            "ola_call_memory_slots_touched",
+            "Distinct memory addresses the call accessed.",
+            self.memory.slots_touched.to_string(),
+        );
+        gauge(
+            "ola_call_max_call_depth",
+            "Deepest contract call nesting of the call.",
+            self.max_call_depth.to_string(),
+        );
+        gauge(
+            "ola_call_events",
+            "Events emitted by the call.",
+            self.events.to_string(),
+        );
+        gauge(
+            "ola_call_wall_clock_seconds",
+            "Wall-clock time the call took to execute.",
+            self.wall_clock.as_secs_f64().to_string(),
+        );
+        out
+    }
+}
+
+/// Writes `metrics` to `path` in the Prometheus text format.
+pub fn write_metrics(metrics: &ExecutionMetrics, path: &Path) -> anyhow::Result<()> {
+    File::create(path)?.write_all(metrics.render().as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::vm::memory::MemoryFootprint;
+    use std::time::Duration;
+
+    use super::ExecutionMetrics;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = ExecutionMetrics {
+            steps: 120,
+            memory: MemoryFootprint {
+                max_stack_addr: Some(18446744060824649731),
+                max_heap_offset: None,
+                slots_touched: 9,
+            },
+            max_call_depth: 2,
+            events: 3,
+            wall_clock: Duration::from_millis(1500),
+        };
+        let out = metrics.render();
+        assert!(out.contains("# TYPE ola_call_steps gauge\nola_call_steps 120\n"));
+        assert!(out.contains("ola_call_memory_max_stack_addr 18446744060824649731\n"));
+        assert!(!out.contains("ola_call_memory_max_heap_offset"));
+        assert!(out.contains("ola_call_memory_slots_touched 9\n"));
+        assert!(out.contains("ola_call_max_call_depth 2\n"));
+        assert!(out.contains("ola_call_events 3\n"));
+        assert!(out.contains("ola_call_wall_clock_seconds 1.5\n"));
+    }
+}
@@ -0,0 +1,206 @@
+use core::{
+    types::{Field, GoldilocksField},
+    vm::transaction::TxCtxInfo,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethereum_types::H256;
+use serde_derive::Deserialize;
+
+use crate::utils::{h256_to_u64_array, parse_address, parse_hex_word, OLA_RAW_TX_TYPE};
+
+pub const DEFAULT_CHAIN_ID: u64 = 1027;
+
+/// A named bundle of tx-context settings. Fields left out fall back to the
+/// command line flags, then to the defaults used without a preset.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TxContextPreset {
+    pub caller: Option<String>,
+    pub chain_id: Option<u64>,
+    pub version: Option<u32>,
+    pub sequencer: Option<String>,
+    pub block: Option<u64>,
+    pub timestamp: Option<u64>,
+    pub sig_r: Option<String>,
+    pub sig_s: Option<String>,
+    pub tx_hash: Option<String>,
+}
+
+impl TxContextPreset {
+    /// Loads preset `name` from a JSON file mapping preset names to presets.
+    pub fn load(path: &Path, name: &str) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow::anyhow!("failed to open presets file {:?}: {}", path, e))?;
+        let mut presets: HashMap<String, TxContextPreset> = serde_json::from_reader(file)?;
+        match presets.remove(name) {
+            Some(preset) => Ok(preset),
+            None => anyhow::bail!("unknown preset '{}' in {:?}", name, path),
+        }
+    }
+
+    /// Returns `self` with every setting given in `overrides` replaced.
+    pub fn merge(self, overrides: TxContextPreset) -> Self {
+        TxContextPreset {
+            caller: overrides.caller.or(self.caller),
+            chain_id: overrides.chain_id.or(self.chain_id),
+            version: overrides.version.or(self.version),
+            sequencer: overrides.sequencer.or(self.sequencer),
+            block: overrides.block.or(self.block),
+            timestamp: overrides.timestamp.or(self.timestamp),
+            sig_r: overrides.sig_r.or(self.sig_r),
+            sig_s: overrides.sig_s.or(self.sig_s),
+            tx_hash: overrides.tx_hash.or(self.tx_hash),
+        }
+    }
+
+    /// Builds the tx context. A missing caller is random, a missing timestamp
+    /// is the current time.
+    pub fn tx_ctx_info(&self, nonce: u32) -> anyhow::Result<TxCtxInfo> {
+        let caller_address = match &self.caller {
+            Some(addr) => parse_address("caller", addr)?,
+            None => h256_to_u64_array(&H256::random()),
+        };
+        let sequencer_address = match &self.sequencer {
+            Some(addr) => parse_address("sequencer", addr)?,
+            None => [0; 4],
+        };
+        let hex_word = |name: &str, value: &Option<String>| match value {
+            Some(value) => parse_hex_word(name, value),
+            None => Ok([0; 4]),
+        };
+        let signature_r = hex_word("signature r", &self.sig_r)?;
+        let signature_s = hex_word("signature s", &self.sig_s)?;
+        let tx_hash = hex_word("tx hash", &self.tx_hash)?;
+        let block_timestamp = match self.timestamp {
+            Some(n) => n,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        Ok(TxCtxInfo {
+            block_number: GoldilocksField::from_canonical_u64(self.block.unwrap_or(0)),
+            block_timestamp: GoldilocksField::from_canonical_u64(block_timestamp),
+            sequencer_address: sequencer_address.map(|n| GoldilocksField::from_canonical_u64(n)),
+            version: GoldilocksField::from_canonical_u32(self.version.unwrap_or(OLA_RAW_TX_TYPE)),
+            chain_id: GoldilocksField::from_canonical_u64(
+                self.chain_id.unwrap_or(DEFAULT_CHAIN_ID),
+            ),
+            caller_address: caller_address.map(|n| GoldilocksField::from_canonical_u64(n)),
+            nonce: GoldilocksField::from_canonical_u32(nonce),
+            signature_r: signature_r.map(|n| GoldilocksField::from_canonical_u64(n)),
+            signature_s: signature_s.map(|n| GoldilocksField::from_canonical_u64(n)),
+            tx_hash: tx_hash.map(|n| GoldilocksField::from_canonical_u64(n)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::TxContextPreset;
+
+    #[test]
+    fn test_preset_with_overrides() {
+        let path = std::env::temp_dir().join("mini_ola_test_presets.json");
+        let mut file = std::fs::File::create(&path).unwrap();
+        write!(
+            file,
+            r#"{{
+                "alice": {{
+                    "caller": "0x0a",
+                    "chain_id": 7,
+                    "sequencer": "0x0b",
+                    "block": 100,
+                    "timestamp": 1700000000
+                }}
+            }}"#
+        )
+        .unwrap();
+
+        assert!(TxContextPreset::load(&path, "bob").is_err());
+
+        let preset = TxContextPreset::load(&path, "alice").unwrap();
+        let ctx = preset.clone().tx_ctx_info(0).unwrap();
+        assert_eq!(ctx.chain_id.0, 7);
+        assert_eq!(ctx.block_number.0, 100);
+        assert_eq!(ctx.block_timestamp.0, 1700000000);
+        assert_eq!(ctx.caller_address.map(|fe| fe.0), [0, 0, 0, 10]);
+        assert_eq!(ctx.sequencer_address.map(|fe| fe.0), [0, 0, 0, 11]);
+
+        let overrides = TxContextPreset {
+            caller: Some("0x0c".to_string()),
+            block: Some(5),
+            version: Some(3),
+            ..Default::default()
+        };
+        let ctx = preset.merge(overrides).tx_ctx_info(9).unwrap();
+        assert_eq!(ctx.caller_address.map(|fe| fe.0), [0, 0, 0, 12]);
+        assert_eq!(ctx.block_number.0, 5);
+        assert_eq!(ctx.chain_id.0, 7);
+        assert_eq!(ctx.version.0, 3);
+        assert_eq!(ctx.nonce.0, 9);
+        assert_eq!(ctx.block_timestamp.0, 1700000000);
+    }
+
+    #[test]
+    fn test_invalid_caller_address() {
+        let preset = |caller: &str| TxContextPreset {
+            caller: Some(caller.to_string()),
+            ..Default::default()
+        };
+        let err = preset(&format!("0x{}", "ab".repeat(33)))
+            .tx_ctx_info(0)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid caller address: expected 32-byte hex, got 33 bytes"
+        );
+        let err = preset("0x").tx_ctx_info(0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid caller address: expected 32-byte hex, got 0 bytes"
+        );
+        let err = preset("0xzz").tx_ctx_info(0).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid caller address: '0xzz' is not hex"));
+    }
+
+    #[test]
+    fn test_signature_and_tx_hash() {
+        let ctx = TxContextPreset::default().tx_ctx_info(0).unwrap();
+        assert_eq!(ctx.signature_r.map(|fe| fe.0), [0; 4]);
+        assert_eq!(ctx.tx_hash.map(|fe| fe.0), [0; 4]);
+
+        let preset = TxContextPreset {
+            sig_r: Some("0x01".to_string()),
+            sig_s: Some(format!("0x{}", "00".repeat(24) + "0000000000000002")),
+            tx_hash: Some(
+                "0x0300000000000000000000000000000000000000000000000000000000000000".to_string(),
+            ),
+            ..Default::default()
+        };
+        let ctx = preset.tx_ctx_info(0).unwrap();
+        assert_eq!(ctx.signature_r.map(|fe| fe.0), [0, 0, 0, 1]);
+        assert_eq!(ctx.signature_s.map(|fe| fe.0), [0, 0, 0, 2]);
+        assert_eq!(ctx.tx_hash.map(|fe| fe.0), [0x0300000000000000, 0, 0, 0]);
+
+        let err = TxContextPreset {
+            tx_hash: Some("0xzz".to_string()),
+            ..Default::default()
+        }
+        .tx_ctx_info(0)
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid tx hash: '0xzz' is not hex"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fs::File, panic, path::PathBuf};
+
+use anyhow::bail;
+use clap::Parser;
+use ola_lang_abi::{Abi, Function, Type, Value};
+
+use crate::utils::ExpandedPathbufParser;
+
+use super::parser::{FromValue, ToValue};
+
+#[derive(Debug, Parser)]
+pub struct AbiSelfTest {
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the JSON ABI"
+    )]
+    abi: PathBuf,
+}
+
+fn is_composite(t: &Type) -> bool {
+    matches!(
+        t,
+        Type::FixedArray(_, _) | Type::Array(_) | Type::Tuple(_) | Type::U256
+    )
+}
+
+/// Returns a representative cli argument for a value of type `t`, in the
+/// format `ToValue` parses.
+pub fn sample_input(t: &Type) -> anyhow::Result<String> {
+    let sample = match t {
+        Type::U32 => "1".to_string(),
+        Type::Field => "2".to_string(),
+        Type::Hash | Type::Address => "0x03".to_string(),
+        Type::Bool => "true".to_string(),
+        Type::String => "ola".to_string(),
+        Type::Fields => "[1,2]".to_string(),
+        Type::U256 => "4".to_string(),
+        Type::FixedArray(t, size) => {
+            if is_composite(t) {
+                bail!("Composite types in FixedArray has not been supported for cli tools.")
+            }
+            let items = vec![sample_input(t)?; *size as usize];
+            format!("[{}]", items.join(","))
+        }
+        Type::Array(t) => {
+            if is_composite(t) {
+                bail!("Composite types in Array has not been supported for cli tools.")
+            }
+            let items = vec![sample_input(t)?; 2];
+            format!("[{}]", items.join(","))
+        }
+        Type::Tuple(attrs) => {
+            let mut items = Vec::new();
+            for (_, t) in attrs {
+                if is_composite(t) {
+                    bail!("Composite types in Tuple has not been supported for cli tools.")
+                }
+                items.push(sample_input(t)?);
+            }
+            format!("{{{}}}", items.join(","))
+        }
+    };
+    Ok(sample)
+}
+
+/// Encodes sample arguments for `func` and, when its outputs have the same
+/// types as its inputs, decodes the encoding back and compares the values.
+pub fn self_test_function(abi: &Abi, func: &Function) -> anyhow::Result<()> {
+    let mut params: Vec<Value> = Vec::new();
+    for param in func.inputs.iter() {
+        let input = sample_input(&param.type_)?;
+        let param = param.clone();
+        let value = panic::catch_unwind(|| ToValue::parse_input(param, input));
+        match value {
+            Ok(value) => params.push(value),
+            Err(_) => bail!("failed to parse sample input for {:?}", func.inputs),
+        }
+    }
+    let signature = func.signature();
+    let encoded = match abi.encode_input_with_signature(signature.as_str(), params.as_slice()) {
+        Ok(encoded) => encoded,
+        Err(e) => bail!("failed to encode inputs: {}", e),
+    };
+
+    let input_types: Vec<String> = func
+        .inputs
+        .iter()
+        .map(|p| format!("{:?}", p.type_))
+        .collect();
+    let output_types: Vec<String> = func
+        .outputs
+        .iter()
+        .map(|p| format!("{:?}", p.type_))
+        .collect();
+    if input_types != output_types {
+        return Ok(());
+    }
+    // calldata ends with the params length and the function selector
+    let encoded_params = &encoded[..encoded.len() - 2];
+    let decoded = match abi.decode_output_from_slice(signature.as_str(), encoded_params) {
+        Ok(decoded) => decoded,
+        Err(e) => bail!("failed to decode outputs: {}", e),
+    };
+    let decoded: Vec<String> = decoded
+        .1
+        .reader()
+        .by_index
+        .iter()
+        .map(|dp| FromValue::parse_input(dp.value.clone()))
+        .collect();
+    let expected: Vec<String> = params.into_iter().map(FromValue::parse_input).collect();
+    if decoded != expected {
+        bail!(
+            "round trip mismatch: encoded {:?}, decoded {:?}",
+            expected,
+            decoded
+        )
+    }
+    Ok(())
+}
+
+impl AbiSelfTest {
+    pub fn run(self) -> anyhow::Result<()> {
+        let abi_file = File::open(self.abi).expect("failed to open ABI file");
+        let abi: Abi = serde_json::from_reader(abi_file)?;
+
+        let mut failed = 0;
+        for func in abi.functions.iter() {
+            match self_test_function(&abi, func) {
+                Ok(_) => println!("{}: pass", func.signature()),
+                Err(e) => {
+                    failed += 1;
+                    println!("{}: fail, {}", func.signature(), e)
+                }
+            }
+        }
+        if failed > 0 {
+            bail!("{} of {} functions failed", failed, abi.functions.len())
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::Abi;
+
+    use super::self_test_function;
+
+    fn abi(json: &str) -> Abi {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_abi_self_test() {
+        let valid = abi(r#"[
+            {
+                "name": "echo",
+                "type": "function",
+                "inputs": [
+                    { "name": "a", "type": "u32" },
+                    { "name": "b", "type": "field" },
+                    { "name": "c", "type": "address" }
+                ],
+                "outputs": [
+                    { "name": "", "type": "u32" },
+                    { "name": "", "type": "field" },
+                    { "name": "", "type": "address" }
+                ]
+            }
+        ]"#);
+        assert!(self_test_function(&valid, &valid.functions[0]).is_ok());
+
+        let malformed = abi(r#"[
+            {
+                "name": "nested",
+                "type": "function",
+                "inputs": [{ "name": "a", "type": "u32[][]" }],
+                "outputs": []
+            }
+        ]"#);
+        assert!(self_test_function(&malformed, &malformed.functions[0]).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use core::{
+    crypto::poseidon_trace::calculate_arbitrary_poseidon,
+    merkle_tree::log::{StorageLogKind, StorageQuery},
+    storage::db::SequencerColumnFamily,
+    types::{merkle_tree::tree_key_to_u8_arr, Field, GoldilocksField},
+    vm::transaction::TxCtxInfo,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use ethereum_types::H256;
+use executor::BatchCacheManager;
+use ola_lang_abi::Abi;
+use rocksdb::WriteBatch;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::utils::{
+    h256_to_u64_array, parse_address, u64_array_to_h256, ExpandedPathbufParser, OLA_RAW_TX_TYPE,
+};
+
+use super::call::encode_call;
+use zk_vm::OlaVM;
+
+#[derive(Debug, Parser)]
+pub struct Batch {
+    #[clap(long, help = "Path of rocksdb database")]
+    db: Option<PathBuf>,
+    #[clap(long, help = "Caller Address")]
+    caller: Option<String>,
+    #[clap(
+        long,
+        help = "Nonce of the first transaction of every caller, increased by one per call"
+    )]
+    nonce: Option<u32>,
+    #[clap(long, help = "Provide block number manually")]
+    block: Option<u64>,
+    #[clap(long, help = "Provide second timestamp manually")]
+    timestamp: Option<u64>,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the contract ABI JSON file"
+    )]
+    abi: PathBuf,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the batch file, one contract call per line, optionally prefixed with \
+                caller=<address>"
+    )]
+    batch: PathBuf,
+}
+
+/// Net effect of a batch on a single storage slot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotChange {
+    pub contract_addr: String,
+    pub storage_key: String,
+    pub pre_value: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub calls: usize,
+    pub changes: Vec<SlotChange>,
+}
+
+/// A call of a batch file, with the caller it runs as when it overrides the
+/// one of the batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchCall {
+    pub caller: Option<[u64; 4]>,
+    pub args: Vec<String>,
+}
+
+/// Reads contract calls from a batch file. Every non-empty line holds the
+/// contract address, the function name and its arguments separated by
+/// whitespace, the same layout as the positional args of `invoke`. A line
+/// may start with `caller=<address>` to run its call as that caller. Lines
+/// starting with `#` are ignored. The file may also be a JSON array whose
+/// items are a call line or an array of its arguments.
+pub fn read_batch_file(path: &Path) -> anyhow::Result<Vec<BatchCall>> {
+    let content = std::fs::read_to_string(path)?;
+    if content.trim_start().starts_with('[') {
+        let items: Vec<serde_json::Value> = serde_json::from_str(&content)?;
+        return items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let args = match item {
+                    serde_json::Value::String(line) => split_call_line(&line),
+                    serde_json::Value::Array(_) => serde_json::from_value(item)
+                        .map_err(|e| anyhow::anyhow!("invalid call {}: {}", index, e))?,
+                    _ => anyhow::bail!("invalid call {}: expected a string or an array", index),
+                };
+                batch_call(args)
+            })
+            .collect();
+    }
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| batch_call(split_call_line(line)))
+        .collect()
+}
+
+fn split_call_line(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+/// Splits the `caller=<address>` override off the args of a call.
+fn batch_call(mut args: Vec<String>) -> anyhow::Result<BatchCall> {
+    let caller = match args.first().and_then(|arg| arg.strip_prefix("caller=")) {
+        Some(addr) => Some(parse_address("caller", addr)?),
+        None => None,
+    };
+    if caller.is_some() {
+        args.remove(0);
+    }
+    Ok(BatchCall { caller, args })
+}
+
+/// Caller and nonce of every call. Calls without a caller override run as
+/// `default_caller`. Each caller's nonce starts at `first_nonce` and is
+/// increased by one per call it makes.
+pub fn call_contexts(
+    calls: &[BatchCall],
+    default_caller: [u64; 4],
+    first_nonce: u32,
+) -> Vec<([u64; 4], u32)> {
+    let mut nonces: BTreeMap<[u64; 4], u32> = BTreeMap::new();
+    calls
+        .iter()
+        .map(|call| {
+            let caller = call.caller.unwrap_or(default_caller);
+            let nonce = nonces.entry(caller).or_insert(first_nonce);
+            let context = (caller, *nonce);
+            *nonce += 1;
+            context
+        })
+        .collect()
+}
+
+fn tx_context(
+    caller_address: [u64; 4],
+    nonce: u32,
+    block_number: u64,
+    block_timestamp: u64,
+) -> TxCtxInfo {
+    TxCtxInfo {
+        block_number: GoldilocksField::from_canonical_u64(block_number),
+        block_timestamp: GoldilocksField::from_canonical_u64(block_timestamp),
+        sequencer_address: [GoldilocksField::ZERO; 4],
+        version: GoldilocksField::from_canonical_u32(OLA_RAW_TX_TYPE),
+        chain_id: GoldilocksField::from_canonical_u64(1027),
+        caller_address: caller_address.map(|n| GoldilocksField::from_canonical_u64(n)),
+        nonce: GoldilocksField::from_canonical_u32(nonce),
+        signature_r: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
+        signature_s: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
+        tx_hash: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
+    }
+}
+
+/// Executes `calls` in order against the database in `db_home`, each with
+/// its tx context from `contexts`, and returns the storage queries of the
+/// whole batch. Every call is persisted so the next one observes its writes.
+pub fn execute_batch(
+    db_home: &Path,
+    abi: &Abi,
+    calls: &[BatchCall],
+    contexts: Vec<TxCtxInfo>,
+) -> anyhow::Result<Vec<StorageQuery>> {
+    let tree_db_path_buf = db_home.join("tree");
+    let state_db_path_buf = db_home.join("state");
+    let mut queries = Vec::new();
+    for (index, (call, tx_init_info)) in calls.iter().zip(contexts).enumerate() {
+        let (to, function_sig_name, args) = match call.args.as_slice() {
+            [to, function_sig_name, args @ ..] => (to, function_sig_name, args.to_vec()),
+            _ => anyhow::bail!(
+                "call {} needs a contract address and a function signature",
+                index
+            ),
+        };
+        let to = parse_address("contract", to.as_str())?;
+        let (_, calldata) = encode_call(abi, function_sig_name.as_str(), args)
+            .map_err(|e| anyhow::anyhow!("call {}: {}", index, e))?;
+
+        let mut vm = OlaVM::new_local(
+            tree_db_path_buf.as_path(),
+            state_db_path_buf.as_path(),
+            tx_init_info,
+        );
+        vm.execute_tx(
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            calldata
+                .iter()
+                .map(|n| GoldilocksField::from_canonical_u64(*n))
+                .collect(),
+            &mut BatchCacheManager::default(),
+            false,
+        )
+        .map_err(|e| anyhow::anyhow!("Invoke TX Error in call {}: {}", index, e))?;
+
+        let mut batch = WriteBatch::default();
+        vm.ola_state.storage_queries.iter().for_each(|q| {
+            if q.kind != StorageLogKind::Read {
+                let mut tree_key = Vec::new();
+                tree_key.extend_from_slice(&q.contract_addr);
+                tree_key.extend_from_slice(&q.storage_key);
+                let tree_key = calculate_arbitrary_poseidon(&tree_key);
+                let key = tree_key_to_u8_arr(&tree_key);
+                let cf = vm
+                    .ola_state
+                    .state_storage
+                    .db
+                    .cf_sequencer_handle(SequencerColumnFamily::State);
+                let value = tree_key_to_u8_arr(&q.value);
+                batch.put_cf(cf, &key, &value);
+            }
+        });
+        vm.ola_state.state_storage.db.write(batch)?;
+        queries.append(&mut vm.ola_state.storage_queries);
+    }
+    Ok(queries)
+}
+
+/// Folds the storage queries of a whole batch into its net effect: the value
+/// a slot held before the first call and the value it holds after the last
+/// one. Slots that end up with their original value are left out.
+pub fn aggregate_storage_changes(queries: &[StorageQuery]) -> Vec<SlotChange> {
+    let mut slots: BTreeMap<([u64; 4], [u64; 4]), ([u64; 4], [u64; 4])> = BTreeMap::new();
+    for q in queries.iter().filter(|q| q.kind != StorageLogKind::Read) {
+        let key = (q.contract_addr.map(|fe| fe.0), q.storage_key.map(|fe| fe.0));
+        let value = q.value.map(|fe| fe.0);
+        slots
+            .entry(key)
+            .and_modify(|slot| slot.1 = value)
+            .or_insert((q.pre_value.map(|fe| fe.0), value));
+    }
+    slots
+        .into_iter()
+        .filter(|(_, (pre_value, value))| pre_value != value)
+        .map(
+            |((contract_addr, storage_key), (pre_value, value))| SlotChange {
+                contract_addr: hex::encode(u64_array_to_h256(&contract_addr).0),
+                storage_key: hex::encode(u64_array_to_h256(&storage_key).0),
+                pre_value: hex::encode(u64_array_to_h256(&pre_value).0),
+                value: hex::encode(u64_array_to_h256(&value).0),
+            },
+        )
+        .collect()
+}
+
+impl Batch {
+    pub fn run(self) -> anyhow::Result<()> {
+        let caller_address: [u64; 4] = if let Some(addr) = self.caller {
+            parse_address("caller", addr.as_str())?
+        } else {
+            h256_to_u64_array(&H256::random())
+        };
+
+        let first_nonce = if let Some(n) = self.nonce { n } else { 1 };
+        let block_number = if let Some(n) = self.block { n } else { 0 };
+        let block_timestamp = if let Some(n) = self.timestamp {
+            n
+        } else {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        let db_home = match self.db {
+            Some(path) => path,
+            None => PathBuf::from("./db"),
+        };
+
+        let abi_file = File::open(self.abi).expect("failed to open ABI file");
+        let abi: Abi = serde_json::from_reader(abi_file)?;
+        let calls = read_batch_file(self.batch.as_path())?;
+        let contexts = call_contexts(&calls, caller_address, first_nonce)
+            .into_iter()
+            .map(|(caller, nonce)| tx_context(caller, nonce, block_number, block_timestamp))
+            .collect();
+        let queries = execute_batch(db_home.as_path(), &abi, &calls, contexts)?;
+
+        let report = BatchReport {
+            calls: calls.len(),
+            changes: aggregate_storage_changes(&queries),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        merkle_tree::log::{StorageLogKind, StorageQuery},
+        types::{Field, GoldilocksField},
+    };
+
+    use std::{fs::File, path::PathBuf};
+
+    use ola_lang_abi::Abi;
+
+    use super::{
+        aggregate_storage_changes, call_contexts, execute_batch, read_batch_file, tx_context,
+        BatchCall,
+    };
+    use crate::{
+        subcommands::call::test_utils::{deploy_test_contract, TEST_ADDRESS},
+        utils::address_to_u64_array,
+    };
+
+    fn approve(caller: Option<[u64; 4]>) -> BatchCall {
+        BatchCall {
+            caller,
+            args: [TEST_ADDRESS, "approve", "0x0456", "10"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+
+    fn write(slot: u64, pre_value: u64, value: u64) -> StorageQuery {
+        StorageQuery {
+            block_timestamp: 0,
+            kind: StorageLogKind::RepeatedWrite,
+            contract_addr: [GoldilocksField::from_canonical_u64(1); 4],
+            storage_key: [GoldilocksField::from_canonical_u64(slot); 4],
+            pre_value: [GoldilocksField::from_canonical_u64(pre_value); 4],
+            value: [GoldilocksField::from_canonical_u64(value); 4],
+        }
+    }
+
+    #[test]
+    fn test_aggregate_overlapping_slots() {
+        // first call writes slots 1 and 2, second call overwrites slot 2 and
+        // restores slot 1 to its original value.
+        let queries = vec![
+            write(1, 0, 5),
+            write(2, 0, 6),
+            write(2, 6, 7),
+            write(1, 5, 0),
+        ];
+        let changes = aggregate_storage_changes(&queries);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].pre_value, format!("{:064x}", 0));
+        assert_eq!(changes[0].value, "0000000000000007".repeat(4));
+    }
+
+    #[test]
+    fn test_nonce_per_caller() {
+        let alice = [0, 0, 0, 0xa];
+        let bob = [0, 0, 0, 0xb];
+        let calls = vec![approve(None), approve(Some(bob)), approve(Some(alice))];
+        assert_eq!(
+            call_contexts(&calls, alice, 5),
+            vec![(alice, 5), (bob, 5), (alice, 6)]
+        );
+    }
+
+    #[test]
+    fn test_callers_share_state() {
+        let (db_home, abi) = deploy_test_contract("callers", "erc20");
+
+        // Alice then Bob approve the same spender, each writing their own
+        // allowance slot of the same contract.
+        let alice = address_to_u64_array("0x0a").unwrap();
+        let bob = address_to_u64_array("0x0b").unwrap();
+        let calls = vec![approve(Some(alice)), approve(Some(bob))];
+        let contexts = call_contexts(&calls, alice, 1)
+            .into_iter()
+            .map(|(caller, nonce)| tx_context(caller, nonce, 0, 0))
+            .collect();
+        let queries = execute_batch(db_home.as_path(), &abi, &calls, contexts).unwrap();
+        let changes = aggregate_storage_changes(&queries);
+        assert_eq!(changes.len(), 2);
+        assert_ne!(changes[0].storage_key, changes[1].storage_key);
+        assert!(changes
+            .iter()
+            .all(|change| change.value.ends_with(&format!("{:016x}", 10))));
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_invalid_call_errors() {
+        let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../executor/test");
+        let abi: Abi = serde_json::from_reader(
+            File::open(test_dir.join("contracts-abi/erc20_abi.json")).unwrap(),
+        )
+        .unwrap();
+        // Calls are encoded before any database is opened.
+        let db_home = std::env::temp_dir().join("mini_ola_invalid_calls");
+        let run = |args: &[&str]| {
+            let call = BatchCall {
+                caller: None,
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+            };
+            let contexts = vec![tx_context([0; 4], 0, 0, 0)];
+            execute_batch(db_home.as_path(), &abi, &[call], contexts)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(run(&["0x0123"]).contains("needs a contract address and a function"));
+        assert!(run(&["0x0123", "missing"]).contains("function missing not found"));
+        assert!(run(&["0x0123", "approve", "0x0456"]).contains("invalid args length"));
+    }
+
+    #[test]
+    fn test_read_batch_file() {
+        let dir = std::env::temp_dir();
+        let lines = dir.join(format!("mini_ola_calls_{}.txt", std::process::id()));
+        std::fs::write(
+            &lines,
+            "# books\n0x0123 createBook 7 ola\n\ncaller=0x0a 0x0123 createBook 8 vm\n",
+        )
+        .unwrap();
+        let calls = read_batch_file(lines.as_path()).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].caller, None);
+        assert_eq!(calls[1].caller, Some(address_to_u64_array("0x0a").unwrap()));
+        assert_eq!(calls[1].args, vec!["0x0123", "createBook", "8", "vm"]);
+
+        let array = dir.join(format!("mini_ola_calls_{}.json", std::process::id()));
+        std::fs::write(
+            &array,
+            r#"["0x0123 createBook 7 ola", ["caller=0x0a", "0x0123", "createBook", "8", "vm"]]"#,
+        )
+        .unwrap();
+        assert_eq!(read_batch_file(array.as_path()).unwrap(), calls);
+        let _ = std::fs::remove_file(lines);
+        let _ = std::fs::remove_file(array);
+    }
+}
@@ -1,26 +1,63 @@
 use core::{
+    merkle_tree::log::StorageLogKind,
+    program::binary_program::ProphetMock,
     types::{Field, GoldilocksField},
-    vm::transaction::TxCtxInfo,
 };
 use std::{
+    collections::BTreeSet,
     fs::File,
-    path::PathBuf,
-    time::{SystemTime, UNIX_EPOCH},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
 };
 
 use clap::Parser;
-use ethereum_types::H256;
 use executor::BatchCacheManager;
-use ola_lang_abi::{Abi, Param, Value};
-use plonky2::hash::utils::bytes_to_u64s;
+use ola_lang_abi::{Abi, Function, Param, Value};
+use serde_json::json;
 
 use crate::{
-    subcommands::parser::FromValue,
-    utils::{address_from_hex_be, h256_to_u64_array, ExpandedPathbufParser, OLA_RAW_TX_TYPE},
+    breakpoint::Breakpoint,
+    coverage::tx_coverage,
+    events::{write_events, EventsFormat},
+    formatter::OutputFormatters,
+    metrics::{write_metrics, ExecutionMetrics},
+    preset::TxContextPreset,
+    subcommands::{
+        batch::{read_batch_file, BatchCall},
+        layout::type_name,
+        parser::FromValue,
+        replay::Recording,
+    },
+    utils::{parse_address, ExpandedPathbufParser},
 };
 
 use super::parser::ToValue;
-use zk_vm::OlaVM;
+use zk_vm::{OlaVM, PausedTx, TxStatus};
+
+/// How the return data of a call is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// One formatted value per line.
+    Text,
+    /// A JSON array of `{name, type, value}` objects.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "unknown output format '{}', expected text or json",
+                s
+            )),
+        }
+    }
+}
 
 #[derive(Debug, Parser)]
 pub struct Call {
@@ -28,40 +65,434 @@ pub struct Call {
     db: Option<PathBuf>,
     #[clap(long, help = "Caller Address")]
     caller: Option<String>,
+    #[clap(long, help = "Sequencer Address")]
+    sequencer: Option<String>,
     #[clap(long, help = "Provide block number manually")]
     block: Option<u64>,
     #[clap(long, help = "Provide second timestamp manually")]
     timestamp: Option<u64>,
+    #[clap(long, help = "Provide chain id manually")]
+    chain_id: Option<u64>,
+    #[clap(long, help = "Provide transaction version manually")]
+    version: Option<u32>,
+    #[clap(long, help = "Provide transaction nonce manually")]
+    nonce: Option<u32>,
+    #[clap(long, help = "Signature r of the transaction, as 32-byte hex")]
+    sig_r: Option<String>,
+    #[clap(long, help = "Signature s of the transaction, as 32-byte hex")]
+    sig_s: Option<String>,
+    #[clap(long, help = "Hash of the transaction, as 32-byte hex")]
+    tx_hash: Option<String>,
+    #[clap(long, help = "Name of the tx context preset to start from")]
+    preset: Option<String>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        default_value = "presets.json",
+        help = "Path of the tx context presets file"
+    )]
+    presets: PathBuf,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "Record inputs, trace, outputs and state diff to a file for `replay`"
+    )]
+    record: Option<PathBuf>,
+    #[clap(
+        long = "break",
+        help = "Pause before the instruction at this pc and print the vm state, optionally only \
+                when a condition holds, e.g. \"12 if r3 == 5\", can be repeated"
+    )]
+    breakpoints: Vec<Breakpoint>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "Write the functions and branches the call executed, keyed by pc, to a JSON file"
+    )]
+    coverage: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Expected value of the next output, fails the call on a mismatch, can be repeated"
+    )]
+    expect: Vec<String>,
+    #[clap(
+        long,
+        default_value = "32",
+        help = "Print at most this many elements of an array output"
+    )]
+    max_output_elements: usize,
+    #[clap(long, help = "Print array outputs in full")]
+    full_output: bool,
+    #[clap(long, help = "Print the memory footprint of the call as JSON")]
+    mem_stats: bool,
+    #[clap(
+        long,
+        help = "Print the executed steps and trace length to stdout instead of stderr"
+    )]
+    stats: bool,
+    #[clap(
+        long,
+        help = "Execute the call like invoke, storage writes included, without persisting them"
+    )]
+    dry_run: bool,
+    #[clap(
+        long,
+        help = "Format outputs by name or type, e.g. address:hex,timestamp:iso"
+    )]
+    formatter: Option<String>,
+    #[clap(
+        long,
+        requires = "dry_run",
+        help = "Print the events the call emitted as text or ndjson, one event per line; only \
+                a dry run can emit events"
+    )]
+    events_format: Option<EventsFormat>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "JSON file with the values prophet inputs and outputs are mocked with"
+    )]
+    prophet: Option<PathBuf>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "Write the execution witness the prover consumes to a JSON file"
+    )]
+    witness: Option<PathBuf>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "Write steps, memory, call depth, events and wall-clock time of the call to a \
+                file in the Prometheus text format"
+    )]
+    metrics: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value = "text",
+        help = "Print the return data as text or as a JSON array of {name, type, value}"
+    )]
+    output: OutputFormat,
+    #[clap(
+        long,
+        help = "Comma-separated felts sent as calldata as is, without abi encoding; the abi can \
+                then be left out and return data is printed as raw u64s"
+    )]
+    raw_calldata: Option<String>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        help = "File of contract calls in the batch file format, one per line or a JSON array, \
+                executed in order against the same vm; lines starting with # are ignored"
+    )]
+    calls_file: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Address of the contract whose code runs, against the storage of the called \
+                contract, e.g. to test delegatecall; defaults to the called contract"
+    )]
+    code_address: Option<String>,
     #[clap(
         value_parser = ExpandedPathbufParser,
         help = "Path to the JSON keystore"
     )]
-    abi: PathBuf,
+    abi: Option<PathBuf>,
     #[clap(help = "One or more contract calls. See documentation for more details")]
     calls: Vec<String>,
 }
 
+/// Looks up `function_sig_name` in the ABI and encodes the cli arguments
+/// into calldata.
+pub fn encode_call<'a>(
+    abi: &'a Abi,
+    function_sig_name: &str,
+    args: Vec<String>,
+) -> anyhow::Result<(&'a Function, Vec<u64>)> {
+    let func = match abi
+        .functions
+        .iter()
+        .find(|func| func.name == function_sig_name)
+    {
+        Some(func) => func,
+        None => anyhow::bail!("function {} not found", function_sig_name),
+    };
+    let func_inputs = &func.inputs;
+    if args.len() != func_inputs.len() {
+        anyhow::bail!(
+            "invalid args length: {} args expected, you input {}",
+            func_inputs.len(),
+            args.len()
+        )
+    }
+    let param_to_input: Vec<(&Param, String)> =
+        func_inputs.into_iter().zip(args.into_iter()).collect();
+    let params: Vec<Value> = param_to_input
+        .iter()
+        .map(|(p, i)| ToValue::parse_input((**p).clone(), i.clone()))
+        .collect();
+    let calldata = abi
+        .encode_input_with_signature(func.signature().as_str(), params.as_slice())
+        .map_err(|e| anyhow::anyhow!("failed to encode calldata: {}", e))?;
+    Ok((func, calldata))
+}
+
+/// Parses comma-separated u64s, e.g. `1,2,3`, into calldata.
+pub fn parse_raw_calldata(raw: &str) -> anyhow::Result<Vec<u64>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|felt| !felt.is_empty())
+        .map(|felt| {
+            felt.parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("invalid calldata felt '{}': {}", felt, e))
+        })
+        .collect()
+}
+
+/// Executes `calls` in order against `vm`, sharing one cache manager the way
+/// the executor does for the txs of a batch, and returns the decoded return
+/// data of every call. Calls without a caller override run as the caller of
+/// the vm's context.
+pub fn execute_calls(
+    vm: &mut OlaVM,
+    abi: &Abi,
+    calls: Vec<BatchCall>,
+) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut cache_manager = BatchCacheManager::default();
+    let mut returns = Vec::new();
+    let default_caller = vm.ctx_info.caller_address;
+    for (index, call) in calls.into_iter().enumerate() {
+        vm.ctx_info.caller_address = match call.caller {
+            Some(caller) => caller.map(|n| GoldilocksField::from_canonical_u64(n)),
+            None => default_caller,
+        };
+        let mut arg_iter = call.args.into_iter();
+        let contract_address_hex = match arg_iter.next() {
+            Some(addr) => addr,
+            None => anyhow::bail!("call {} is empty", index),
+        };
+        let to = parse_address("contract", contract_address_hex.as_str())?;
+        let function_sig_name = match arg_iter.next() {
+            Some(name) => name,
+            None => anyhow::bail!("function signature needed in call {}", index),
+        };
+        let (func, calldata) = encode_call(abi, function_sig_name.as_str(), arg_iter.collect())
+            .map_err(|e| anyhow::anyhow!("call {}: {}", index, e))?;
+        vm.execute_tx(
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            calldata
+                .iter()
+                .map(|n| GoldilocksField::from_canonical_u64(*n))
+                .collect(),
+            &mut cache_manager,
+            false,
+        )
+        .map_err(|e| anyhow::anyhow!("Invoke TX Error in call {}: {}", index, e))?;
+        let ret: Vec<u64> = vm.ola_state.return_data.iter().map(|fe| fe.0).collect();
+        returns.push(decode_return(abi, func, &ret)?);
+    }
+    Ok(returns)
+}
+
+/// Decodes the return data of `func` into abi values.
+pub fn decode_return_values(abi: &Abi, func: &Function, ret: &[u64]) -> anyhow::Result<Vec<Value>> {
+    let decoded = abi
+        .decode_output_from_slice(func.signature().as_str(), ret)
+        .map_err(|e| anyhow::anyhow!("failed to decode return data: {}", e))?;
+    Ok(decoded
+        .1
+        .reader()
+        .by_index
+        .iter()
+        .map(|dp| dp.value.clone())
+        .collect())
+}
+
+/// Decodes the return data of `func` into cli strings.
+pub fn decode_return(abi: &Abi, func: &Function, ret: &[u64]) -> anyhow::Result<Vec<String>> {
+    Ok(decode_return_values(abi, func, ret)?
+        .into_iter()
+        .map(FromValue::parse_input)
+        .collect())
+}
+
+/// JSON array describing the outputs of `func`, one `{name, type, value}`
+/// object per output. Unnamed outputs are named by their position.
+pub fn outputs_json(func: &Function, values: &[Value]) -> serde_json::Value {
+    let outputs: Vec<serde_json::Value> = func
+        .outputs
+        .iter()
+        .zip(values.iter())
+        .enumerate()
+        .map(|(index, (param, value))| {
+            let name = match param.name.as_str() {
+                "" => index.to_string(),
+                name => name.to_string(),
+            };
+            json!({
+                "name": name,
+                "type": type_name(&param.type_),
+                "value": FromValue::parse_input(value.clone()),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(outputs)
+}
+
+/// Formats an output value for printing, cutting arrays longer than
+/// `max_elements` short with an ellipsis and the element count.
+pub fn format_output(value: Value, max_elements: Option<usize>) -> String {
+    let max = match max_elements {
+        Some(max) => max,
+        None => return FromValue::parse_input(value),
+    };
+    let (items, t) = match value {
+        Value::Array(items, t) | Value::FixedArray(items, t) if items.len() > max => (items, t),
+        value => return FromValue::parse_input(value),
+    };
+    if max == 0 {
+        return format!("[...] ({} elements)", items.len());
+    }
+    let shown = FromValue::parse_input(Value::Array(items[..max].to_vec(), t));
+    format!(
+        "{},...] ({} of {} elements)",
+        shown.trim_end_matches(']'),
+        max,
+        items.len()
+    )
+}
+
+/// Compares decoded return values with expected cli values, parsed against
+/// the outputs of `func`, and describes every output that differs.
+pub fn expectation_mismatches(
+    func: &Function,
+    actual: &[String],
+    expected: Vec<String>,
+) -> Vec<String> {
+    if expected.len() != func.outputs.len() {
+        return vec![format!(
+            "{} outputs expected, {} returns {}",
+            expected.len(),
+            func.name,
+            func.outputs.len()
+        )];
+    }
+    let mut mismatches = Vec::new();
+    for (index, (param, expected)) in func.outputs.iter().zip(expected).enumerate() {
+        let expected = FromValue::parse_input(ToValue::parse_input(param.clone(), expected));
+        let actual = actual.get(index).map(String::as_str).unwrap_or("<missing>");
+        if expected != actual {
+            mismatches.push(format!(
+                "output {}: expected {}, got {}",
+                index, expected, actual
+            ));
+        }
+    }
+    mismatches
+}
+
+/// Storage writes of the tx `vm` just executed. They only reach the state
+/// database when the caller commits them, as invoke does.
+pub fn pending_writes(vm: &OlaVM) -> usize {
+    vm.ola_state
+        .storage_queries
+        .iter()
+        .filter(|query| query.kind != StorageLogKind::Read)
+        .count()
+}
+
+/// Executes a call to `to` running the code deployed at `code`, invoking
+/// `on_pause` each time execution stops at one of `breakpoints` and resuming
+/// after it returns.
+pub fn execute_with_breakpoints(
+    vm: &mut OlaVM,
+    to: [u64; 4],
+    code: [u64; 4],
+    calldata: &[u64],
+    breakpoints: BTreeSet<u64>,
+    mut on_pause: impl FnMut(&PausedTx),
+) -> anyhow::Result<()> {
+    let mut cache_manager = BatchCacheManager::default();
+    let mut status = vm
+        .execute_tx_with_breakpoints(
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            code.map(|n| GoldilocksField::from_canonical_u64(n)),
+            calldata
+                .iter()
+                .map(|n| GoldilocksField::from_canonical_u64(*n))
+                .collect(),
+            &mut cache_manager,
+            false,
+            breakpoints,
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    while let TxStatus::Paused(tx) = status {
+        on_pause(&tx);
+        status = vm
+            .resume_tx(tx, &mut cache_manager)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+    }
+    Ok(())
+}
+
+/// Writes the witness of the tx `vm` just executed as JSON, in the trace
+/// format the prover consumes. Drains the per-contract traces of `vm`.
+pub fn write_witness(vm: &mut OlaVM, path: &Path) -> anyhow::Result<()> {
+    let witness = vm.take_witness().map_err(|e| anyhow::anyhow!("{}", e))?;
+    serde_json::to_writer(File::create(path)?, &witness)?;
+    Ok(())
+}
+
+/// Executes a call like `execute_with_breakpoints`, invoking `on_pause` only
+/// when one of `breakpoints` triggers at the paused pc.
+pub fn execute_with_conditional_breakpoints(
+    vm: &mut OlaVM,
+    to: [u64; 4],
+    code: [u64; 4],
+    calldata: &[u64],
+    breakpoints: &[Breakpoint],
+    mut on_pause: impl FnMut(&PausedTx),
+) -> anyhow::Result<()> {
+    let pcs = breakpoints.iter().map(|bp| bp.pc).collect();
+    execute_with_breakpoints(vm, to, code, calldata, pcs, |tx| {
+        if breakpoints.iter().any(|bp| bp.triggers(tx)) {
+            on_pause(tx);
+        }
+    })
+}
+
+fn print_paused(tx: &PausedTx) {
+    let code_addr: Vec<u64> = tx.code_exe_addr.iter().map(|fe| fe.0).collect();
+    let registers: Vec<u64> = tx.registers().iter().map(|fe| fe.0).collect();
+    println!(
+        "Breakpoint at pc {} in contract {:?}, clk {}",
+        tx.pc(),
+        code_addr,
+        tx.process.clk
+    );
+    println!("registers: {:?}", registers);
+}
+
 impl Call {
     pub fn run(self) -> anyhow::Result<()> {
-        let caller_address: [u64; 4] = if let Some(addr) = self.caller {
-            let bytes = address_from_hex_be(addr.as_str()).unwrap();
-            let caller_vec = bytes_to_u64s(&bytes);
-            let mut caller = [0u64; 4];
-            caller.clone_from_slice(&caller_vec[..4]);
-            caller
-        } else {
-            h256_to_u64_array(&H256::random())
+        let overrides = TxContextPreset {
+            caller: self.caller,
+            sequencer: self.sequencer,
+            block: self.block,
+            timestamp: self.timestamp,
+            chain_id: self.chain_id,
+            version: self.version,
+            sig_r: self.sig_r,
+            sig_s: self.sig_s,
+            tx_hash: self.tx_hash,
+            ..Default::default()
         };
-
-        let block_number = if let Some(n) = self.block { n } else { 0 };
-        let block_timestamp = if let Some(n) = self.timestamp {
-            n
-        } else {
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
+        let ctx = match self.preset {
+            Some(name) => {
+                TxContextPreset::load(self.presets.as_path(), name.as_str())?.merge(overrides)
+            }
+            None => overrides,
         };
+        let tx_init_info = ctx.tx_ctx_info(self.nonce.unwrap_or(0))?;
         let db_home = match self.db {
             Some(path) => path,
             None => PathBuf::from("./db"),
@@ -69,81 +500,243 @@ impl Call {
         let tree_db_path_buf = db_home.join("tree");
         let state_db_path_buf = db_home.join("state");
 
-        let mut arg_iter = self.calls.into_iter();
-        let contract_address_hex = arg_iter.next().expect("contract address needed");
-        let contract_address_bytes = address_from_hex_be(contract_address_hex.as_str()).unwrap();
-        let to_vec = bytes_to_u64s(&contract_address_bytes);
-        let mut to = [0u64; 4];
-        to.clone_from_slice(&to_vec[..4]);
-
-        let abi_file = File::open(self.abi).expect("failed to open ABI file");
-        let function_sig_name = arg_iter.next().expect("function signature needed");
-        let abi: Abi = serde_json::from_reader(abi_file)?;
-        let func = abi
-            .functions
-            .iter()
-            .find(|func| func.name == function_sig_name)
-            .expect("function not found");
-        let func_inputs = &func.inputs;
-        if arg_iter.len() != func_inputs.len() {
-            anyhow::bail!(
-                "invalid args length: {} args expected, you input {}",
-                func_inputs.len(),
-                arg_iter.len()
-            )
-        }
-        let param_to_input: Vec<(&Param, String)> =
-            func_inputs.into_iter().zip(arg_iter.into_iter()).collect();
-        let params: Vec<Value> = param_to_input
-            .iter()
-            .map(|(p, i)| ToValue::parse_input((**p).clone(), i.clone()))
+        if let Some(path) = self.calls_file {
+            if self.raw_calldata.is_some() || self.code_address.is_some() || !self.calls.is_empty()
+            {
+                anyhow::bail!(
+                    "--calls-file cannot be combined with --raw-calldata, --code-address or \
+                     positional calls"
+                )
+            }
+            // Only the return data of every call is printed.
+            let per_call_flags: Vec<&str> = [
+                ("--break", !self.breakpoints.is_empty()),
+                ("--record", self.record.is_some()),
+                ("--expect", !self.expect.is_empty()),
+                ("--output", self.output != OutputFormat::Text),
+                ("--formatter", self.formatter.is_some()),
+                ("--prophet", self.prophet.is_some()),
+                ("--coverage", self.coverage.is_some()),
+                ("--witness", self.witness.is_some()),
+                ("--metrics", self.metrics.is_some()),
+                ("--events-format", self.events_format.is_some()),
+            ]
+            .into_iter()
+            .filter(|(_, given)| *given)
+            .map(|(flag, _)| flag)
             .collect();
-        let calldata = abi
-            .encode_input_with_signature(func.signature().as_str(), params.as_slice())
-            .unwrap();
+            if !per_call_flags.is_empty() {
+                anyhow::bail!(
+                    "--calls-file cannot be combined with {}",
+                    per_call_flags.join(", ")
+                )
+            }
+            let abi_path = match self.abi {
+                Some(path) => path,
+                None => anyhow::bail!("an ABI file is needed to encode the calls of --calls-file"),
+            };
+            let abi: Abi = serde_json::from_reader(File::open(abi_path)?)?;
+            let calls = read_batch_file(path.as_path())?;
+            let mut vm = if self.dry_run {
+                OlaVM::new(
+                    tree_db_path_buf.as_path(),
+                    state_db_path_buf.as_path(),
+                    tx_init_info,
+                )
+            } else {
+                OlaVM::new_call(
+                    tree_db_path_buf.as_path(),
+                    state_db_path_buf.as_path(),
+                    tx_init_info,
+                )
+            };
+            for (index, values) in execute_calls(&mut vm, &abi, calls)?.into_iter().enumerate() {
+                println!("Return data of call {}:", index);
+                for value in values {
+                    println!("{}", value);
+                }
+            }
+            return Ok(());
+        }
 
-        let tx_init_info = TxCtxInfo {
-            block_number: GoldilocksField::from_canonical_u64(block_number),
-            block_timestamp: GoldilocksField::from_canonical_u64(block_timestamp),
-            sequencer_address: [GoldilocksField::ZERO; 4],
-            version: GoldilocksField::from_canonical_u32(OLA_RAW_TX_TYPE),
-            chain_id: GoldilocksField::from_canonical_u64(1027),
-            caller_address: caller_address.map(|n| GoldilocksField::from_canonical_u64(n)),
-            nonce: GoldilocksField::ZERO,
-            signature_r: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
-            signature_s: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
-            tx_hash: [0; 4].map(|n| GoldilocksField::from_canonical_u64(n)),
+        let raw_calldata = match &self.raw_calldata {
+            Some(raw) => Some(parse_raw_calldata(raw)?),
+            None => None,
+        };
+        let (abi_path, calls) = match (&raw_calldata, self.abi) {
+            // Without an abi the contract address is the first positional
+            // argument.
+            (Some(_), Some(path)) if self.calls.is_empty() => {
+                (None, vec![path.to_string_lossy().into_owned()])
+            }
+            (_, abi_path) => (abi_path, self.calls),
+        };
+        if raw_calldata.is_some() && !self.expect.is_empty() {
+            anyhow::bail!("--expect needs the abi encoding, it cannot be used with --raw-calldata")
+        }
+        let mut arg_iter = calls.into_iter();
+        let contract_address_hex = arg_iter.next().expect("contract address needed");
+        let to = parse_address("contract", contract_address_hex.as_str())?;
+        let code = match &self.code_address {
+            Some(addr) => parse_address("code", addr)?,
+            None => to,
         };
 
-        let mut vm = OlaVM::new_call(
-            tree_db_path_buf.as_path(),
-            state_db_path_buf.as_path(),
-            tx_init_info,
-        );
-        let exec_res = vm.execute_tx(
-            to.map(|n| GoldilocksField::from_canonical_u64(n)),
-            to.map(|n| GoldilocksField::from_canonical_u64(n)),
-            calldata
-                .iter()
-                .map(|n| GoldilocksField::from_canonical_u64(*n))
-                .collect(),
-            &mut BatchCacheManager::default(),
-            false,
-        );
+        let abi: Abi = match abi_path {
+            Some(path) => {
+                let abi_file = File::open(path).expect("failed to open ABI file");
+                serde_json::from_reader(abi_file)?
+            }
+            None if raw_calldata.is_some() => serde_json::from_str("[]")?,
+            None => anyhow::bail!("an ABI file is needed unless --raw-calldata is given"),
+        };
+        let (func, calldata) = match raw_calldata {
+            Some(calldata) => (None, calldata),
+            None => {
+                let function_sig_name = arg_iter.next().expect("function signature needed");
+                let (func, calldata) =
+                    encode_call(&abi, function_sig_name.as_str(), arg_iter.collect())?;
+                (Some(func), calldata)
+            }
+        };
+        let formatters = match &self.formatter {
+            Some(spec) => OutputFormatters::parse(spec)?,
+            None => OutputFormatters::default(),
+        };
+
+        // Both open the state database read-only, a dry run also allows the
+        // sstores a call rejects.
+        let mut vm = if self.dry_run {
+            OlaVM::new(
+                tree_db_path_buf.as_path(),
+                state_db_path_buf.as_path(),
+                tx_init_info,
+            )
+        } else {
+            OlaVM::new_call(
+                tree_db_path_buf.as_path(),
+                state_db_path_buf.as_path(),
+                tx_init_info,
+            )
+        };
+        if let Some(path) = self.prophet {
+            let mock: ProphetMock = serde_json::from_reader(File::open(path)?)?;
+            vm.prophet_mock = Some(mock);
+        }
+        let started = Instant::now();
+        let exec_res = if self.breakpoints.is_empty() {
+            vm.execute_tx(
+                to.map(|n| GoldilocksField::from_canonical_u64(n)),
+                code.map(|n| GoldilocksField::from_canonical_u64(n)),
+                calldata
+                    .iter()
+                    .map(|n| GoldilocksField::from_canonical_u64(*n))
+                    .collect(),
+                &mut BatchCacheManager::default(),
+                false,
+            )
+            .map_err(|e| anyhow::anyhow!("{}", e))
+        } else {
+            execute_with_conditional_breakpoints(
+                &mut vm,
+                to,
+                code,
+                &calldata,
+                &self.breakpoints,
+                print_paused,
+            )
+        };
+        let wall_clock = started.elapsed();
 
         match exec_res {
             Ok(_) => {
+                if let Some(path) = self.record {
+                    let recording = Recording::capture(&vm, to, code, calldata.clone());
+                    serde_json::to_writer_pretty(File::create(path)?, &recording)?;
+                }
+                if let Some(path) = self.coverage {
+                    serde_json::to_writer_pretty(File::create(path)?, &tx_coverage(&vm))?;
+                }
+                if let Some(format) = self.events_format {
+                    write_events(&mut std::io::stdout(), &abi, &vm.events, format)?;
+                }
+                if let Some(path) = self.metrics {
+                    let metrics = ExecutionMetrics::from_vm(&vm, wall_clock);
+                    write_metrics(&metrics, path.as_path())?;
+                }
+                if self.dry_run {
+                    eprintln!(
+                        "Dry run: {} storage writes were not persisted",
+                        pending_writes(&vm)
+                    );
+                }
+                // Taken before the witness drains the traces.
+                let stats = format!(
+                    "Steps: {}, trace length: {}",
+                    vm.executed_steps(),
+                    vm.trace_length()
+                );
+                if self.stats {
+                    println!("{}", stats);
+                } else {
+                    eprintln!("{}", stats);
+                }
+                if self.mem_stats {
+                    println!("{}", serde_json::to_string(&vm.memory_footprint)?);
+                }
+                if let Some(path) = self.witness {
+                    write_witness(&mut vm, path.as_path())?;
+                }
                 let ret_data = vm.ola_state.return_data;
                 let u64_ret: Vec<u64> = ret_data.iter().map(|fe| fe.0).collect();
-                let decoded = abi
-                    .decode_output_from_slice(func.signature().as_str(), &u64_ret)
-                    .unwrap();
-                println!("Return data:");
-                for dp in decoded.1.reader().by_index {
-                    let value = FromValue::parse_input(dp.value.clone());
-                    println!("{}", value);
+                // Raw calldata comes without a function signature to decode
+                // the return data with.
+                let func = match func {
+                    Some(func) => func,
+                    None => {
+                        match self.output {
+                            OutputFormat::Text => {
+                                println!("Return data:");
+                                for felt in u64_ret.iter() {
+                                    println!("{}", felt);
+                                }
+                            }
+                            OutputFormat::Json => println!("{}", json!(u64_ret)),
+                        }
+                        return Ok(());
+                    }
+                };
+                let decoded = decode_return_values(&abi, func, &u64_ret)?;
+                let max_elements = if self.full_output {
+                    None
+                } else {
+                    Some(self.max_output_elements)
+                };
+                match self.output {
+                    OutputFormat::Text => {
+                        println!("Return data:");
+                        for (param, value) in func.outputs.iter().zip(decoded.iter()) {
+                            println!("{}", formatters.format(param, value.clone(), max_elements));
+                        }
+                    }
+                    OutputFormat::Json => println!("{}", outputs_json(func, &decoded)),
+                }
+                if !self.expect.is_empty() {
+                    let values: Vec<String> =
+                        decoded.into_iter().map(FromValue::parse_input).collect();
+                    let mismatches = expectation_mismatches(func, &values, self.expect);
+                    for mismatch in mismatches.iter() {
+                        eprintln!("{}", mismatch);
+                    }
+                    if !mismatches.is_empty() {
+                        anyhow::bail!("return data does not match the expectation")
+                    }
                 }
             }
+            // A reverted call never matches an expectation.
+            Err(e) if !self.expect.is_empty() => {
+                anyhow::bail!("call failed, return data was expected: {}", e)
+            }
             Err(e) => {
                 eprintln!("Invoke TX Error: {}", e)
             }
@@ -151,3 +744,435 @@ impl Call {
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::{
+        collections::BTreeSet,
+        fs::File,
+        path::{Path, PathBuf},
+    };
+
+    use ola_lang_abi::Abi;
+    use zk_vm::OlaVM;
+
+    use super::{encode_call, execute_with_breakpoints};
+    use crate::{
+        preset::TxContextPreset,
+        subcommands::deploy::deploy_program,
+        utils::{address_from_hex_be, address_to_u64_array},
+    };
+
+    /// Address the test contracts are deployed at.
+    pub(crate) const TEST_ADDRESS: &str = "0x0123";
+
+    /// Deploys the executor test contract `contract`, e.g. "books", at
+    /// `TEST_ADDRESS` in a database named after `name` and returns the
+    /// database home with the contract ABI.
+    pub(crate) fn deploy_test_contract(name: &str, contract: &str) -> (PathBuf, Abi) {
+        let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../executor/test");
+        let db_home =
+            std::env::temp_dir().join(format!("mini_ola_{}_{}", name, std::process::id()));
+        deploy_program(
+            db_home.as_path(),
+            test_dir
+                .join(format!("contracts/{}_bin.json", contract))
+                .as_path(),
+            address_from_hex_be(TEST_ADDRESS).unwrap(),
+        )
+        .unwrap();
+        let abi = serde_json::from_reader(
+            File::open(test_dir.join(format!("contracts-abi/{}_abi.json", contract))).unwrap(),
+        )
+        .unwrap();
+        (db_home, abi)
+    }
+
+    /// Opens a VM on the database at `db_home` with the default tx context.
+    pub(crate) fn call_vm(db_home: &Path) -> OlaVM {
+        OlaVM::new_call(
+            db_home.join("tree").as_path(),
+            db_home.join("state").as_path(),
+            TxContextPreset::default().tx_ctx_info(0).unwrap(),
+        )
+    }
+
+    /// Opens a VM on the database at `db_home` that, like `call --dry-run`,
+    /// also runs the sstores and logs a call rejects.
+    pub(crate) fn dry_run_vm(db_home: &Path) -> OlaVM {
+        OlaVM::new(
+            db_home.join("tree").as_path(),
+            db_home.join("state").as_path(),
+            TxContextPreset::default().tx_ctx_info(0).unwrap(),
+        )
+    }
+
+    /// Deploys `contract` like `deploy_test_contract` and calls `function`
+    /// on it without breakpoints, returning the database home with the VM
+    /// that executed the call.
+    pub(crate) fn deploy_and_call(
+        name: &str,
+        contract: &str,
+        function: &str,
+        args: &[&str],
+    ) -> (PathBuf, OlaVM) {
+        let (db_home, abi) = deploy_test_contract(name, contract);
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let (_, calldata) = encode_call(&abi, function, args).unwrap();
+        let mut vm = call_vm(db_home.as_path());
+        let address = address_to_u64_array(TEST_ADDRESS).unwrap();
+        execute_with_breakpoints(
+            &mut vm,
+            address,
+            address,
+            &calldata,
+            BTreeSet::new(),
+            |_| {},
+        )
+        .unwrap();
+        (db_home, vm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::trace::trace::Trace;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        fs::File,
+        path::PathBuf,
+        time::Instant,
+    };
+
+    use ola_lang_abi::{Abi, Type, Value};
+    use zk_vm::OlaVM;
+
+    use super::test_utils::{
+        call_vm, deploy_and_call, deploy_test_contract, dry_run_vm, TEST_ADDRESS,
+    };
+    use super::{
+        encode_call, execute_calls, execute_with_breakpoints, execute_with_conditional_breakpoints,
+        expectation_mismatches, format_output, outputs_json, parse_raw_calldata, pending_writes,
+        write_witness,
+    };
+    use crate::events::{write_events, EventsFormat};
+    use crate::metrics::{write_metrics, ExecutionMetrics};
+    use crate::subcommands::parser::{FromValue, ToValue};
+    use crate::{
+        breakpoint::Breakpoint, subcommands::batch::BatchCall, utils::address_to_u64_array,
+    };
+
+    // Moves nothing between two accounts of a fresh erc20, which passes the
+    // allowance and balance checks and logs a Transfer and an Approval.
+    fn dry_run_transfer_from(name: &str) -> (PathBuf, Abi, OlaVM) {
+        let (db_home, abi) = deploy_test_contract(name, "erc20");
+        let args = ["0x0a", "0x0b", "0"].map(String::from).to_vec();
+        let (_, calldata) = encode_call(&abi, "transferFrom", args).unwrap();
+        let mut vm = dry_run_vm(db_home.as_path());
+        let address = address_to_u64_array(TEST_ADDRESS).unwrap();
+        execute_with_breakpoints(
+            &mut vm,
+            address,
+            address,
+            &calldata,
+            BTreeSet::new(),
+            |_| {},
+        )
+        .unwrap();
+        (db_home, abi, vm)
+    }
+
+    #[test]
+    fn test_pause_at_breakpoint() {
+        let (db_home, abi) = deploy_test_contract("breakpoint", "books");
+        let (_, calldata) =
+            encode_call(&abi, "createBook", vec!["7".to_string(), "ola".to_string()]).unwrap();
+        let mut vm = call_vm(db_home.as_path());
+
+        let mut pauses = Vec::new();
+        execute_with_breakpoints(
+            &mut vm,
+            address_to_u64_array(TEST_ADDRESS).unwrap(),
+            address_to_u64_array(TEST_ADDRESS).unwrap(),
+            &calldata,
+            [0].into_iter().collect(),
+            |tx| {
+                assert_eq!(tx.registers().len(), 10);
+                pauses.push(tx.pc());
+            },
+        )
+        .unwrap();
+        assert_eq!(pauses.first(), Some(&0));
+        assert!(!vm.ola_state.return_data.is_empty());
+        assert!(vm.memory_footprint.slots_touched > 0);
+        assert!(vm.memory_footprint.max_stack_addr.is_some());
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_conditional_breakpoint() {
+        let (db_home, abi) = deploy_test_contract("cond_break", "books");
+        let (_, calldata) =
+            encode_call(&abi, "createBook", vec!["7".to_string(), "ola".to_string()]).unwrap();
+        for (condition, expected) in [("r0 == 0", 1), ("r0 != 0", 0)] {
+            let mut vm = call_vm(db_home.as_path());
+            let breakpoint: Breakpoint = format!("0 if {}", condition).parse().unwrap();
+            let mut pauses = 0;
+            execute_with_conditional_breakpoints(
+                &mut vm,
+                address_to_u64_array(TEST_ADDRESS).unwrap(),
+                address_to_u64_array(TEST_ADDRESS).unwrap(),
+                &calldata,
+                &[breakpoint],
+                |_| pauses += 1,
+            )
+            .unwrap();
+            assert_eq!(pauses, expected, "condition {}", condition);
+        }
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_export_witness() {
+        let (db_home, mut vm) = deploy_and_call("witness", "books", "createBook", &["7", "ola"]);
+        let path = db_home.join("witness.json");
+        write_witness(&mut vm, path.as_path()).unwrap();
+        let witness: Trace = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert!(!witness.exec.is_empty());
+        assert!(!witness.memory.is_empty());
+        assert_eq!(witness.exec[0].clk, 0);
+        assert!(write_witness(&mut vm, path.as_path()).is_err());
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_export_metrics() {
+        let started = Instant::now();
+        let (db_home, _, vm) = dry_run_transfer_from("metrics");
+        let path = db_home.join("metrics.prom");
+        let metrics = ExecutionMetrics::from_vm(&vm, started.elapsed());
+        write_metrics(&metrics, path.as_path()).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        let samples: BTreeMap<&str, f64> = exported
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, value)| (name, value.parse().unwrap()))
+            .collect();
+        assert!(samples["ola_call_steps"] > 0.0);
+        assert!(samples["ola_call_memory_max_stack_addr"] > 0.0);
+        assert!(samples["ola_call_memory_slots_touched"] > 0.0);
+        assert_eq!(samples["ola_call_max_call_depth"], 1.0);
+        assert_eq!(samples["ola_call_events"], 2.0);
+        assert!(samples["ola_call_wall_clock_seconds"] < 60.0);
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_events_as_ndjson() {
+        let (db_home, abi, vm) = dry_run_transfer_from("events");
+        let mut out = Vec::new();
+        write_events(&mut out, &abi, &vm.events, EventsFormat::Ndjson).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "Transfer");
+        assert_eq!(lines[1]["event"], "Approval");
+        assert!(lines
+            .iter()
+            .all(|line| line["address"].as_str().unwrap().ends_with("0123")));
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_expected_return_data() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "name": "get",
+                    "type": "function",
+                    "inputs": [],
+                    "outputs": [
+                        { "name": "", "type": "u32" },
+                        { "name": "", "type": "field[]" }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let func = &abi.functions[0];
+        let actual: Vec<String> = func
+            .outputs
+            .iter()
+            .zip(["7", "[1,2]"])
+            .map(|(p, v)| FromValue::parse_input(ToValue::parse_input(p.clone(), v.to_string())))
+            .collect();
+
+        let expected = vec!["7".to_string(), "[1,2]".to_string()];
+        assert!(expectation_mismatches(func, &actual, expected).is_empty());
+
+        let expected = vec!["7".to_string(), "[1,3]".to_string()];
+        let mismatches = expectation_mismatches(func, &actual, expected);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].starts_with("output 1:"));
+
+        let mismatches = expectation_mismatches(func, &actual, vec!["7".to_string()]);
+        assert_eq!(mismatches.len(), 1);
+    }
+
+    #[test]
+    fn test_outputs_as_json() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "name": "get",
+                    "type": "function",
+                    "inputs": [],
+                    "outputs": [
+                        { "name": "count", "type": "u32" },
+                        { "name": "", "type": "field[]" }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let values = vec![
+            Value::U32(7),
+            Value::Array(vec![Value::Field(1), Value::Field(2)], Type::Field),
+        ];
+        let outputs = outputs_json(&abi.functions[0], &values);
+        assert_eq!(outputs.as_array().unwrap().len(), 2);
+        assert_eq!(outputs[0]["name"], "count");
+        assert_eq!(outputs[0]["type"], "u32");
+        assert_eq!(outputs[0]["value"], "7");
+        assert_eq!(outputs[1]["name"], "1");
+        assert_eq!(outputs[1]["type"], "field[]");
+        assert_eq!(outputs[1]["value"], "[1,2]");
+    }
+
+    #[test]
+    fn test_parse_raw_calldata() {
+        assert_eq!(parse_raw_calldata("7, 1,2").unwrap(), vec![7, 1, 2]);
+        assert!(parse_raw_calldata("").unwrap().is_empty());
+        assert!(parse_raw_calldata("1,0x2").is_err());
+    }
+
+    #[test]
+    fn test_call_with_raw_calldata() {
+        let (db_home, abi) = deploy_test_contract("raw_calldata", "books");
+        let (_, encoded) =
+            encode_call(&abi, "createBook", vec!["7".to_string(), "ola".to_string()]).unwrap();
+        let raw: Vec<String> = encoded.iter().map(u64::to_string).collect();
+        let calldata = parse_raw_calldata(&raw.join(",")).unwrap();
+        assert_eq!(calldata, encoded);
+
+        let mut vm = call_vm(db_home.as_path());
+        execute_with_breakpoints(
+            &mut vm,
+            address_to_u64_array(TEST_ADDRESS).unwrap(),
+            address_to_u64_array(TEST_ADDRESS).unwrap(),
+            &calldata,
+            BTreeSet::new(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(!vm.ola_state.return_data.is_empty());
+        assert!(vm.executed_steps() > 0);
+        assert!(vm.trace_length() as u64 >= vm.executed_steps());
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_dry_run_does_not_persist() {
+        let (db_home, abi) = deploy_test_contract("dry_run", "erc20");
+        let (_, calldata) = encode_call(
+            &abi,
+            "approve",
+            vec!["0x0456".to_string(), "10".to_string()],
+        )
+        .unwrap();
+
+        // Both runs start from the same state, the first run's writes were
+        // never persisted.
+        let mut pre_values = Vec::new();
+        for _ in 0..2 {
+            let mut vm = dry_run_vm(db_home.as_path());
+            execute_with_breakpoints(
+                &mut vm,
+                address_to_u64_array(TEST_ADDRESS).unwrap(),
+                address_to_u64_array(TEST_ADDRESS).unwrap(),
+                &calldata,
+                BTreeSet::new(),
+                |_| {},
+            )
+            .unwrap();
+            assert!(pending_writes(&vm) > 0);
+            let writes: Vec<_> = vm
+                .ola_state
+                .storage_queries
+                .iter()
+                .map(|query| query.pre_value)
+                .collect();
+            pre_values.push(writes);
+        }
+        assert_eq!(pre_values[0], pre_values[1]);
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_truncate_long_array_output() {
+        let long = Value::Array((1..=100).map(Value::Field).collect(), Type::Field);
+        assert_eq!(
+            format_output(long.clone(), Some(3)),
+            "[1,2,3,...] (3 of 100 elements)"
+        );
+        assert_eq!(format_output(long.clone(), None).matches(',').count(), 99);
+
+        let short = Value::Array(vec![Value::Field(1), Value::Field(2)], Type::Field);
+        assert_eq!(format_output(short, Some(3)), "[1,2]");
+    }
+
+    #[test]
+    fn test_execute_calls_in_order() {
+        let (db_home, abi) = deploy_test_contract("calls_file", "books");
+        let mut vm = call_vm(db_home.as_path());
+        let call = |args: &[&str]| BatchCall {
+            caller: None,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+        let calls = vec![
+            call(&[TEST_ADDRESS, "createBook", "7", "ola"]),
+            call(&[TEST_ADDRESS, "createBook", "8", "vm"]),
+        ];
+        let returns = execute_calls(&mut vm, &abi, calls).unwrap();
+        assert_eq!(returns.len(), 2);
+        assert!(returns[0][0].contains("ola"));
+        assert!(returns[1][0].contains("vm"));
+        assert!(execute_calls(&mut vm, &abi, vec![call(&[TEST_ADDRESS])]).is_err());
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+
+    #[test]
+    fn test_call_with_code_address() {
+        let (db_home, abi) = deploy_test_contract("code_address", "books");
+        let (_, calldata) =
+            encode_call(&abi, "createBook", vec!["7".to_string(), "ola".to_string()]).unwrap();
+
+        // Nothing is deployed at 0x0456, its call runs the code of 0x0123.
+        let mut vm = call_vm(db_home.as_path());
+        execute_with_breakpoints(
+            &mut vm,
+            address_to_u64_array("0x0456").unwrap(),
+            address_to_u64_array(TEST_ADDRESS).unwrap(),
+            &calldata,
+            BTreeSet::new(),
+            |_| {},
+        )
+        .unwrap();
+        assert!(!vm.ola_state.return_data.is_empty());
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+}
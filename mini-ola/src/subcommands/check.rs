@@ -0,0 +1,122 @@
+use std::{
+    fs, panic,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use interpreter::{
+    parser::{node::Node, Parser as SourceParser},
+    sema::{SemaOptions, SymTableGen, TypeDump},
+};
+
+use crate::utils::ExpandedPathbufParser;
+
+#[derive(Debug, Parser)]
+pub struct Check {
+    #[clap(
+        long,
+        help = "Print nothing on success, only diagnostics and a nonzero exit on failure"
+    )]
+    quiet: bool,
+    #[clap(
+        long,
+        default_value = "off",
+        help = "Print the inferred type of every expression as text or json"
+    )]
+    dump_types: TypeDump,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the source file"
+    )]
+    source: PathBuf,
+}
+
+/// Outcome of a successful check.
+#[derive(Debug)]
+pub struct CheckReport {
+    pub warnings: Vec<String>,
+    /// Inferred expression types, empty unless a dump was asked for.
+    pub types: String,
+    pub parse_time: Duration,
+    pub sema_time: Duration,
+}
+
+/// Lexes, parses and analyzes `source` without running it, dumping the
+/// inferred expression types in the `dump_types` format.
+pub fn check_source(source: &str, dump_types: TypeDump) -> anyhow::Result<CheckReport> {
+    let start = Instant::now();
+    // The parser panics on syntax errors, report them as diagnostics instead.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let root = panic::catch_unwind(|| SourceParser::new(source).parse());
+    panic::set_hook(hook);
+    let root = root.map_err(|err| {
+        let message = err
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| err.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "unknown syntax error".to_string());
+        anyhow::anyhow!("syntax error: {}", message)
+    })?;
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let options = SemaOptions {
+        dump_types,
+        ..Default::default()
+    };
+    let mut gen = SymTableGen::new_standalone_with_options(options);
+    root.write()
+        .unwrap()
+        .traverse(&mut gen)
+        .map_err(|err| anyhow::anyhow!("{}", err.diagnostic()))?;
+    Ok(CheckReport {
+        types: gen.dump_types(dump_types),
+        warnings: gen.warnings,
+        parse_time,
+        sema_time: start.elapsed(),
+    })
+}
+
+impl Check {
+    pub fn run(self) -> anyhow::Result<()> {
+        let source = fs::read_to_string(&self.source)?;
+        let report = check_source(&source, self.dump_types)
+            .map_err(|err| anyhow::anyhow!("{}: {}", self.source.display(), err))?;
+        if !report.types.is_empty() {
+            println!("{}", report.types);
+        }
+        if self.quiet {
+            return Ok(());
+        }
+        for warning in report.warnings.iter() {
+            println!("warning: {}", warning);
+        }
+        println!(
+            "{}: ok (parse {:?}, sema {:?})",
+            self.source.display(),
+            report.parse_time,
+            report.sema_time
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use interpreter::sema::TypeDump;
+
+    use super::check_source;
+
+    #[test]
+    fn test_dump_types() {
+        let source = "entry() {\n    i32 a;\n    i32 b;\n    a = 1;\n    b = a * 2;\n}";
+        let report = check_source(source, TypeDump::Text).unwrap();
+        assert!(report.types.contains("5:9: a * 2: i32"));
+        assert!(check_source(source, TypeDump::Off)
+            .unwrap()
+            .types
+            .is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use ola_lang_abi::{Abi, Function};
+
+use crate::{
+    events::{read_events, write_events, EventsFormat},
+    subcommands::call::{decode_return, parse_raw_calldata},
+    utils::ExpandedPathbufParser,
+};
+
+#[derive(Debug, Parser)]
+pub struct Decode {
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the JSON ABI"
+    )]
+    abi: PathBuf,
+    #[clap(
+        required_unless_present = "events",
+        help = "Name or signature of the function that returned the data"
+    )]
+    function: Option<String>,
+    #[clap(
+        required_unless_present = "events",
+        help = "Comma-separated u64 return felts, e.g. 1,2,3"
+    )]
+    return_data: Option<String>,
+    #[clap(
+        long,
+        value_parser = ExpandedPathbufParser,
+        conflicts_with_all = ["function", "return_data"],
+        help = "Decode the events of a JSON array of {address, topics, data} instead"
+    )]
+    events: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value = "ndjson",
+        help = "Print the decoded events as text or ndjson, one event per line"
+    )]
+    events_format: EventsFormat,
+}
+
+/// Finds a function of `abi` by its name or its full signature, e.g.
+/// `balanceOf` or `balanceOf(address)`.
+pub fn find_function<'a>(abi: &'a Abi, function: &str) -> anyhow::Result<&'a Function> {
+    match abi
+        .functions
+        .iter()
+        .find(|func| func.name == function || func.signature() == function)
+    {
+        Some(func) => Ok(func),
+        None => anyhow::bail!("function {} not found", function),
+    }
+}
+
+impl Decode {
+    pub fn run(self) -> anyhow::Result<()> {
+        let abi_file = File::open(self.abi).expect("failed to open ABI file");
+        let abi: Abi = serde_json::from_reader(abi_file)?;
+        if let Some(path) = self.events {
+            let events = read_events(path.as_path())?;
+            return write_events(&mut std::io::stdout(), &abi, &events, self.events_format);
+        }
+        // Both are required without `--events`.
+        let (function, return_data) = (self.function.unwrap(), self.return_data.unwrap());
+        let func = find_function(&abi, function.as_str())?;
+        let ret = parse_raw_calldata(return_data.as_str())?;
+        println!("Return data:");
+        for value in decode_return(&abi, func, &ret)? {
+            println!("{}", value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::Abi;
+
+    use super::find_function;
+    use crate::subcommands::call::{decode_return, parse_raw_calldata};
+
+    #[test]
+    fn test_decode_return_data() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "name": "stats",
+                    "type": "function",
+                    "inputs": [{ "name": "a", "type": "field" }],
+                    "outputs": [
+                        { "name": "total", "type": "field" },
+                        { "name": "count", "type": "u32" }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let func = find_function(&abi, "stats").unwrap();
+        assert_eq!(
+            find_function(&abi, "stats(field)").unwrap().signature(),
+            func.signature()
+        );
+        assert!(find_function(&abi, "missing").is_err());
+
+        let ret = parse_raw_calldata("5,7").unwrap();
+        assert_eq!(decode_return(&abi, func, &ret).unwrap(), vec!["5", "7"]);
+    }
+}
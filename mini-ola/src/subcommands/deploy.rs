@@ -1,10 +1,14 @@
 use core::{
+    crypto::poseidon_trace::calculate_arbitrary_poseidon_u64s,
     program::binary_program::BinaryProgram,
     state::utils::get_prog_hash_cf_key_from_contract_addr,
     storage::db::{Database, RocksDB, SequencerColumnFamily},
     types::{storage::u8_arr_to_field_arr, Field, GoldilocksField},
 };
-use std::{fs::File, path::PathBuf};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Ok;
 use clap::Parser;
@@ -12,7 +16,9 @@ use plonky2::hash::utils::poseidon_hash_bytes;
 use rand::{thread_rng, Rng};
 use rocksdb::WriteBatch;
 
-use crate::utils::{address_from_hex_be, ExpandedPathbufParser};
+use crate::utils::{
+    address_from_hex_be, address_to_u64_array, u64_array_to_h256, ExpandedPathbufParser,
+};
 
 #[derive(Debug, Parser)]
 pub struct Deploy {
@@ -20,6 +26,17 @@ pub struct Deploy {
     db: Option<PathBuf>,
     #[clap(long, help = "Address you want to deploy")]
     address: Option<String>,
+    #[clap(
+        long,
+        help = "Deployer address the contract address is derived from, zero when only the \
+                salt is given"
+    )]
+    deployer: Option<String>,
+    #[clap(
+        long,
+        help = "Salt the contract address is derived from, zero when only the deployer is given"
+    )]
+    salt: Option<String>,
     #[clap(
         value_parser = ExpandedPathbufParser,
         help = "Path to contract binary file"
@@ -27,51 +44,118 @@ pub struct Deploy {
     contract: PathBuf,
 }
 
+fn load_program(contract: &Path) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let program: BinaryProgram = serde_json::from_reader(File::open(contract)?)?;
+    let program_bytes = bincode::serialize(&program)?;
+    let program_hash = poseidon_hash_bytes(program_bytes.as_ref()).to_vec();
+    Ok((program_bytes, program_hash))
+}
+
+/// Hash the program of a contract binary file is stored under.
+pub fn bytecode_hash(contract: &Path) -> anyhow::Result<[u64; 4]> {
+    let (_, program_hash) = load_program(contract)?;
+    let mut hash = [0; 4];
+    for (word, fe) in hash.iter_mut().zip(u8_arr_to_field_arr(&program_hash)) {
+        *word = fe.0;
+    }
+    Ok(hash)
+}
+
+/// Address the ContractDeployer system contract deploys to, as computed by
+/// its `getNewAddressCreate2`: the poseidon hash of the hashed "OlaCreate2"
+/// prefix, the deployer, the salt and the bytecode hash.
+pub fn create2_address(deployer: [u64; 4], salt: [u64; 4], bytecode_hash: [u64; 4]) -> [u64; 4] {
+    let prefix: Vec<u64> = "OlaCreate2".bytes().map(|b| b as u64).collect();
+    let prefix = calculate_arbitrary_poseidon_u64s(&prefix);
+    calculate_arbitrary_poseidon_u64s(&[prefix, deployer, salt, bytecode_hash].concat())
+}
+
+/// Stores the program of a contract binary file under `target_address` in
+/// the state db.
+pub fn deploy_program(
+    db_home: &Path,
+    contract: &Path,
+    target_address: [u8; 32],
+) -> anyhow::Result<()> {
+    let (program_bytes, program_hash) = load_program(contract)?;
+
+    let state_db_path = db_home.join("state");
+    let state_db = RocksDB::new(Database::Sequencer, state_db_path.as_path(), false);
+
+    let addr_fes = u8_arr_to_field_arr(&target_address.to_vec());
+    let mut addr_key = [GoldilocksField::ZERO; 4];
+    addr_key.clone_from_slice(&addr_fes[..4]);
+    let cf = state_db.cf_sequencer_handle(SequencerColumnFamily::State);
+    let addr_key = get_prog_hash_cf_key_from_contract_addr(&addr_key).unwrap();
+    let mut batch = WriteBatch::default();
+    batch.put_cf(cf, &addr_key, &program_hash);
+    state_db
+        .write(batch)
+        .map_err(|e| anyhow::anyhow!("DB write error: {}", e))?;
+
+    let cf = state_db.cf_sequencer_handle(SequencerColumnFamily::FactoryDeps);
+    let mut batch = WriteBatch::default();
+    batch.put_cf(cf, &program_hash, &program_bytes);
+    state_db
+        .write(batch)
+        .map_err(|e| anyhow::anyhow!("DB write error: {}", e))?;
+    Ok(())
+}
+
 impl Deploy {
     pub fn run(self) -> anyhow::Result<()> {
-        let program: BinaryProgram = serde_json::from_reader(File::open(self.contract)?)?;
-        let program_bytes = bincode::serialize(&program)?;
-        let program_hash = poseidon_hash_bytes(program_bytes.as_ref()).to_vec();
-
         let target_address: [u8; 32] = if let Some(addr) = self.address {
             address_from_hex_be(addr.as_str()).unwrap()
-        } else {
+        } else if self.deployer.is_none() && self.salt.is_none() {
             let mut rng = thread_rng();
             let mut bytes = [0u8; 32];
             rng.fill(&mut bytes);
             bytes
+        } else {
+            let deployer = match self.deployer {
+                Some(addr) => address_to_u64_array(addr.as_str())?,
+                None => [0; 4],
+            };
+            let salt = match self.salt {
+                Some(salt) => address_to_u64_array(salt.as_str())?,
+                None => [0; 4],
+            };
+            let bytecode_hash = bytecode_hash(self.contract.as_path())?;
+            u64_array_to_h256(&create2_address(deployer, salt, bytecode_hash)).0
         };
 
         let db_home = match self.db {
             Some(path) => path,
             None => PathBuf::from("./db"),
         };
-        let state_db_path = db_home.join("state");
-        let state_db = RocksDB::new(Database::Sequencer, state_db_path.as_path(), false);
-
-        let addr_fes = u8_arr_to_field_arr(&target_address.to_vec());
-        let mut addr_key = [GoldilocksField::ZERO; 4];
-        addr_key.clone_from_slice(&addr_fes[..4]);
-        let cf = state_db.cf_sequencer_handle(SequencerColumnFamily::State);
-        let addr_key = get_prog_hash_cf_key_from_contract_addr(&addr_key).unwrap();
-        let mut batch = WriteBatch::default();
-        batch.put_cf(cf, &addr_key, &program_hash);
-        let db_write = state_db.write(batch);
-        if db_write.is_err() {
-            eprintln!("DB write error.");
-            return Ok(());
-        }
-
-        let cf = state_db.cf_sequencer_handle(SequencerColumnFamily::FactoryDeps);
-        let mut batch = WriteBatch::default();
-        batch.put_cf(cf, &program_hash, &program_bytes);
-        let db_write = state_db.write(batch);
-        if db_write.is_err() {
-            eprintln!("DB write error.");
-            return Ok(());
-        }
+        deploy_program(db_home.as_path(), self.contract.as_path(), target_address)?;
         let target_address = hex::encode(target_address);
         println!("Deploy success at address: 0x{}", target_address);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::create2_address;
+
+    #[test]
+    fn test_create2_address() {
+        let deployer = [0, 0, 0, 32773];
+        let salt = [0, 0, 0, 1];
+        let address = create2_address(deployer, salt, [1, 2, 3, 4]);
+        assert_eq!(
+            address,
+            [
+                857201634586301737,
+                13468634765753361445,
+                18426656605621208167,
+                7293653122728185548
+            ]
+        );
+        assert_ne!(
+            address,
+            create2_address(deployer, [0, 0, 0, 2], [1, 2, 3, 4])
+        );
+    }
+}
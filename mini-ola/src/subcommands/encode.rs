@@ -0,0 +1,72 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use ola_lang_abi::Abi;
+
+use crate::{subcommands::call::encode_call, utils::ExpandedPathbufParser};
+
+#[derive(Debug, Parser)]
+pub struct Encode {
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the JSON ABI"
+    )]
+    abi: PathBuf,
+    #[clap(help = "Name of the function")]
+    function: String,
+    #[clap(help = "Arguments of the function, in the same format as for call")]
+    args: Vec<String>,
+}
+
+/// Formats calldata as comma-separated u64s, the format `--raw-calldata`
+/// reads.
+pub fn format_calldata(calldata: &[u64]) -> String {
+    calldata
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Encode {
+    pub fn run(self) -> anyhow::Result<()> {
+        let abi_file = File::open(self.abi).expect("failed to open ABI file");
+        let abi: Abi = serde_json::from_reader(abi_file)?;
+        let (_, calldata) = encode_call(&abi, self.function.as_str(), self.args)?;
+        println!("{}", format_calldata(&calldata));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::Abi;
+
+    use super::format_calldata;
+    use crate::subcommands::call::{encode_call, parse_raw_calldata};
+
+    #[test]
+    fn test_encode_calldata() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "name": "add",
+                    "type": "function",
+                    "inputs": [
+                        { "name": "a", "type": "field" },
+                        { "name": "b", "type": "u32" }
+                    ],
+                    "outputs": [{ "name": "", "type": "field" }]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let (_, calldata) =
+            encode_call(&abi, "add", vec!["5".to_string(), "7".to_string()]).unwrap();
+        let formatted = format_calldata(&calldata);
+        // The params, their number of words and the selector.
+        assert!(formatted.starts_with("5,7,2,"));
+        assert_eq!(parse_raw_calldata(&formatted).unwrap(), calldata);
+        assert!(encode_call(&abi, "add", vec!["5".to_string()]).is_err());
+    }
+}
@@ -15,12 +15,9 @@ use clap::Parser;
 use ethereum_types::H256;
 use executor::BatchCacheManager;
 use ola_lang_abi::{Abi, Param, Value};
-use plonky2::hash::utils::bytes_to_u64s;
 use rocksdb::WriteBatch;
 
-use crate::utils::{
-    address_from_hex_be, h256_to_u64_array, ExpandedPathbufParser, OLA_RAW_TX_TYPE,
-};
+use crate::utils::{h256_to_u64_array, parse_address, ExpandedPathbufParser, OLA_RAW_TX_TYPE};
 
 use super::parser::ToValue;
 use zk_vm::OlaVM;
@@ -49,11 +46,7 @@ pub struct Invoke {
 impl Invoke {
     pub fn run(self) -> anyhow::Result<()> {
         let caller_address: [u64; 4] = if let Some(addr) = self.caller {
-            let bytes = address_from_hex_be(addr.as_str()).unwrap();
-            let caller_vec = bytes_to_u64s(&bytes);
-            let mut caller = [0u64; 4];
-            caller.clone_from_slice(&caller_vec[..4]);
-            caller
+            parse_address("caller", addr.as_str())?
         } else {
             h256_to_u64_array(&H256::random())
         };
@@ -77,10 +70,7 @@ impl Invoke {
 
         let mut arg_iter = self.calls.into_iter();
         let contract_address_hex = arg_iter.next().expect("contract address needed");
-        let contract_address_bytes = address_from_hex_be(contract_address_hex.as_str()).unwrap();
-        let to_vec = bytes_to_u64s(&contract_address_bytes);
-        let mut to = [0u64; 4];
-        to.clone_from_slice(&to_vec[..4]);
+        let to = parse_address("contract", contract_address_hex.as_str())?;
 
         let abi_file = File::open(self.abi).expect("failed to open ABI file");
         let function_sig_name = arg_iter.next().expect("function signature needed");
@@ -0,0 +1,207 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use ola_lang_abi::{Abi, Function, Type};
+use serde_derive::Serialize;
+
+use crate::utils::ExpandedPathbufParser;
+
+#[derive(Debug, Parser)]
+pub struct Layout {
+    #[clap(long, help = "Print the layout as JSON")]
+    json: bool,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to the JSON ABI"
+    )]
+    abi: PathBuf,
+    #[clap(help = "Name of the function")]
+    function: String,
+}
+
+/// A run of calldata words. `position` is a word index from the start, or
+/// relative to the end once a variable length param has been laid out.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayoutEntry {
+    pub position: String,
+    pub words: Option<usize>,
+    pub param: String,
+    pub description: String,
+}
+
+pub fn type_name(t: &Type) -> String {
+    match t {
+        Type::U32 => "u32".to_string(),
+        Type::Field => "field".to_string(),
+        Type::Hash => "hash".to_string(),
+        Type::Address => "address".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::String => "string".to_string(),
+        Type::Fields => "fields".to_string(),
+        Type::U256 => "u256".to_string(),
+        Type::FixedArray(t, size) => format!("{}[{}]", type_name(t), size),
+        Type::Array(t) => format!("{}[]", type_name(t)),
+        Type::Tuple(attrs) => {
+            let attrs: Vec<String> = attrs.iter().map(|(_, t)| type_name(t)).collect();
+            format!("({})", attrs.join(","))
+        }
+    }
+}
+
+/// Number of words a value of type `t` takes, `None` for variable length
+/// types.
+pub fn static_words(t: &Type) -> Option<usize> {
+    match t {
+        Type::U32 | Type::Field | Type::Bool => Some(1),
+        Type::Hash | Type::Address => Some(4),
+        Type::U256 => Some(8),
+        Type::FixedArray(t, size) => static_words(t).map(|words| words * *size as usize),
+        Type::Tuple(attrs) => attrs.iter().map(|(_, t)| static_words(t)).sum(),
+        Type::String | Type::Fields | Type::Array(_) => None,
+    }
+}
+
+fn position(offset: Option<usize>, open_ended: bool) -> String {
+    match (offset, open_ended) {
+        (Some(offset), false) => offset.to_string(),
+        (Some(offset), true) => format!("{}..", offset),
+        (None, _) => "..".to_string(),
+    }
+}
+
+/// Lays out the calldata of `func`: the encoded params in order, followed
+/// by the number of param words and the function selector.
+pub fn calldata_layout(func: &Function) -> Vec<LayoutEntry> {
+    let mut entries = Vec::new();
+    let mut offset = Some(0);
+    for param in func.inputs.iter() {
+        let param_type = type_name(&param.type_);
+        let param_name = format!("{}: {}", param.name, param_type);
+        match static_words(&param.type_) {
+            Some(words) => {
+                entries.push(LayoutEntry {
+                    position: position(offset, false),
+                    words: Some(words),
+                    param: param_name,
+                    description: "value".to_string(),
+                });
+                offset = offset.map(|offset| offset + words);
+            }
+            None => {
+                entries.push(LayoutEntry {
+                    position: position(offset, false),
+                    words: Some(1),
+                    param: param_name.clone(),
+                    description: "length prefix".to_string(),
+                });
+                let item = match &param.type_ {
+                    Type::Array(t) => match static_words(t) {
+                        Some(words) => format!("items, {} word(s) each", words),
+                        None => "items, variable length each".to_string(),
+                    },
+                    Type::String => "bytes, one word each".to_string(),
+                    _ => "items, one word each".to_string(),
+                };
+                entries.push(LayoutEntry {
+                    position: position(offset.map(|offset| offset + 1), true),
+                    words: None,
+                    param: param_name,
+                    description: item,
+                });
+                offset = None;
+            }
+        }
+    }
+    let (length_position, selector_position) = match offset {
+        Some(offset) => (offset.to_string(), (offset + 1).to_string()),
+        None => ("end-1".to_string(), "end".to_string()),
+    };
+    entries.push(LayoutEntry {
+        position: length_position,
+        words: Some(1),
+        param: String::new(),
+        description: "number of param words".to_string(),
+    });
+    entries.push(LayoutEntry {
+        position: selector_position,
+        words: Some(1),
+        param: String::new(),
+        description: format!("selector of {}", func.signature()),
+    });
+    entries
+}
+
+impl Layout {
+    pub fn run(self) -> anyhow::Result<()> {
+        let abi_file = File::open(self.abi).expect("failed to open ABI file");
+        let abi: Abi = serde_json::from_reader(abi_file)?;
+        let func = match abi.functions.iter().find(|func| func.name == self.function) {
+            Some(func) => func,
+            None => anyhow::bail!("function {} not found", self.function),
+        };
+        let layout = calldata_layout(func);
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&layout)?);
+        } else {
+            for entry in layout {
+                let words = match entry.words {
+                    Some(words) => words.to_string(),
+                    None => "n".to_string(),
+                };
+                println!(
+                    "{:<8} {:>2} word(s)  {} {}",
+                    entry.position, words, entry.param, entry.description
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::Abi;
+
+    use super::calldata_layout;
+
+    #[test]
+    fn test_felt_and_felt_array_layout() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "name": "sum",
+                    "type": "function",
+                    "inputs": [
+                        { "name": "a", "type": "field" },
+                        { "name": "b", "type": "field[]" }
+                    ],
+                    "outputs": [{ "name": "", "type": "field" }]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let layout = calldata_layout(&abi.functions[0]);
+        let rows: Vec<(&str, Option<usize>, &str, &str)> = layout
+            .iter()
+            .map(|e| {
+                (
+                    e.position.as_str(),
+                    e.words,
+                    e.param.as_str(),
+                    e.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows[..4],
+            [
+                ("0", Some(1), "a: field", "value"),
+                ("1", Some(1), "b: field[]", "length prefix"),
+                ("2..", None, "b: field[]", "items, 1 word(s) each"),
+                ("end-1", Some(1), "", "number of param words"),
+            ]
+        );
+        assert_eq!(layout[4].position, "end");
+        assert!(layout[4].description.starts_with("selector of sum"));
+    }
+}
@@ -1,4 +1,12 @@
+pub mod abi_self_test;
+pub mod batch;
 pub mod call;
+pub mod check;
+pub mod decode;
 pub mod deploy;
+pub mod encode;
 pub mod invoke;
+pub mod layout;
 pub mod parser;
+pub mod replay;
+pub mod serve;
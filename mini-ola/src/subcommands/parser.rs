@@ -22,13 +22,34 @@ impl ToValue {
         parse_result.unwrap()
     }
 
+    /// Parses a number argument, decimal by default. A `:hex` or `:dec`
+    /// suffix overrides the format, e.g. `0x1f:hex`, `1f:hex` or `42:dec`.
+    fn parse_number(input: &str) -> Result<u64> {
+        let value = match input.rsplit_once(':') {
+            Some((value, "hex")) => {
+                let digits = value.trim_start_matches("0x");
+                u64::from_str_radix(digits, 16).ok()
+            }
+            Some((value, "dec")) => value.parse::<u64>().ok(),
+            Some((_, hint)) => bail!("invalid type hint '{}', expected 'hex' or 'dec'", hint),
+            None => input.parse::<u64>().ok(),
+        };
+        match value {
+            Some(value) => Ok(value),
+            None => bail!("invalid number input '{}'", input),
+        }
+    }
+
     fn parse_u32(input: String) -> Result<Value> {
-        let value = input.parse::<u32>().expect("invalid u32 input");
-        Ok(Value::U32(value as u64))
+        let value = Self::parse_number(input.as_str())?;
+        if value > u32::MAX as u64 {
+            bail!("invalid u32 input")
+        }
+        Ok(Value::U32(value))
     }
 
     fn parse_field(input: String) -> Result<Value> {
-        let value = input.parse::<u64>().expect("invalid field element input");
+        let value = Self::parse_number(input.as_str())?;
         if value > OLA_FIELD_ORDER {
             bail!("invalid field element input")
         }
@@ -332,3 +353,34 @@ impl FromValue {
         Ok(str)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ola_lang_abi::{Param, Type, Value};
+
+    use super::ToValue;
+
+    fn felt_param() -> Param {
+        Param {
+            name: "a".to_string(),
+            type_: Type::Field,
+            indexed: None,
+        }
+    }
+
+    #[test]
+    fn test_felt_type_hints() {
+        let parse = |input: &str| ToValue::parse_input(felt_param(), input.to_string());
+        assert!(matches!(parse("31"), Value::Field(31)));
+        assert!(matches!(parse("0x1f:hex"), Value::Field(31)));
+        assert!(matches!(parse("1f:hex"), Value::Field(31)));
+        assert!(matches!(parse("42:dec"), Value::Field(42)));
+
+        let err = ToValue::parse_field("42:oct".to_string()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type hint 'oct', expected 'hex' or 'dec'"
+        );
+        assert!(ToValue::parse_field("0x1f:dec".to_string()).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use core::{
+    types::{Field, GoldilocksField},
+    vm::transaction::TxCtxInfo,
+};
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use executor::BatchCacheManager;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::utils::ExpandedPathbufParser;
+
+use super::batch::{aggregate_storage_changes, SlotChange};
+use zk_vm::OlaVM;
+
+#[derive(Debug, Parser)]
+pub struct Replay {
+    #[clap(long, help = "Path of rocksdb database")]
+    db: Option<PathBuf>,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to a recording written by `call --record`"
+    )]
+    recording: PathBuf,
+}
+
+/// The part of an execution step that is compared on replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedStep {
+    pub env_idx: u64,
+    pub clk: u32,
+    pub pc: u64,
+    pub opcode: u64,
+    pub regs: Vec<u64>,
+}
+
+/// Everything needed to re-execute a call and check that it behaves the same:
+/// the transaction inputs and context, the execution trace of every contract
+/// in call order, the return data and the net storage changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub caller: [u64; 4],
+    pub to: [u64; 4],
+    pub code: [u64; 4],
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub chain_id: u64,
+    pub version: u64,
+    pub nonce: u64,
+    pub sequencer: [u64; 4],
+    pub sig_r: [u64; 4],
+    pub sig_s: [u64; 4],
+    pub tx_hash: [u64; 4],
+    pub calldata: Vec<u64>,
+    pub steps: Vec<RecordedStep>,
+    pub return_data: Vec<u64>,
+    pub changes: Vec<SlotChange>,
+}
+
+impl Recording {
+    /// Builds a recording from a vm that has just executed `calldata` at
+    /// `to` with the code of `code`.
+    pub fn capture(vm: &OlaVM, to: [u64; 4], code: [u64; 4], calldata: Vec<u64>) -> Self {
+        let ctx = &vm.ctx_info;
+        let words = |fes: &[GoldilocksField; 4]| fes.map(|fe| fe.0);
+        let steps = vm
+            .ola_state
+            .txs_trace
+            .values()
+            .flat_map(|trace| trace.exec.iter())
+            .map(|step| RecordedStep {
+                env_idx: step.env_idx.0,
+                clk: step.clk,
+                pc: step.pc,
+                opcode: step.opcode.0,
+                regs: step.regs.iter().map(|fe| fe.0).collect(),
+            })
+            .collect();
+        Recording {
+            caller: words(&ctx.caller_address),
+            to,
+            code,
+            block_number: ctx.block_number.0,
+            block_timestamp: ctx.block_timestamp.0,
+            chain_id: ctx.chain_id.0,
+            version: ctx.version.0,
+            nonce: ctx.nonce.0,
+            sequencer: words(&ctx.sequencer_address),
+            sig_r: words(&ctx.signature_r),
+            sig_s: words(&ctx.signature_s),
+            tx_hash: words(&ctx.tx_hash),
+            calldata,
+            steps,
+            return_data: vm.ola_state.return_data.iter().map(|fe| fe.0).collect(),
+            changes: aggregate_storage_changes(&vm.ola_state.storage_queries),
+        }
+    }
+
+    /// The tx context the recorded call ran with.
+    pub fn tx_ctx_info(&self) -> TxCtxInfo {
+        let fes = |words: [u64; 4]| words.map(|n| GoldilocksField::from_canonical_u64(n));
+        TxCtxInfo {
+            block_number: GoldilocksField::from_canonical_u64(self.block_number),
+            block_timestamp: GoldilocksField::from_canonical_u64(self.block_timestamp),
+            sequencer_address: fes(self.sequencer),
+            version: GoldilocksField::from_canonical_u64(self.version),
+            chain_id: GoldilocksField::from_canonical_u64(self.chain_id),
+            caller_address: fes(self.caller),
+            nonce: GoldilocksField::from_canonical_u64(self.nonce),
+            signature_r: fes(self.sig_r),
+            signature_s: fes(self.sig_s),
+            tx_hash: fes(self.tx_hash),
+        }
+    }
+}
+
+/// Describes where a replay first differs from its recording.
+pub fn first_divergence(expected: &Recording, actual: &Recording) -> Option<String> {
+    let steps = expected.steps.iter().zip(actual.steps.iter()).enumerate();
+    for (index, (want, got)) in steps {
+        if want != got {
+            return Some(format!(
+                "step {} diverges: recorded {:?}, replayed {:?}",
+                index, want, got
+            ));
+        }
+    }
+    if expected.steps.len() != actual.steps.len() {
+        return Some(format!(
+            "step {} diverges: recorded {} steps, replayed {}",
+            expected.steps.len().min(actual.steps.len()),
+            expected.steps.len(),
+            actual.steps.len()
+        ));
+    }
+    if expected.return_data != actual.return_data {
+        return Some(format!(
+            "return data diverges: recorded {:?}, replayed {:?}",
+            expected.return_data, actual.return_data
+        ));
+    }
+    if expected.changes != actual.changes {
+        return Some(format!(
+            "state diff diverges: recorded {:?}, replayed {:?}",
+            expected.changes, actual.changes
+        ));
+    }
+    None
+}
+
+impl Replay {
+    pub fn run(self) -> anyhow::Result<()> {
+        let db_home = match self.db {
+            Some(path) => path,
+            None => PathBuf::from("./db"),
+        };
+        let tree_db_path_buf = db_home.join("tree");
+        let state_db_path_buf = db_home.join("state");
+
+        let recording_file = File::open(self.recording).expect("failed to open recording file");
+        let recording: Recording = serde_json::from_reader(recording_file)?;
+
+        let mut vm = OlaVM::new_call(
+            tree_db_path_buf.as_path(),
+            state_db_path_buf.as_path(),
+            recording.tx_ctx_info(),
+        );
+        vm.execute_tx(
+            recording.to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            recording
+                .code
+                .map(|n| GoldilocksField::from_canonical_u64(n)),
+            recording
+                .calldata
+                .iter()
+                .map(|n| GoldilocksField::from_canonical_u64(*n))
+                .collect(),
+            &mut BatchCacheManager::default(),
+            false,
+        )
+        .map_err(|e| anyhow::anyhow!("Replay TX Error: {}", e))?;
+
+        let replayed = Recording::capture(
+            &vm,
+            recording.to,
+            recording.code,
+            recording.calldata.clone(),
+        );
+        if let Some(divergence) = first_divergence(&recording, &replayed) {
+            anyhow::bail!("replay diverges from recording, {}", divergence)
+        }
+        println!("Replay matches recording, {} steps", replayed.steps.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_divergence, RecordedStep, Recording};
+
+    fn step(clk: u32, opcode: u64) -> RecordedStep {
+        RecordedStep {
+            env_idx: 0,
+            clk,
+            pc: clk as u64,
+            opcode,
+            regs: vec![0; 10],
+        }
+    }
+
+    fn recording(steps: Vec<RecordedStep>) -> Recording {
+        Recording {
+            caller: [1; 4],
+            to: [2; 4],
+            code: [2; 4],
+            block_number: 0,
+            block_timestamp: 0,
+            chain_id: 1027,
+            version: 0,
+            nonce: 0,
+            sequencer: [0; 4],
+            sig_r: [0; 4],
+            sig_s: [0; 4],
+            tx_hash: [0; 4],
+            calldata: vec![3, 1, 7],
+            steps,
+            return_data: vec![7, 1],
+            changes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let expected = recording(vec![step(0, 1), step(1, 2), step(2, 3)]);
+        assert_eq!(first_divergence(&expected, &expected.clone()), None);
+
+        let changed = recording(vec![step(0, 1), step(1, 4), step(2, 3)]);
+        let divergence = first_divergence(&expected, &changed).unwrap();
+        assert!(divergence.starts_with("step 1 diverges"));
+
+        let truncated = recording(vec![step(0, 1), step(1, 2)]);
+        let divergence = first_divergence(&expected, &truncated).unwrap();
+        assert!(divergence.starts_with("step 2 diverges"));
+
+        let mut other_return = expected.clone();
+        other_return.return_data = vec![8, 1];
+        let divergence = first_divergence(&expected, &other_return).unwrap();
+        assert!(divergence.starts_with("return data diverges"));
+    }
+
+    #[test]
+    fn test_recorded_tx_context() {
+        let mut recorded = recording(vec![]);
+        recorded.chain_id = 7;
+        recorded.nonce = 3;
+        recorded.sequencer = [4; 4];
+        recorded.tx_hash = [5; 4];
+        let ctx = recorded.tx_ctx_info();
+        assert_eq!(ctx.chain_id.0, 7);
+        assert_eq!(ctx.nonce.0, 3);
+        assert_eq!(ctx.caller_address.map(|fe| fe.0), [1; 4]);
+        assert_eq!(ctx.sequencer_address.map(|fe| fe.0), [4; 4]);
+        assert_eq!(ctx.tx_hash.map(|fe| fe.0), [5; 4]);
+        assert_eq!(ctx.signature_r.map(|fe| fe.0), [0; 4]);
+    }
+}
@@ -0,0 +1,290 @@
+use core::types::{Field, GoldilocksField};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use clap::Parser;
+use executor::BatchCacheManager;
+use ola_lang_abi::Abi;
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    preset::TxContextPreset,
+    utils::{address_to_u64_array, u64_array_to_h256, ExpandedPathbufParser},
+};
+
+use super::call::{decode_return, encode_call};
+use zk_vm::OlaVM;
+
+#[derive(Debug, Parser)]
+pub struct Serve {
+    #[clap(long, help = "Path of rocksdb database")]
+    db: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value = "127.0.0.1:8545",
+        help = "Address the JSON-RPC server listens on"
+    )]
+    listen: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Params of the `call` method, the same inputs as the `call` subcommand.
+#[derive(Debug, Deserialize)]
+pub struct CallParams {
+    pub abi: PathBuf,
+    pub to: String,
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub caller: Option<String>,
+    pub block: Option<u64>,
+    pub timestamp: Option<u64>,
+}
+
+/// Params of the `query` method, reading a single storage slot.
+#[derive(Debug, Deserialize)]
+pub struct QueryParams {
+    pub address: String,
+    pub slot: String,
+}
+
+/// Serves `call` and `query` against a state db opened once. The db is
+/// opened read only, so requests never write; they still run one at a time
+/// because executing a call mutates the vm.
+pub struct RpcServer {
+    vm: Mutex<OlaVM>,
+}
+
+impl RpcServer {
+    pub fn open(db_home: &Path) -> anyhow::Result<Self> {
+        let tree_db_path_buf = db_home.join("tree");
+        let state_db_path_buf = db_home.join("state");
+        let vm = OlaVM::new_call(
+            tree_db_path_buf.as_path(),
+            state_db_path_buf.as_path(),
+            TxContextPreset::default().tx_ctx_info(0)?,
+        );
+        Ok(RpcServer { vm: Mutex::new(vm) })
+    }
+
+    /// Handles one JSON-RPC request and returns the response object.
+    pub fn handle(&self, request: RpcRequest) -> Value {
+        let result = match request.method.as_str() {
+            "call" => serde_json::from_value(request.params)
+                .map_err(anyhow::Error::from)
+                .and_then(|params| self.call(params)),
+            "query" => serde_json::from_value(request.params)
+                .map_err(anyhow::Error::from)
+                .and_then(|params| self.query(params)),
+            method => Err(anyhow::anyhow!("unknown method {}", method)),
+        };
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": request.id,
+                "error": { "code": -32000, "message": e.to_string() },
+            }),
+        }
+    }
+
+    fn call(&self, params: CallParams) -> anyhow::Result<Value> {
+        let abi: Abi = serde_json::from_reader(File::open(params.abi)?)?;
+        let to = address_to_u64_array(params.to.as_str())?;
+        let (func, calldata) = encode_call(&abi, params.function.as_str(), params.args)?;
+        let ctx = TxContextPreset {
+            caller: params.caller,
+            block: params.block,
+            timestamp: params.timestamp,
+            ..Default::default()
+        };
+
+        let mut vm = self.vm.lock().unwrap();
+        vm.ctx_info = ctx.tx_ctx_info(0)?;
+        vm.process_ctx.clear();
+        vm.ola_state.txs_trace.clear();
+        vm.ola_state.storage_queries.clear();
+        vm.ola_state.return_data.clear();
+        vm.execute_tx(
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            to.map(|n| GoldilocksField::from_canonical_u64(n)),
+            calldata
+                .iter()
+                .map(|n| GoldilocksField::from_canonical_u64(*n))
+                .collect(),
+            &mut BatchCacheManager::default(),
+            false,
+        )
+        .map_err(|e| anyhow::anyhow!("Invoke TX Error: {}", e))?;
+        let u64_ret: Vec<u64> = vm.ola_state.return_data.iter().map(|fe| fe.0).collect();
+        Ok(json!(decode_return(&abi, func, &u64_ret)?))
+    }
+
+    fn query(&self, params: QueryParams) -> anyhow::Result<Value> {
+        let address = address_to_u64_array(params.address.as_str())?;
+        let slot = address_to_u64_array(params.slot.as_str())?;
+        let vm = self.vm.lock().unwrap();
+        let value = vm.ola_state.state_storage.get_storage(
+            &address.map(|n| GoldilocksField::from_canonical_u64(n)),
+            &slot.map(|n| GoldilocksField::from_canonical_u64(n)),
+        )?;
+        let value = value.unwrap_or([GoldilocksField::ZERO; 4]).map(|fe| fe.0);
+        Ok(json!(format!(
+            "0x{}",
+            hex::encode(u64_array_to_h256(&value).0)
+        )))
+    }
+
+    /// Accepts connections until the listener fails, one thread each.
+    pub fn serve(self: Arc<Self>, listener: TcpListener) -> anyhow::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = server.handle_connection(stream) {
+                    eprintln!("JSON-RPC connection error: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> anyhow::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse()?;
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body)?;
+
+        let (status, response) = if !request_line.starts_with("POST ") {
+            (
+                "405 Method Not Allowed",
+                json!({ "error": "only POST is supported" }),
+            )
+        } else {
+            match serde_json::from_slice::<RpcRequest>(&body) {
+                Ok(request) => ("200 OK", self.handle(request)),
+                Err(e) => (
+                    "400 Bad Request",
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": -32700, "message": e.to_string() },
+                    }),
+                ),
+            }
+        };
+        let response = response.to_string();
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            response.len(),
+            response
+        )?;
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+impl Serve {
+    pub fn run(self) -> anyhow::Result<()> {
+        let db_home = match self.db {
+            Some(path) => path,
+            None => PathBuf::from("./db"),
+        };
+        let server = Arc::new(RpcServer::open(db_home.as_path())?);
+        let listener = TcpListener::bind(self.listen.as_str())?;
+        println!("JSON-RPC server listening on {}", listener.local_addr()?);
+        server.serve(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        path::PathBuf,
+        sync::Arc,
+        thread,
+    };
+
+    use serde_json::{json, Value};
+
+    use super::RpcServer;
+    use crate::subcommands::call::test_utils::{deploy_test_contract, TEST_ADDRESS};
+
+    #[test]
+    fn test_serve_call() {
+        let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../executor/test");
+        let (db_home, _) = deploy_test_contract("serve", "books");
+
+        let server = Arc::new(RpcServer::open(db_home.as_path()).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local_addr = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve(listener));
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "call",
+            "params": {
+                "abi": test_dir.join("contracts-abi/books_abi.json"),
+                "to": TEST_ADDRESS,
+                "function": "createBook",
+                "args": ["7", "ola"],
+            },
+        })
+        .to_string();
+        let mut stream = TcpStream::connect(local_addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+
+        let response: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(response["id"], 1);
+        let result = response["result"][0].as_str().unwrap();
+        assert!(result.contains("book_id: 7"));
+        assert!(result.contains("book_name: ola"));
+        let _ = std::fs::remove_dir_all(db_home);
+    }
+}
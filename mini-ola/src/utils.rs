@@ -136,3 +136,35 @@ pub fn address_from_hex_be(value: &str) -> anyhow::Result<[u8; 32]> {
     };
     Ok(parsed_bytes)
 }
+
+pub fn address_to_u64_array(value: &str) -> anyhow::Result<[u64; 4]> {
+    let bytes = address_from_hex_be(value)?;
+    let addr_vec = bytes_to_u64s(bytes.to_vec());
+    let mut addr = [0u64; 4];
+    addr.clone_from_slice(&addr_vec[..4]);
+    Ok(addr)
+}
+
+/// Parses a hex address given on the command line like
+/// `address_to_u64_array`, with errors naming the address, e.g. "caller".
+pub fn parse_address(kind: &str, value: &str) -> anyhow::Result<[u64; 4]> {
+    parse_hex_word(format!("{} address", kind).as_str(), value)
+}
+
+/// Parses a 32-byte hex value given on the command line, such as a
+/// signature part or a tx hash, into four u64s like an address. Errors name
+/// the value, e.g. "tx hash".
+pub fn parse_hex_word(name: &str, value: &str) -> anyhow::Result<[u64; 4]> {
+    let hex = value.trim_start_matches("0x");
+    if let Some(c) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        anyhow::bail!("invalid {}: '{}' is not hex, found '{}'", name, value, c);
+    }
+    if hex.is_empty() || hex.len() > 64 {
+        anyhow::bail!(
+            "invalid {}: expected 32-byte hex, got {} bytes",
+            name,
+            (hex.len() + 1) / 2
+        );
+    }
+    address_to_u64_array(value)
+}
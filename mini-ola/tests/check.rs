@@ -0,0 +1,35 @@
+use std::{path::PathBuf, process::Command};
+
+fn write_source(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mini_ola_{}_{}.ola", name, std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn test_quiet_check() {
+    let valid = write_source("check_valid", "entry() { felt x; x = 1; }");
+    let output = Command::new(env!("CARGO_BIN_EXE_mini-ola"))
+        .args(["check", "--quiet"])
+        .arg(&valid)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let invalid = write_source("check_invalid", "entry() { x = 1; }");
+    let output = Command::new(env!("CARGO_BIN_EXE_mini-ola"))
+        .args(["check", "--quiet"])
+        .arg(&invalid)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error[E0001]"));
+    assert!(stderr.contains("Undeclared variable x"));
+
+    let _ = std::fs::remove_file(valid);
+    let _ = std::fs::remove_file(invalid);
+}
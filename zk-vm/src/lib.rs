@@ -5,7 +5,7 @@ use executor::{BatchCacheManager, Process};
 use log::debug;
 use ola_core::crypto::ZkHasher;
 use ola_core::merkle_tree::tree::AccountTree;
-use ola_core::program::binary_program::BinaryProgram;
+use ola_core::program::binary_program::{BinaryProgram, ProphetMock};
 use ola_core::program::Program;
 use ola_core::state::contracts::Contracts;
 use ola_core::state::error::StateError;
@@ -20,11 +20,13 @@ use ola_core::types::merkle_tree::{
 use ola_core::types::GoldilocksField;
 use ola_core::types::{Field, PrimeField64};
 use ola_core::vm::error::ProcessorError;
+use ola_core::vm::memory::MemoryFootprint;
 use ola_core::vm::transaction::TxCtxInfo;
+use ola_core::vm::types::Event;
 use ola_core::vm::vm_state::{SCCallType, VMState};
 
 use ola_core::merkle_tree::log::{StorageLog, StorageLogKind, WitnessStorageLog};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -39,6 +41,39 @@ pub use vm_manager::*;
 #[cfg(test)]
 pub mod test;
 
+/// A tx stopped at a breakpoint, with the state of the contract it stopped
+/// in. Hand it back to `OlaVM::resume_tx` to carry on.
+#[derive(Debug)]
+pub struct PausedTx {
+    pub process: Process,
+    pub program: Program,
+    pub caller_addr: Address,
+    pub code_exe_addr: Address,
+    env_idx: u64,
+    sc_cnt: u64,
+}
+
+impl PausedTx {
+    pub fn pc(&self) -> u64 {
+        self.process.pc
+    }
+
+    pub fn registers(&self) -> &[GoldilocksField] {
+        &self.process.registers
+    }
+
+    /// Current value of memory at `addr` in the paused contract.
+    pub fn memory(&self, addr: u64) -> Option<GoldilocksField> {
+        self.process.memory.peek(addr)
+    }
+}
+
+#[derive(Debug)]
+pub enum TxStatus {
+    Paused(PausedTx),
+    Finished,
+}
+
 #[derive(Debug)]
 pub struct OlaVM {
     pub ola_state: NodeState<ZkHasher>,
@@ -47,6 +82,16 @@ pub struct OlaVM {
     pub process_ctx: Vec<(Process, Program, Address, Address)>,
     pub ctx_info: TxCtxInfo,
     pub is_call: bool,
+    /// Prophet values used by every tx instead of evaluating the prophets.
+    pub prophet_mock: Option<ProphetMock>,
+    /// Memory used by the contracts of the last executed tx.
+    pub memory_footprint: MemoryFootprint,
+    /// Deepest contract call nesting of the last executed tx, 1 when it
+    /// called no other contract.
+    pub max_call_depth: usize,
+    /// Events emitted by the last executed tx, in the order the contracts
+    /// logged them.
+    pub events: Vec<Event>,
 }
 
 impl OlaVM {
@@ -69,6 +114,10 @@ impl OlaVM {
             process_ctx: Vec::new(),
             ctx_info,
             is_call: false,
+            prophet_mock: None,
+            memory_footprint: MemoryFootprint::default(),
+            max_call_depth: 0,
+            events: Vec::new(),
         }
     }
 
@@ -91,6 +140,10 @@ impl OlaVM {
             process_ctx: Vec::new(),
             ctx_info,
             is_call: false,
+            prophet_mock: None,
+            memory_footprint: MemoryFootprint::default(),
+            max_call_depth: 0,
+            events: Vec::new(),
         }
     }
 
@@ -112,6 +165,10 @@ impl OlaVM {
             process_ctx: Vec::new(),
             ctx_info,
             is_call: true,
+            prophet_mock: None,
+            memory_footprint: MemoryFootprint::default(),
+            max_call_depth: 0,
+            events: Vec::new(),
         }
     }
 
@@ -318,16 +375,38 @@ impl OlaVM {
         cache_manager: &mut BatchCacheManager,
         is_preexecute: bool,
     ) -> Result<(), StateError> {
-        let mut env_idx = 0;
-        let mut sc_cnt = 0;
+        match self.execute_tx_with_breakpoints(
+            caller_addr,
+            code_exe_addr,
+            calldata,
+            cache_manager,
+            is_preexecute,
+            BTreeSet::new(),
+        )? {
+            TxStatus::Finished => Ok(()),
+            TxStatus::Paused(_) => unreachable!("no breakpoints were set"),
+        }
+    }
+
+    /// Executes a tx like `execute_tx`, pausing before any instruction whose
+    /// pc is in `breakpoints`. The pcs apply to every contract the tx calls.
+    pub fn execute_tx_with_breakpoints(
+        &mut self,
+        caller_addr: TreeValue,
+        code_exe_addr: TreeValue,
+        calldata: Vec<GoldilocksField>,
+        cache_manager: &mut BatchCacheManager,
+        is_preexecute: bool,
+        breakpoints: BTreeSet<u64>,
+    ) -> Result<TxStatus, StateError> {
         let mut process = if self.is_call {
             Process::new_call()
         } else {
             Process::new()
         };
         process.block_timestamp = self.ctx_info.block_timestamp.0;
-        process.env_idx = GoldilocksField::from_canonical_u64(env_idx);
-        process.call_sc_cnt = GoldilocksField::from_canonical_u64(sc_cnt);
+        process.env_idx = GoldilocksField::ZERO;
+        process.call_sc_cnt = GoldilocksField::ZERO;
         process.addr_storage = caller_addr;
         process.addr_code = code_exe_addr;
         init_tape(
@@ -338,10 +417,13 @@ impl OlaVM {
             code_exe_addr,
             &self.ctx_info,
         );
+        self.memory_footprint = MemoryFootprint::default();
+        self.max_call_depth = 1;
+        self.events.clear();
         let mut program = Program::default();
         program.pre_exe_flag = is_preexecute;
-        let mut caller_addr = caller_addr;
-        let mut code_exe_addr = code_exe_addr;
+        program.breakpoints = breakpoints;
+        program.prophet_mock = self.prophet_mock.clone();
         let res = self.contract_run(
             &mut process,
             &mut program,
@@ -350,11 +432,55 @@ impl OlaVM {
             true,
             cache_manager,
         );
-        let mut res = res.map_err(|err| {
+        let res = res.map_err(|err| {
             self.process_ctx
                 .push((process.clone(), program.clone(), caller_addr, code_exe_addr));
             err
         })?;
+        let tx = PausedTx {
+            process,
+            program,
+            caller_addr,
+            code_exe_addr,
+            env_idx: 0,
+            sc_cnt: 0,
+        };
+        self.run_tx(tx, res, cache_manager)
+    }
+
+    /// Continues a tx from the breakpoint it paused at.
+    pub fn resume_tx(
+        &mut self,
+        mut tx: PausedTx,
+        cache_manager: &mut BatchCacheManager,
+    ) -> Result<TxStatus, StateError> {
+        let res = self.contract_run(
+            &mut tx.process,
+            &mut tx.program,
+            tx.caller_addr,
+            tx.code_exe_addr,
+            false,
+            cache_manager,
+        )?;
+        self.run_tx(tx, res, cache_manager)
+    }
+
+    fn run_tx(
+        &mut self,
+        tx: PausedTx,
+        res: VMState,
+        cache_manager: &mut BatchCacheManager,
+    ) -> Result<TxStatus, StateError> {
+        let PausedTx {
+            mut process,
+            mut program,
+            mut caller_addr,
+            mut code_exe_addr,
+            mut env_idx,
+            mut sc_cnt,
+        } = tx;
+        let is_preexecute = program.pre_exe_flag;
+        let mut res = res;
         loop {
             match res {
                 VMState::SCCall(ref ret) => {
@@ -362,10 +488,12 @@ impl OlaVM {
                     let tape_tree = process.tape.clone();
                     let tp = process.tp.clone();
                     let return_data = process.return_data.clone();
+                    self.collect_events(&mut process);
                     self.process_ctx
                         .push((process, program.clone(), caller_addr, code_exe_addr));
                     env_idx += 1;
                     sc_cnt += 1;
+                    self.max_call_depth = self.max_call_depth.max(self.process_ctx.len() + 1);
 
                     process = Process::new();
                     process.tape = tape_tree;
@@ -374,8 +502,11 @@ impl OlaVM {
                     process.call_sc_cnt = GoldilocksField::from_canonical_u64(sc_cnt);
                     process.return_data = return_data;
 
+                    let breakpoints = program.breakpoints.clone();
                     program = Program::default();
                     program.pre_exe_flag = is_preexecute;
+                    program.breakpoints = breakpoints;
+                    program.prophet_mock = self.prophet_mock.clone();
 
                     match ret {
                         SCCallType::Call(addr) => {
@@ -398,6 +529,20 @@ impl OlaVM {
                         cache_manager,
                     )?;
                 }
+                VMState::Breakpoint(pc) => {
+                    debug!(
+                        "breakpoint at pc {} in contract:{:?}",
+                        pc, process.addr_code
+                    );
+                    return Ok(TxStatus::Paused(PausedTx {
+                        process,
+                        program,
+                        caller_addr,
+                        code_exe_addr,
+                        env_idx,
+                        sc_cnt,
+                    }));
+                }
                 VMState::ExeEnd(step) => {
                     debug!("end contract:{:?}", process.addr_code);
                     if self.process_ctx.is_empty() {
@@ -411,6 +556,8 @@ impl OlaVM {
                         // let _ = gen_storage_table(&mut process, &mut program, hash_roots)
                         //     .map_err(StateError::GenStorageTableError)?;
                         let trace = std::mem::replace(&mut program.trace, Trace::default());
+                        self.memory_footprint.merge(&process.memory.footprint());
+                        self.collect_events(&mut process);
                         self.ola_state
                             .txs_trace
                             .insert(process.env_idx.to_canonical_u64(), trace);
@@ -430,6 +577,8 @@ impl OlaVM {
                             .pop()
                             .ok_or(StateError::ProcessContextEmpty)?;
                         let env_id = process.env_idx.to_canonical_u64();
+                        self.memory_footprint.merge(&process.memory.footprint());
+                        self.collect_events(&mut process);
                         let program_log = std::mem::replace(&mut process.program_log, Vec::new());
                         let witness_log = std::mem::replace(&mut process.storage_log, Vec::new());
                         let mut storage_queries =
@@ -480,7 +629,47 @@ impl OlaVM {
                 }
             }
         }
-        Ok(())
+        Ok(TxStatus::Finished)
+    }
+
+    /// Moves the events `process` logged so far to the tx events, numbering
+    /// them in emission order.
+    fn collect_events(&mut self, process: &mut Process) {
+        for mut event in process.events.drain(..) {
+            event.index_in_batch = self.events.len() as u64;
+            self.events.push(event);
+        }
+    }
+
+    /// Takes the witness of the last executed tx, the traces of every
+    /// contract it called merged into the single trace the prover consumes.
+    pub fn take_witness(&mut self) -> Result<Trace, StateError> {
+        if !self.ola_state.txs_trace.contains_key(&0) {
+            return Err(StateError::EmptyArrayError(
+                "no tx has been executed".to_string(),
+            ));
+        }
+        Ok(self.ola_state.gen_tx_trace())
+    }
+
+    /// Instructions executed by the last tx, across every contract it
+    /// called. Extension rows of multi-row instructions are not counted.
+    pub fn executed_steps(&self) -> u64 {
+        self.ola_state
+            .txs_trace
+            .values()
+            .flat_map(|trace| trace.exec.iter())
+            .filter(|step| step.is_ext_line.is_zero())
+            .count() as u64
+    }
+
+    /// Rows of the execution traces of the last tx, extension rows included.
+    pub fn trace_length(&self) -> usize {
+        self.ola_state
+            .txs_trace
+            .values()
+            .map(|trace| trace.exec.len())
+            .sum()
     }
 
     pub fn finish_batch(&mut self, block_number: u32) -> Result<(), StateError> {